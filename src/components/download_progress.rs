@@ -25,6 +25,7 @@ pub struct DownloadInfo {
 pub enum DownloadStatus {
     NotStarted,
     Downloading,
+    Paused,
     Completed,
     Failed(String),
 }
@@ -40,6 +41,9 @@ impl Default for DownloadStatus {
 pub fn DownloadProgress(
     download_info: Signal<DownloadInfo>,
     on_download_click: EventHandler<()>,
+    on_pause: Option<EventHandler<()>>,
+    on_resume: Option<EventHandler<()>>,
+    on_cancel: Option<EventHandler<()>>,
 ) -> Element {
     // Render platform-specific progress UI
     #[cfg(feature = "web")]
@@ -81,6 +85,45 @@ pub fn DownloadProgress(
                                 span { "{download_info().speed}" }
                                 span { "ETA: {download_info().eta}" }
                             }
+                            div { class: "flex justify-end gap-2 mt-3",
+                                if let Some(on_pause) = on_pause {
+                                    button {
+                                        class: "bg-background-medium hover:bg-background-hover text-text-primary py-1 px-3 rounded-lg text-sm transition-colors",
+                                        onclick: move |_| on_pause.call(()),
+                                        "Pause"
+                                    }
+                                }
+                                if let Some(on_cancel) = on_cancel {
+                                    button {
+                                        class: "bg-background-medium hover:bg-background-hover text-text-primary py-1 px-3 rounded-lg text-sm transition-colors",
+                                        onclick: move |_| on_cancel.call(()),
+                                        "Cancel"
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    DownloadStatus::Paused => rsx! {
+                        div { class: "flex flex-col",
+                            div { class: "flex justify-between mb-2",
+                                span { class: "text-sm font-medium text-accent-amber", "Paused" }
+                                span { class: "text-xs text-text-secondary",
+                                    "{download_info().downloaded_size} of {download_info().total_size} ({(download_info().progress * 100.0) as i32}%)"
+                                }
+                            }
+                            div { class: "w-full bg-background-dark rounded-full h-2.5",
+                                div {
+                                    class: "bg-accent-amber h-2.5 rounded-full",
+                                    style: "width: {(download_info().progress * 100.0) as i32}%",
+                                }
+                            }
+                            if let Some(on_resume) = on_resume {
+                                button {
+                                    class: "mt-3 self-end bg-accent-teal hover:bg-opacity-80 text-text-invert py-1 px-3 rounded-lg text-sm transition-colors",
+                                    onclick: move |_| on_resume.call(()),
+                                    "Resume"
+                                }
+                            }
                         }
                     },
                     DownloadStatus::Completed => rsx! {
@@ -169,6 +212,45 @@ pub fn DownloadProgress(
                                 span { "{download_info().speed}" }
                                 span { "ETA: {download_info().eta}" }
                             }
+                            div { class: "flex justify-end gap-2 mt-3",
+                                if let Some(on_pause) = on_pause {
+                                    button {
+                                        class: "bg-background-medium hover:bg-background-hover text-text-primary py-1 px-3 rounded-lg text-sm transition-colors",
+                                        onclick: move |_| on_pause.call(()),
+                                        "Pause"
+                                    }
+                                }
+                                if let Some(on_cancel) = on_cancel {
+                                    button {
+                                        class: "bg-background-medium hover:bg-background-hover text-text-primary py-1 px-3 rounded-lg text-sm transition-colors",
+                                        onclick: move |_| on_cancel.call(()),
+                                        "Cancel"
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    DownloadStatus::Paused => rsx! {
+                        div { class: "flex flex-col",
+                            div { class: "flex justify-between mb-2",
+                                span { class: "text-sm font-medium text-accent-amber", "Paused" }
+                                span { class: "text-xs text-text-secondary",
+                                    "{download_info().downloaded_size} of {download_info().total_size} ({(download_info().progress * 100.0) as i32}%)"
+                                }
+                            }
+                            div { class: "w-full bg-background-dark rounded-full h-2.5",
+                                div {
+                                    class: "bg-accent-amber h-2.5 rounded-full",
+                                    style: "width: {(download_info().progress * 100.0) as i32}%",
+                                }
+                            }
+                            if let Some(on_resume) = on_resume {
+                                button {
+                                    class: "mt-3 self-end bg-accent-teal hover:bg-opacity-80 text-text-invert py-1 px-3 rounded-lg text-sm transition-colors",
+                                    onclick: move |_| on_resume.call(()),
+                                    "Resume"
+                                }
+                            }
                         }
                     },
                     DownloadStatus::Completed => rsx! {
@@ -2,8 +2,8 @@ use crate::Route;
 use dioxus::prelude::*;
 use dioxus_free_icons::{
     icons::{
-        bs_icons::{BsHouseDoorFill, BsInfoCircleFill, BsNewspaper, BsSearch},
-        fa_solid_icons::{FaDownload, FaMusic, FaVideo},
+        bs_icons::{BsGearFill, BsHouseDoorFill, BsInfoCircleFill, BsNewspaper, BsSearch},
+        fa_solid_icons::{FaDownload, FaListOl, FaListUl, FaMusic, FaRss, FaVideo},
     },
     Icon,
 };
@@ -24,6 +24,10 @@ pub fn Navbar() -> Element {
     #[cfg(feature = "desktop")]
     let is_downloads = matches!(route, Route::Downloads {});
     let is_search = matches!(route, Route::Search {});
+    let is_queue = matches!(route, Route::Queue {});
+    let is_playlist = matches!(route, Route::Playlist {});
+    let is_subscriptions = matches!(route, Route::Subscriptions {});
+    let is_settings = matches!(route, Route::Settings {});
 
     rsx! {
         document::Link { rel: "stylesheet", href: NAVBAR_CSS }
@@ -110,6 +114,51 @@ pub fn Navbar() -> Element {
                             span { "Search" }
                         }
                     }
+                    // Queue link
+                    div {
+                        class: "flex items-center py-3 px-3 mb-2 rounded-lg transition-all duration-200",
+                        class: if !show_labels() { "justify-center" } else { "" },
+                        class: if is_queue { "bg-primary-600 text-text-primary shadow-glow" } else { "text-text-muted hover:bg-background-hover hover:text-text-primary" },
+                        onclick: move |_| {
+                            nav.replace(Route::Queue {});
+                        },
+                        div { class: if show_labels() { "mr-3" } else { "" },
+                            Icon { icon: FaListUl, width: 20, height: 20 }
+                        }
+                        if show_labels() {
+                            span { "Queue" }
+                        }
+                    }
+                    // Playlist link
+                    div {
+                        class: "flex items-center py-3 px-3 mb-2 rounded-lg transition-all duration-200",
+                        class: if !show_labels() { "justify-center" } else { "" },
+                        class: if is_playlist { "bg-primary-600 text-text-primary shadow-glow" } else { "text-text-muted hover:bg-background-hover hover:text-text-primary" },
+                        onclick: move |_| {
+                            nav.replace(Route::Playlist {});
+                        },
+                        div { class: if show_labels() { "mr-3" } else { "" },
+                            Icon { icon: FaListOl, width: 20, height: 20 }
+                        }
+                        if show_labels() {
+                            span { "Playlist" }
+                        }
+                    }
+                    // Subscriptions link
+                    div {
+                        class: "flex items-center py-3 px-3 mb-2 rounded-lg transition-all duration-200",
+                        class: if !show_labels() { "justify-center" } else { "" },
+                        class: if is_subscriptions { "bg-primary-600 text-text-primary shadow-glow" } else { "text-text-muted hover:bg-background-hover hover:text-text-primary" },
+                        onclick: move |_| {
+                            nav.replace(Route::Subscriptions {});
+                        },
+                        div { class: if show_labels() { "mr-3" } else { "" },
+                            Icon { icon: FaRss, width: 20, height: 20 }
+                        }
+                        if show_labels() {
+                            span { "Subscriptions" }
+                        }
+                    }
                     // My Downloads link
                     {
                         #[cfg(feature = "desktop")]
@@ -130,6 +179,21 @@ pub fn Navbar() -> Element {
                             }
                         }
                     }
+                    // Settings link
+                    div {
+                        class: "flex items-center py-3 px-3 mb-2 rounded-lg transition-all duration-200",
+                        class: if !show_labels() { "justify-center" } else { "" },
+                        class: if is_settings { "bg-primary-600 text-text-primary shadow-glow" } else { "text-text-muted hover:bg-background-hover hover:text-text-primary" },
+                        onclick: move |_| {
+                            nav.replace(Route::Settings {});
+                        },
+                        div { class: if show_labels() { "mr-3" } else { "" },
+                            Icon { icon: BsGearFill, width: 20, height: 20 }
+                        }
+                        if show_labels() {
+                            span { "Settings" }
+                        }
+                    }
                 }
             }
             // Main content area
@@ -15,7 +15,10 @@ pub use schema::*;
 pub use web_models::*;
 
 #[cfg(feature = "server")]
-use sqlx::{sqlite::SqlitePool, Executor, Pool, Sqlite};
+use sqlx::{
+    sqlite::{SqlitePool, SqlitePoolOptions},
+    Executor, Pool, Sqlite,
+};
 #[cfg(feature = "server")]
 use std::path::PathBuf;
 #[cfg(feature = "server")]
@@ -43,7 +46,7 @@ pub async fn init_database() -> Result<Pool<Sqlite>, sqlx::Error> {
             println!(
                 "WARNING: Using in-memory database - history will not persist between sessions"
             );
-            return get_memory_database().await;
+            return init_memory_database().await;
         }
 
         println!("Using database at: {}", db_path.display());
@@ -54,7 +57,7 @@ pub async fn init_database() -> Result<Pool<Sqlite>, sqlx::Error> {
                 // Run migrations
                 if let Err(e) = run_migrations(&pool).await {
                     println!("Migration error: {}", e);
-                    return get_memory_database().await;
+                    return init_memory_database().await;
                 }
 
                 // Store in global static
@@ -67,7 +70,7 @@ pub async fn init_database() -> Result<Pool<Sqlite>, sqlx::Error> {
                     e,
                     db_path.display()
                 );
-                return get_memory_database().await;
+                return init_memory_database().await;
             }
         }
     }
@@ -75,10 +78,20 @@ pub async fn init_database() -> Result<Pool<Sqlite>, sqlx::Error> {
     // For non-desktop builds, just use in-memory database
     #[cfg(not(feature = "desktop"))]
     {
-        return get_memory_database().await;
+        return init_memory_database().await;
     }
 }
 
+/// Open an in-memory database and keep it as the global pool
+///
+/// Each in-memory pool is a separate, empty database, so the queue, settings
+/// and jobs only survive between calls when every caller shares this one.
+#[cfg(feature = "server")]
+async fn init_memory_database() -> Result<Pool<Sqlite>, sqlx::Error> {
+    let pool = get_memory_database().await?;
+    Ok(DB_POOL.get_or_init(|| pool).clone())
+}
+
 /// Get a connection to the database
 #[cfg(feature = "server")]
 pub async fn get_database() -> Result<Pool<Sqlite>, sqlx::Error> {
@@ -109,10 +122,118 @@ async fn run_migrations(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
             video_id TEXT,
             duration INTEGER
         );
+
+        CREATE TABLE IF NOT EXISTS download_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            title TEXT,
+            format_type TEXT NOT NULL,
+            quality TEXT NOT NULL,
+            options TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'queued',
+            error TEXT,
+            download_id INTEGER REFERENCES downloads(id) ON DELETE SET NULL,
+            workspace TEXT,
+            created_at INTEGER,
+            started_at INTEGER,
+            finished_at INTEGER,
+            starts_at INTEGER
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_download_jobs_status ON download_jobs(status);
+
+        CREATE TABLE IF NOT EXISTS playlists (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            title TEXT NOT NULL,
+            uploader TEXT,
+            entry_count INTEGER NOT NULL,
+            created_at INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS subscriptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL UNIQUE,
+            title TEXT NOT NULL,
+            format_type TEXT NOT NULL,
+            quality TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            last_checked_at INTEGER,
+            last_error TEXT,
+            created_at INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS subscription_videos (
+            subscription_id INTEGER NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+            video_id TEXT NOT NULL,
+            seen_at INTEGER,
+            PRIMARY KEY (subscription_id, video_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS subtitles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            download_id INTEGER NOT NULL REFERENCES downloads(id) ON DELETE CASCADE,
+            language TEXT NOT NULL,
+            format TEXT NOT NULL,
+            auto_generated INTEGER NOT NULL DEFAULT 0,
+            file_path TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_subtitles_download_id ON subtitles(download_id);
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
         "#,
     )
     .await?;
 
+    // Columns added after the table was first released
+    ensure_column(pool, "download_jobs", "workspace", "TEXT").await?;
+    ensure_column(pool, "download_jobs", "starts_at", "INTEGER").await?;
+    ensure_column(
+        pool,
+        "downloads",
+        "playlist_id",
+        "INTEGER REFERENCES playlists(id) ON DELETE SET NULL",
+    )
+    .await?;
+    ensure_column(pool, "downloads", "playlist_index", "INTEGER").await?;
+    ensure_column(pool, "downloads", "extractor", "TEXT").await?;
+    ensure_column(pool, "downloads", "format_id", "TEXT").await?;
+    ensure_column(pool, "downloads", "chapters", "TEXT").await?;
+    ensure_column(pool, "downloads", "uploader", "TEXT").await?;
+    ensure_column(pool, "downloads", "upload_date", "TEXT").await?;
+    ensure_column(pool, "downloads", "description", "TEXT").await?;
+    ensure_column(pool, "downloads", "clips", "TEXT").await?;
+
+    Ok(())
+}
+
+/// Add a column to a table created by an older version of the app
+#[cfg(feature = "server")]
+async fn ensure_column(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    let count: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?",
+        table
+    ))
+    .bind(column)
+    .fetch_one(pool)
+    .await?;
+
+    if count == 0 {
+        pool.execute(
+            format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition).as_str(),
+        )
+        .await?;
+    }
+
     Ok(())
 }
 
@@ -199,7 +320,13 @@ fn get_desktop_database_path() -> PathBuf {
 #[cfg(feature = "server")]
 pub async fn get_memory_database() -> Result<Pool<Sqlite>, sqlx::Error> {
     println!("Creating in-memory SQLite database");
-    let pool = SqlitePool::connect("sqlite::memory:").await?;
+    // Every connection to `sqlite::memory:` opens a separate database, so keep a single one
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await?;
 
     // Run migrations
     if let Err(e) = run_migrations(&pool).await {
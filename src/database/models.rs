@@ -2,12 +2,21 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "server")]
 use time::OffsetDateTime;
 
+#[cfg(feature = "server")]
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::{Chapter, ClipRange};
+#[cfg(feature = "server")]
+use crate::server::download::types::{
+    DownloadOptions, JobState, QueueEntry, SubscriptionEntry, SubtitleFile,
+};
+use crate::server::download::youtube_url::youtube_video_id;
+
 /// Represents a downloaded video in the database
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Download {
     /// Unique identifier
     pub id: Option<i64>,
-    /// Original YouTube URL
+    /// URL the video was downloaded from
     pub url: String,
     /// Video title
     pub title: Option<String>,
@@ -19,6 +28,9 @@ pub struct Download {
     pub format_type: String,
     /// Quality setting used
     pub quality: String,
+    /// yt-dlp format that was downloaded, when picked from the format list
+    #[serde(default)]
+    pub format_id: Option<String>,
     /// File size in bytes
     pub file_size: Option<i64>,
     /// When the file was downloaded
@@ -29,10 +41,37 @@ pub struct Download {
     pub download_date: Option<String>,
     /// URL to video thumbnail
     pub thumbnail_url: Option<String>,
-    /// YouTube video ID
+    /// yt-dlp extractor that handled the site, e.g. "youtube" or "vimeo"
+    #[serde(default)]
+    pub extractor: Option<String>,
+    /// ID the extractor gives the video, e.g. the YouTube video ID
     pub video_id: Option<String>,
     /// Duration in seconds
     pub duration: Option<i64>,
+    /// The playlist the video was downloaded from
+    #[serde(default)]
+    pub playlist_id: Option<i64>,
+    /// Title of that playlist, read from the `playlists` table
+    #[serde(default)]
+    pub playlist_title: Option<String>,
+    /// 1-based position in that playlist
+    #[serde(default)]
+    pub playlist_index: Option<i64>,
+    /// Chapters of the video, stored as JSON
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    /// Channel or user that uploaded the video
+    #[serde(default)]
+    pub uploader: Option<String>,
+    /// Upload date as yt-dlp reports it, e.g. "20240131"
+    #[serde(default)]
+    pub upload_date: Option<String>,
+    /// Video description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Parts of the video the file holds, empty for the whole video
+    #[serde(default)]
+    pub clips: Vec<ClipRange>,
 }
 
 impl Download {
@@ -58,11 +97,21 @@ impl Download {
             file_path,
             format_type,
             quality,
+            format_id: None,
             file_size,
             download_date: Some(OffsetDateTime::now_utc()),
             thumbnail_url,
+            extractor: None,
             video_id,
             duration,
+            playlist_id: None,
+            playlist_title: None,
+            playlist_index: None,
+            chapters: Vec::new(),
+            uploader: None,
+            upload_date: None,
+            description: None,
+            clips: Vec::new(),
         }
     }
 
@@ -87,46 +136,27 @@ impl Download {
             file_path,
             format_type,
             quality,
+            format_id: None,
             file_size,
             download_date: Some(chrono::Utc::now().to_rfc3339()),
             thumbnail_url,
+            extractor: None,
             video_id,
             duration,
+            playlist_id: None,
+            playlist_title: None,
+            playlist_index: None,
+            chapters: Vec::new(),
+            uploader: None,
+            upload_date: None,
+            description: None,
+            clips: Vec::new(),
         }
     }
 
-    /// Extract the video ID from a YouTube URL
+    /// Extract the video ID from any form of YouTube URL
     pub fn extract_video_id(url: &str) -> Option<String> {
-        let parsed_url = url::Url::parse(url).ok()?;
-
-        // Handle youtube.com/watch?v=VIDEO_ID
-        if parsed_url.host_str() == Some("www.youtube.com")
-            || parsed_url.host_str() == Some("youtube.com")
-        {
-            if parsed_url.path() == "/watch" {
-                let pairs = parsed_url.query_pairs();
-                for (key, value) in pairs {
-                    if key == "v" {
-                        return Some(value.to_string());
-                    }
-                }
-            }
-        }
-
-        // Handle youtu.be/VIDEO_ID
-        if parsed_url.host_str() == Some("youtu.be") {
-            let path = parsed_url.path();
-            if path.len() > 1 {
-                return Some(path[1..].to_string());
-            }
-        }
-
-        None
-    }
-
-    /// Generate a thumbnail URL from a video ID
-    pub fn generate_thumbnail_url(video_id: &str) -> String {
-        format!("https://i.ytimg.com/vi/{}/mqdefault.jpg", video_id)
+        youtube_video_id(url)
     }
 
     /// Format the duration as a human-readable string
@@ -193,3 +223,259 @@ impl Download {
         std::path::Path::new(&self.file_path).exists()
     }
 }
+
+/// Format a timestamp as "01 Jan 14:30" for the queue and subscription lists
+#[cfg(feature = "server")]
+fn format_short_datetime(date: OffsetDateTime) -> Option<String> {
+    time::format_description::parse("[day] [month repr:short] [hour]:[minute]")
+        .ok()
+        .and_then(|fmt| date.format(&fmt).ok())
+}
+
+/// Subtitles saved with a download
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Subtitle {
+    /// Unique identifier
+    pub id: Option<i64>,
+    /// The `downloads` record the subtitles belong to
+    pub download_id: i64,
+    /// Language code, e.g. "en"
+    pub language: String,
+    /// File format (srt or vtt)
+    pub format: String,
+    /// Whether the subtitles are auto-generated captions
+    pub auto_generated: bool,
+    /// Sidecar file, `None` when the subtitles are embedded in the media file
+    pub file_path: Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl Subtitle {
+    /// Create a new subtitle record
+    pub fn new(
+        download_id: i64,
+        language: String,
+        format: String,
+        auto_generated: bool,
+        file_path: Option<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            download_id,
+            language,
+            format,
+            auto_generated,
+            file_path,
+        }
+    }
+
+    /// Convert to the DTO shown with the download
+    pub fn to_file(&self) -> SubtitleFile {
+        SubtitleFile {
+            id: self.id.unwrap_or_default(),
+            language: self.language.clone(),
+            format: self.format.clone(),
+            auto_generated: self.auto_generated,
+            embedded: self.file_path.is_none(),
+        }
+    }
+}
+
+/// A playlist whose entries were queued as individual jobs
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Playlist {
+    /// Unique identifier
+    pub id: Option<i64>,
+    /// URL the playlist was resolved from
+    pub url: String,
+    /// Playlist title
+    pub title: String,
+    /// Channel or user that owns the playlist
+    pub uploader: Option<String>,
+    /// Number of entries when the playlist was resolved
+    pub entry_count: i64,
+    /// When the playlist was queued
+    #[serde(with = "time::serde::timestamp::option")]
+    pub created_at: Option<OffsetDateTime>,
+}
+
+#[cfg(feature = "server")]
+impl Playlist {
+    /// Create a new playlist record
+    pub fn new(url: String, title: String, uploader: Option<String>, entry_count: i64) -> Self {
+        Self {
+            id: None,
+            url,
+            title,
+            uploader,
+            entry_count,
+            created_at: Some(OffsetDateTime::now_utc()),
+        }
+    }
+}
+
+/// A channel whose new uploads are downloaded automatically
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Subscription {
+    /// Unique identifier
+    pub id: Option<i64>,
+    /// The channel's uploads page
+    pub url: String,
+    /// Channel name
+    pub title: String,
+    /// Format type new uploads are downloaded as (video or audio)
+    pub format_type: String,
+    /// Quality new uploads are downloaded in
+    pub quality: String,
+    /// Whether the channel is checked for new uploads
+    pub enabled: bool,
+    /// When the channel was last checked
+    #[serde(with = "time::serde::timestamp::option")]
+    pub last_checked_at: Option<OffsetDateTime>,
+    /// Error of the last check, if it failed
+    pub last_error: Option<String>,
+    /// When the subscription was added
+    #[serde(with = "time::serde::timestamp::option")]
+    pub created_at: Option<OffsetDateTime>,
+}
+
+#[cfg(feature = "server")]
+impl Subscription {
+    /// Create a new, enabled subscription
+    pub fn new(url: String, title: String, format_type: String, quality: String) -> Self {
+        Self {
+            id: None,
+            url,
+            title,
+            format_type,
+            quality,
+            enabled: true,
+            last_checked_at: None,
+            last_error: None,
+            created_at: Some(OffsetDateTime::now_utc()),
+        }
+    }
+
+    /// Options new uploads are queued with
+    pub fn download_options(&self) -> DownloadOptions {
+        DownloadOptions::new(&self.format_type, &self.quality)
+    }
+
+    /// Convert to the DTO used by the subscriptions view
+    pub fn to_entry(&self) -> SubscriptionEntry {
+        SubscriptionEntry {
+            id: self.id.unwrap_or_default(),
+            url: self.url.clone(),
+            title: self.title.clone(),
+            format_type: self.format_type.clone(),
+            quality: self.quality.clone(),
+            enabled: self.enabled,
+            last_checked: self
+                .last_checked_at
+                .and_then(format_short_datetime)
+                .unwrap_or_else(|| "Never".to_string()),
+            last_error: self
+                .last_error
+                .as_deref()
+                .map(|error| DownloaderError::from_stored(error).message()),
+        }
+    }
+}
+
+/// Represents a queued download job in the database
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DownloadJob {
+    /// Unique identifier
+    pub id: Option<i64>,
+    /// URL to download
+    pub url: String,
+    /// Video title, filled in once the job has fetched video info
+    pub title: Option<String>,
+    /// Format type (video or audio)
+    pub format_type: String,
+    /// Quality setting used
+    pub quality: String,
+    /// JSON encoded `DownloadOptions`
+    pub options: String,
+    /// Current lifecycle state
+    pub status: JobState,
+    /// Error message for failed jobs
+    pub error: Option<String>,
+    /// The `downloads` record created when the job completed
+    pub download_id: Option<i64>,
+    /// Directory holding the job's partial files between runs
+    pub workspace: Option<String>,
+    /// When the job was enqueued
+    #[serde(with = "time::serde::timestamp::option")]
+    pub created_at: Option<OffsetDateTime>,
+    /// When a worker picked the job up
+    #[serde(with = "time::serde::timestamp::option")]
+    pub started_at: Option<OffsetDateTime>,
+    /// When the job completed or failed
+    #[serde(with = "time::serde::timestamp::option")]
+    pub finished_at: Option<OffsetDateTime>,
+    /// When a waiting job goes back to the queue
+    #[serde(with = "time::serde::timestamp::option")]
+    pub starts_at: Option<OffsetDateTime>,
+}
+
+#[cfg(feature = "server")]
+impl DownloadJob {
+    /// Create a new queued job
+    pub fn new(url: String, options: &DownloadOptions) -> Self {
+        Self {
+            id: None,
+            url,
+            title: None,
+            format_type: options.format_type.clone(),
+            quality: options.quality.clone(),
+            options: serde_json::to_string(options).unwrap_or_default(),
+            status: JobState::Queued,
+            error: None,
+            download_id: None,
+            workspace: None,
+            created_at: Some(OffsetDateTime::now_utc()),
+            started_at: None,
+            finished_at: None,
+            starts_at: None,
+        }
+    }
+
+    /// Decode the stored options, falling back to the format/quality columns
+    pub fn download_options(&self) -> DownloadOptions {
+        serde_json::from_str(&self.options)
+            .unwrap_or_else(|_| DownloadOptions::new(&self.format_type, &self.quality))
+    }
+
+    /// Convert to the DTO used by the queue view
+    pub fn to_queue_entry(&self) -> QueueEntry {
+        let error = self.error.as_deref().map(DownloaderError::from_stored);
+        QueueEntry {
+            id: self.id.unwrap_or_default(),
+            url: self.url.clone(),
+            title: self.title.clone(),
+            format_type: self.format_type.clone(),
+            quality: self.quality.clone(),
+            state: self.status,
+            error: error.as_ref().map(|e| e.message()),
+            failure: match (self.status, &error) {
+                (JobState::Failed, Some(error)) => Some(error.failure_kind()),
+                _ => None,
+            },
+            playlist: self.download_options().playlist,
+            download_id: self.download_id,
+            created_at: self
+                .created_at
+                .and_then(format_short_datetime)
+                .unwrap_or_default(),
+            starts_at: match self.status {
+                JobState::Waiting => self.starts_at.and_then(format_short_datetime),
+                _ => None,
+            },
+        }
+    }
+}
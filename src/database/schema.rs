@@ -1,7 +1,10 @@
 #[cfg(feature = "server")]
-use crate::database::models::Download;
+use crate::database::models::{Download, DownloadJob, Playlist, Subscription, Subtitle};
+use crate::server::download::types::JobState;
+use sqlx::sqlite::SqliteRow;
 use sqlx::Row;
 use sqlx::{Pool, Sqlite};
+use std::collections::HashSet;
 use std::path::Path;
 
 /// Save a download record to the database
@@ -9,9 +12,10 @@ pub async fn save_download(pool: &Pool<Sqlite>, download: &Download) -> Result<i
     let query = sqlx::query(
         r#"
         INSERT INTO downloads (
-            url, title, filename, file_path, format_type, quality, file_size, 
-            download_date, thumbnail_url, video_id, duration
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            url, title, filename, file_path, format_type, quality, format_id, file_size, 
+            download_date, thumbnail_url, extractor, video_id, duration, playlist_id,
+            playlist_index, chapters, uploader, upload_date, description, clips
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         RETURNING id
         "#,
     )
@@ -21,11 +25,20 @@ pub async fn save_download(pool: &Pool<Sqlite>, download: &Download) -> Result<i
     .bind(&download.file_path)
     .bind(&download.format_type)
     .bind(&download.quality)
+    .bind(&download.format_id)
     .bind(download.file_size)
     .bind(download.download_date.map(|dt| dt.unix_timestamp()))
     .bind(&download.thumbnail_url)
+    .bind(&download.extractor)
     .bind(&download.video_id)
-    .bind(download.duration);
+    .bind(download.duration)
+    .bind(download.playlist_id)
+    .bind(download.playlist_index)
+    .bind(serde_json::to_string(&download.chapters).unwrap_or_default())
+    .bind(&download.uploader)
+    .bind(&download.upload_date)
+    .bind(&download.description)
+    .bind(serde_json::to_string(&download.clips).unwrap_or_default());
 
     let id = query.fetch_one(pool).await?.get(0);
     Ok(id)
@@ -33,40 +46,14 @@ pub async fn save_download(pool: &Pool<Sqlite>, download: &Download) -> Result<i
 
 /// Get all downloads from the database
 pub async fn get_all_downloads(pool: &Pool<Sqlite>) -> Result<Vec<Download>, sqlx::Error> {
-    let rows = sqlx::query(
-        r#"
-        SELECT
-            id, url, title, filename, file_path, format_type, quality, file_size,
-            download_date, thumbnail_url, video_id, duration
-        FROM downloads
-        ORDER BY download_date DESC
-        "#,
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM {} ORDER BY d.download_date DESC",
+        DOWNLOAD_COLUMNS, DOWNLOAD_TABLES
+    ))
     .fetch_all(pool)
     .await?;
 
-    let mut downloads = Vec::with_capacity(rows.len());
-    for row in rows {
-        let timestamp: Option<i64> = row.get("download_date");
-        let download = Download {
-            id: row.get("id"),
-            url: row.get("url"),
-            title: row.get("title"),
-            filename: row.get("filename"),
-            file_path: row.get("file_path"),
-            format_type: row.get("format_type"),
-            quality: row.get("quality"),
-            file_size: row.get("file_size"),
-            download_date: timestamp
-                .and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok()),
-            thumbnail_url: row.get("thumbnail_url"),
-            video_id: row.get("video_id"),
-            duration: row.get("duration"),
-        };
-        downloads.push(download);
-    }
-
-    Ok(downloads)
+    Ok(rows.iter().map(download_from_row).collect())
 }
 
 /// Get download by ID
@@ -74,41 +61,15 @@ pub async fn get_download_by_id(
     pool: &Pool<Sqlite>,
     id: i64,
 ) -> Result<Option<Download>, sqlx::Error> {
-    let row = sqlx::query(
-        r#"
-        SELECT
-            id, url, title, filename, file_path, format_type, quality, file_size,
-            download_date, thumbnail_url, video_id, duration
-        FROM downloads
-        WHERE id = ?
-        "#,
-    )
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM {} WHERE d.id = ?",
+        DOWNLOAD_COLUMNS, DOWNLOAD_TABLES
+    ))
     .bind(id)
     .fetch_optional(pool)
     .await?;
 
-    match row {
-        Some(row) => {
-            let timestamp: Option<i64> = row.get("download_date");
-            let download = Download {
-                id: row.get("id"),
-                url: row.get("url"),
-                title: row.get("title"),
-                filename: row.get("filename"),
-                file_path: row.get("file_path"),
-                format_type: row.get("format_type"),
-                quality: row.get("quality"),
-                file_size: row.get("file_size"),
-                download_date: timestamp
-                    .and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok()),
-                thumbnail_url: row.get("thumbnail_url"),
-                video_id: row.get("video_id"),
-                duration: row.get("duration"),
-            };
-            Ok(Some(download))
-        }
-        None => Ok(None),
-    }
+    Ok(row.as_ref().map(download_from_row))
 }
 
 /// Delete a download record from the database
@@ -124,19 +85,65 @@ pub async fn delete_download(pool: &Pool<Sqlite>, id: i64) -> Result<bool, sqlx:
             }
         }
 
-        // Delete the database record
-        let result = sqlx::query("DELETE FROM downloads WHERE id = ?")
-            .bind(id)
-            .execute(pool)
-            .await?;
+        // Delete the files the audio was split into by chapter
+        for path in download
+            .chapters
+            .iter()
+            .filter_map(|chapter| chapter.file_path.as_ref())
+        {
+            if let Err(e) = std::fs::remove_file(path) {
+                tracing::warn!("Failed to delete chapter file {}: {}", path, e);
+            }
+        }
+
+        // Subtitle files saved next to the media go with it
+        for subtitle in get_subtitles_for_download(pool, id).await? {
+            if let Some(path) = subtitle.file_path.filter(|path| Path::new(path).exists()) {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    tracing::warn!("Failed to delete subtitle file {}: {}", path, e);
+                }
+            }
+        }
 
-        Ok(result.rows_affected() > 0)
+        delete_download_record(pool, id).await
     } else {
         Ok(false)
     }
 }
 
-/// Search downloads by title or filename
+/// Delete a download record and its subtitle records, leaving the files alone
+pub async fn delete_download_record(pool: &Pool<Sqlite>, id: i64) -> Result<bool, sqlx::Error> {
+    // Foreign keys aren't enforced, so the cascade is done by hand
+    sqlx::query("DELETE FROM subtitles WHERE download_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    // Delete the database record
+    let result = sqlx::query("DELETE FROM downloads WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Store the size of a download's file after it was rewritten, e.g. by tagging
+pub async fn update_download_file_size(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    file_size: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE downloads SET file_size = ? WHERE id = ?")
+        .bind(file_size)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Search downloads by title, filename, URL or playlist title
 pub async fn search_downloads(
     pool: &Pool<Sqlite>,
     query: &str,
@@ -144,47 +151,26 @@ pub async fn search_downloads(
     // Add wildcards for SQL LIKE
     let search_term = format!("%{}%", query);
 
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
         r#"
-        SELECT
-            id, url, title, filename, file_path, format_type, quality, file_size,
-            download_date, thumbnail_url, video_id, duration
-        FROM downloads
+        SELECT {} FROM {}
         WHERE 
-            title LIKE ? OR 
-            filename LIKE ? OR
-            url LIKE ?
-        ORDER BY download_date DESC
+            d.title LIKE ? OR 
+            d.filename LIKE ? OR
+            d.url LIKE ? OR
+            p.title LIKE ?
+        ORDER BY d.download_date DESC
         "#,
-    )
+        DOWNLOAD_COLUMNS, DOWNLOAD_TABLES
+    ))
+    .bind(&search_term)
     .bind(&search_term)
     .bind(&search_term)
     .bind(&search_term)
     .fetch_all(pool)
     .await?;
 
-    let mut downloads = Vec::with_capacity(rows.len());
-    for row in rows {
-        let timestamp: Option<i64> = row.get("download_date");
-        let download = Download {
-            id: row.get("id"),
-            url: row.get("url"),
-            title: row.get("title"),
-            filename: row.get("filename"),
-            file_path: row.get("file_path"),
-            format_type: row.get("format_type"),
-            quality: row.get("quality"),
-            file_size: row.get("file_size"),
-            download_date: timestamp
-                .and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok()),
-            thumbnail_url: row.get("thumbnail_url"),
-            video_id: row.get("video_id"),
-            duration: row.get("duration"),
-        };
-        downloads.push(download);
-    }
-
-    Ok(downloads)
+    Ok(rows.iter().map(download_from_row).collect())
 }
 
 /// Get downloads filtered by format type (video or audio)
@@ -192,42 +178,78 @@ pub async fn get_downloads_by_format(
     pool: &Pool<Sqlite>,
     format_type: &str,
 ) -> Result<Vec<Download>, sqlx::Error> {
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM {} WHERE d.format_type = ? ORDER BY d.download_date DESC",
+        DOWNLOAD_COLUMNS, DOWNLOAD_TABLES
+    ))
+    .bind(format_type)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(download_from_row).collect())
+}
+
+/// Earlier downloads of a video in the given format and quality, newest first
+///
+/// The video is matched by its YouTube video ID when it has one, otherwise by
+/// the URL it was downloaded from. `format_id` is the yt-dlp format picked by
+/// hand, if any.
+pub async fn find_downloads_of_video(
+    pool: &Pool<Sqlite>,
+    video_id: Option<&str>,
+    url: &str,
+    format_type: &str,
+    quality: &str,
+    format_id: Option<&str>,
+) -> Result<Vec<Download>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
         r#"
-        SELECT
-            id, url, title, filename, file_path, format_type, quality, file_size,
-            download_date, thumbnail_url, video_id, duration
-        FROM downloads
-        WHERE format_type = ?
-        ORDER BY download_date DESC
+        SELECT {} FROM {}
+        WHERE d.format_type = ? AND d.quality = ? AND d.format_id IS ? AND (
+            (d.video_id = ? AND (d.extractor IS NULL OR d.extractor = 'youtube')) OR
+            d.url = ?
+        )
+        ORDER BY d.download_date DESC
         "#,
-    )
+        DOWNLOAD_COLUMNS, DOWNLOAD_TABLES
+    ))
     .bind(format_type)
+    .bind(quality)
+    .bind(format_id)
+    .bind(video_id)
+    .bind(url)
     .fetch_all(pool)
     .await?;
 
-    let mut downloads = Vec::with_capacity(rows.len());
-    for row in rows {
-        let timestamp: Option<i64> = row.get("download_date");
-        let download = Download {
-            id: row.get("id"),
-            url: row.get("url"),
-            title: row.get("title"),
-            filename: row.get("filename"),
-            file_path: row.get("file_path"),
-            format_type: row.get("format_type"),
-            quality: row.get("quality"),
-            file_size: row.get("file_size"),
-            download_date: timestamp
-                .and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok()),
-            thumbnail_url: row.get("thumbnail_url"),
-            video_id: row.get("video_id"),
-            duration: row.get("duration"),
-        };
-        downloads.push(download);
+    Ok(rows.iter().map(download_from_row).collect())
+}
+
+/// Video IDs and file paths of the YouTube downloads of the given videos
+pub async fn find_library_files(
+    pool: &Pool<Sqlite>,
+    video_ids: &[String],
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    if video_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        r#"
+        SELECT video_id, file_path FROM downloads
+        WHERE video_id IN ({}) AND (extractor IS NULL OR extractor = 'youtube')
+        "#,
+        vec!["?"; video_ids.len()].join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for video_id in video_ids {
+        query = query.bind(video_id);
     }
+    let rows = query.fetch_all(pool).await?;
 
-    Ok(downloads)
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("video_id"), row.get("file_path")))
+        .collect())
 }
 
 /// Update file_exists status for all downloads
@@ -251,3 +273,649 @@ pub async fn update_file_exists_status(pool: &Pool<Sqlite>) -> Result<Vec<i64>,
 
     Ok(deleted_ids)
 }
+
+/// Convert a unix timestamp column into an `OffsetDateTime`
+fn timestamp_column(row: &SqliteRow, column: &str) -> Option<time::OffsetDateTime> {
+    let timestamp: Option<i64> = row.get(column);
+    timestamp.and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok())
+}
+
+/// Build a `Download` from a row selected with `DOWNLOAD_COLUMNS`
+fn download_from_row(row: &SqliteRow) -> Download {
+    Download {
+        id: row.get("id"),
+        url: row.get("url"),
+        title: row.get("title"),
+        filename: row.get("filename"),
+        file_path: row.get("file_path"),
+        format_type: row.get("format_type"),
+        quality: row.get("quality"),
+        format_id: row.get("format_id"),
+        file_size: row.get("file_size"),
+        download_date: timestamp_column(row, "download_date"),
+        thumbnail_url: row.get("thumbnail_url"),
+        extractor: row.get("extractor"),
+        video_id: row.get("video_id"),
+        duration: row.get("duration"),
+        playlist_id: row.get("playlist_id"),
+        playlist_title: row.get("playlist_title"),
+        playlist_index: row.get("playlist_index"),
+        chapters: row
+            .get::<Option<String>, _>("chapters")
+            .and_then(|chapters| serde_json::from_str(&chapters).ok())
+            .unwrap_or_default(),
+        uploader: row.get("uploader"),
+        upload_date: row.get("upload_date"),
+        description: row.get("description"),
+        clips: row
+            .get::<Option<String>, _>("clips")
+            .and_then(|clips| serde_json::from_str(&clips).ok())
+            .unwrap_or_default(),
+    }
+}
+
+/// Download columns, with the playlist title joined in
+const DOWNLOAD_COLUMNS: &str = "d.id, d.url, d.title, d.filename, d.file_path, d.format_type, \
+    d.quality, d.format_id, d.file_size, d.download_date, d.thumbnail_url, d.extractor, d.video_id, d.duration, \
+    d.playlist_id, p.title AS playlist_title, d.playlist_index, d.chapters, d.uploader, \
+    d.upload_date, d.description, d.clips";
+
+const DOWNLOAD_TABLES: &str = "downloads d LEFT JOIN playlists p ON p.id = d.playlist_id";
+
+/// Build a `Subtitle` from a `subtitles` row
+fn subtitle_from_row(row: &SqliteRow) -> Subtitle {
+    let auto_generated: i64 = row.get("auto_generated");
+    Subtitle {
+        id: row.get("id"),
+        download_id: row.get("download_id"),
+        language: row.get("language"),
+        format: row.get("format"),
+        auto_generated: auto_generated != 0,
+        file_path: row.get("file_path"),
+    }
+}
+
+const SUBTITLE_COLUMNS: &str = "id, download_id, language, format, auto_generated, file_path";
+
+/// Save a subtitle record, returning its ID
+pub async fn insert_subtitle(pool: &Pool<Sqlite>, subtitle: &Subtitle) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO subtitles (download_id, language, format, auto_generated, file_path)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(subtitle.download_id)
+    .bind(&subtitle.language)
+    .bind(&subtitle.format)
+    .bind(subtitle.auto_generated)
+    .bind(&subtitle.file_path)
+    .fetch_one(pool)
+    .await?
+    .get(0);
+
+    Ok(id)
+}
+
+/// Get a subtitle record by ID
+pub async fn get_subtitle_by_id(
+    pool: &Pool<Sqlite>,
+    id: i64,
+) -> Result<Option<Subtitle>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM subtitles WHERE id = ?",
+        SUBTITLE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(subtitle_from_row))
+}
+
+/// Get the subtitles saved with a download, ordered by language
+pub async fn get_subtitles_for_download(
+    pool: &Pool<Sqlite>,
+    download_id: i64,
+) -> Result<Vec<Subtitle>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM subtitles WHERE download_id = ? ORDER BY language",
+        SUBTITLE_COLUMNS
+    ))
+    .bind(download_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(subtitle_from_row).collect())
+}
+
+/// Get the subtitles of every download, ordered by language
+pub async fn get_all_subtitles(pool: &Pool<Sqlite>) -> Result<Vec<Subtitle>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM subtitles ORDER BY download_id, language",
+        SUBTITLE_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(subtitle_from_row).collect())
+}
+
+/// Build a `DownloadJob` from a `download_jobs` row
+fn job_from_row(row: &SqliteRow) -> DownloadJob {
+    let status: String = row.get("status");
+    DownloadJob {
+        id: row.get("id"),
+        url: row.get("url"),
+        title: row.get("title"),
+        format_type: row.get("format_type"),
+        quality: row.get("quality"),
+        options: row.get("options"),
+        status: JobState::from_db(&status),
+        error: row.get("error"),
+        download_id: row.get("download_id"),
+        workspace: row.get("workspace"),
+        created_at: timestamp_column(row, "created_at"),
+        started_at: timestamp_column(row, "started_at"),
+        finished_at: timestamp_column(row, "finished_at"),
+        starts_at: timestamp_column(row, "starts_at"),
+    }
+}
+
+const JOB_COLUMNS: &str = "id, url, title, format_type, quality, options, status, error, \
+    download_id, workspace, created_at, started_at, finished_at, starts_at";
+
+/// Add a job to the download queue
+pub async fn insert_job(pool: &Pool<Sqlite>, job: &DownloadJob) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO download_jobs (
+            url, title, format_type, quality, options, status, error, download_id, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(&job.url)
+    .bind(&job.title)
+    .bind(&job.format_type)
+    .bind(&job.quality)
+    .bind(&job.options)
+    .bind(job.status.as_str())
+    .bind(&job.error)
+    .bind(job.download_id)
+    .bind(job.created_at.map(|dt| dt.unix_timestamp()))
+    .fetch_one(pool)
+    .await?
+    .get(0);
+
+    Ok(id)
+}
+
+/// Get a queued job by ID
+pub async fn get_job_by_id(
+    pool: &Pool<Sqlite>,
+    id: i64,
+) -> Result<Option<DownloadJob>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM download_jobs WHERE id = ?",
+        JOB_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(job_from_row))
+}
+
+/// Get all jobs in the queue, oldest first
+pub async fn get_all_jobs(pool: &Pool<Sqlite>) -> Result<Vec<DownloadJob>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM download_jobs ORDER BY id ASC",
+        JOB_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(job_from_row).collect())
+}
+
+/// Jobs for a URL in the given format and quality that are yet to finish
+///
+/// Covers queued, waiting and running jobs, oldest first.
+pub async fn find_pending_jobs_for_url(
+    pool: &Pool<Sqlite>,
+    url: &str,
+    format_type: &str,
+    quality: &str,
+) -> Result<Vec<DownloadJob>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT {} FROM download_jobs
+        WHERE url = ? AND format_type = ? AND quality = ? AND status IN (?, ?, ?)
+        ORDER BY id ASC
+        "#,
+        JOB_COLUMNS
+    ))
+    .bind(url)
+    .bind(format_type)
+    .bind(quality)
+    .bind(JobState::Queued.as_str())
+    .bind(JobState::Waiting.as_str())
+    .bind(JobState::Running.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(job_from_row).collect())
+}
+
+/// Atomically mark the oldest queued job as running and return it
+///
+/// Waiting jobs count as queued once their start time has passed.
+pub async fn claim_next_queued_job(
+    pool: &Pool<Sqlite>,
+) -> Result<Option<DownloadJob>, sqlx::Error> {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE download_jobs
+        SET status = ?, started_at = ?
+        WHERE id = (
+            SELECT id FROM download_jobs
+            WHERE status = ? OR (status = ? AND starts_at <= ?)
+            ORDER BY id ASC LIMIT 1
+        )
+        RETURNING {}
+        "#,
+        JOB_COLUMNS
+    ))
+    .bind(JobState::Running.as_str())
+    .bind(now)
+    .bind(JobState::Queued.as_str())
+    .bind(JobState::Waiting.as_str())
+    .bind(now)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(job_from_row))
+}
+
+/// Update the state of a job, recording the finish time for terminal states
+pub async fn update_job_status(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    status: JobState,
+    error: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let finished_at = status
+        .is_finished()
+        .then(|| time::OffsetDateTime::now_utc().unix_timestamp());
+
+    sqlx::query("UPDATE download_jobs SET status = ?, error = ?, finished_at = ? WHERE id = ?")
+        .bind(status.as_str())
+        .bind(error)
+        .bind(finished_at)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Mark a job as completed and link it to its download record
+pub async fn complete_job(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    download_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE download_jobs SET status = ?, error = NULL, download_id = ?, finished_at = ? WHERE id = ?",
+    )
+    .bind(JobState::Completed.as_str())
+    .bind(download_id)
+    .bind(time::OffsetDateTime::now_utc().unix_timestamp())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Store the video title once a job has fetched video info
+pub async fn update_job_title(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    title: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE download_jobs SET title = ? WHERE id = ?")
+        .bind(title)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Park a job until a scheduled stream starts
+///
+/// `starts_at` is a Unix timestamp; the job is claimed again once it has passed.
+pub async fn set_job_waiting(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    starts_at: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE download_jobs SET status = ?, starts_at = ?, started_at = NULL WHERE id = ?",
+    )
+    .bind(JobState::Waiting.as_str())
+    .bind(starts_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Put jobs that were running when the app stopped back into the queue
+pub async fn requeue_interrupted_jobs(pool: &Pool<Sqlite>) -> Result<u64, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE download_jobs SET status = ?, started_at = NULL WHERE status = ?")
+            .bind(JobState::Queued.as_str())
+            .bind(JobState::Running.as_str())
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Move a job that no worker is running from one state to another
+///
+/// Clears the job's error. Returns false if the job wasn't in the `from` state.
+pub async fn transition_job(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    from: JobState,
+    to: JobState,
+) -> Result<bool, sqlx::Error> {
+    let finished_at = to
+        .is_finished()
+        .then(|| time::OffsetDateTime::now_utc().unix_timestamp());
+
+    let result = sqlx::query(
+        "UPDATE download_jobs SET status = ?, error = NULL, finished_at = ? WHERE id = ? AND status = ?",
+    )
+    .bind(to.as_str())
+    .bind(finished_at)
+    .bind(id)
+    .bind(from.as_str())
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remember where a job keeps its partial files
+pub async fn set_job_workspace(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    workspace: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE download_jobs SET workspace = ? WHERE id = ?")
+        .bind(workspace)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Remove completed, failed and cancelled jobs from the queue, returning the removed jobs
+pub async fn delete_finished_jobs(pool: &Pool<Sqlite>) -> Result<Vec<DownloadJob>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "DELETE FROM download_jobs WHERE status IN (?, ?, ?) RETURNING {}",
+        JOB_COLUMNS
+    ))
+    .bind(JobState::Completed.as_str())
+    .bind(JobState::Failed.as_str())
+    .bind(JobState::Cancelled.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(job_from_row).collect())
+}
+
+/// Save a playlist record, returning its ID
+pub async fn insert_playlist(pool: &Pool<Sqlite>, playlist: &Playlist) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO playlists (url, title, uploader, entry_count, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(&playlist.url)
+    .bind(&playlist.title)
+    .bind(&playlist.uploader)
+    .bind(playlist.entry_count)
+    .bind(playlist.created_at.map(|dt| dt.unix_timestamp()))
+    .fetch_one(pool)
+    .await?
+    .get(0);
+
+    Ok(id)
+}
+
+/// Build a `Subscription` from a `subscriptions` row
+fn subscription_from_row(row: &SqliteRow) -> Subscription {
+    let enabled: i64 = row.get("enabled");
+    Subscription {
+        id: row.get("id"),
+        url: row.get("url"),
+        title: row.get("title"),
+        format_type: row.get("format_type"),
+        quality: row.get("quality"),
+        enabled: enabled != 0,
+        last_checked_at: timestamp_column(row, "last_checked_at"),
+        last_error: row.get("last_error"),
+        created_at: timestamp_column(row, "created_at"),
+    }
+}
+
+const SUBSCRIPTION_COLUMNS: &str =
+    "id, url, title, format_type, quality, enabled, last_checked_at, last_error, created_at";
+
+/// Save a subscription, returning its ID
+pub async fn insert_subscription(
+    pool: &Pool<Sqlite>,
+    subscription: &Subscription,
+) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO subscriptions (
+            url, title, format_type, quality, enabled, last_checked_at, last_error, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(&subscription.url)
+    .bind(&subscription.title)
+    .bind(&subscription.format_type)
+    .bind(&subscription.quality)
+    .bind(subscription.enabled)
+    .bind(subscription.last_checked_at.map(|dt| dt.unix_timestamp()))
+    .bind(&subscription.last_error)
+    .bind(subscription.created_at.map(|dt| dt.unix_timestamp()))
+    .fetch_one(pool)
+    .await?
+    .get(0);
+
+    Ok(id)
+}
+
+/// Get a subscription by ID
+pub async fn get_subscription_by_id(
+    pool: &Pool<Sqlite>,
+    id: i64,
+) -> Result<Option<Subscription>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM subscriptions WHERE id = ?",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(subscription_from_row))
+}
+
+/// Get a subscription by its channel URL
+pub async fn get_subscription_by_url(
+    pool: &Pool<Sqlite>,
+    url: &str,
+) -> Result<Option<Subscription>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM subscriptions WHERE url = ?",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(url)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(subscription_from_row))
+}
+
+/// Get all subscriptions, in the order they were added
+pub async fn get_all_subscriptions(pool: &Pool<Sqlite>) -> Result<Vec<Subscription>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM subscriptions ORDER BY id ASC",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(subscription_from_row).collect())
+}
+
+/// Turn checking a subscription on or off
+pub async fn set_subscription_enabled(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    enabled: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE subscriptions SET enabled = ? WHERE id = ?")
+        .bind(enabled)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record the time and outcome of a subscription check
+pub async fn update_subscription_check(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    error: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE subscriptions SET last_checked_at = ?, last_error = ? WHERE id = ?")
+        .bind(time::OffsetDateTime::now_utc().unix_timestamp())
+        .bind(error)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete a subscription and the record of the uploads it has seen
+pub async fn delete_subscription(pool: &Pool<Sqlite>, id: i64) -> Result<bool, sqlx::Error> {
+    // Foreign keys aren't enforced, so the cascade is done by hand
+    sqlx::query("DELETE FROM subscription_videos WHERE subscription_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    let result = sqlx::query("DELETE FROM subscriptions WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// IDs of the uploads a subscription has already seen
+pub async fn get_seen_video_ids(
+    pool: &Pool<Sqlite>,
+    subscription_id: i64,
+) -> Result<HashSet<String>, sqlx::Error> {
+    let rows = sqlx::query("SELECT video_id FROM subscription_videos WHERE subscription_id = ?")
+        .bind(subscription_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(|row| row.get("video_id")).collect())
+}
+
+/// Remember that a subscription has seen an upload, so it isn't queued again
+pub async fn mark_video_seen(
+    pool: &Pool<Sqlite>,
+    subscription_id: i64,
+    video_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO subscription_videos (subscription_id, video_id, seen_at) VALUES (?, ?, ?)",
+    )
+    .bind(subscription_id)
+    .bind(video_id)
+    .bind(time::OffsetDateTime::now_utc().unix_timestamp())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// IDs of every YouTube video in the downloads history
+///
+/// Records from before the extractor was stored are all YouTube downloads.
+pub async fn get_downloaded_video_ids(pool: &Pool<Sqlite>) -> Result<HashSet<String>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT DISTINCT video_id FROM downloads WHERE video_id IS NOT NULL AND (extractor IS NULL OR extractor = 'youtube')",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| row.get("video_id")).collect())
+}
+
+/// Read a value from the settings table
+pub async fn get_setting(pool: &Pool<Sqlite>, key: &str) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.map(|row| row.get("value")))
+}
+
+/// Remove a value from the settings table
+pub async fn delete_setting(pool: &Pool<Sqlite>, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM settings WHERE key = ?")
+        .bind(key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Insert or replace a value in the settings table
+pub async fn set_setting(pool: &Pool<Sqlite>, key: &str, value: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
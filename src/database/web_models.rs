@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::server::download::youtube_url::youtube_video_id;
+
 /// Represents a downloaded video for web platform
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Download {
@@ -60,33 +62,9 @@ impl Download {
         }
     }
 
-    /// Extract the video ID from a YouTube URL
+    /// Extract the video ID from any form of YouTube URL
     pub fn extract_video_id(url: &str) -> Option<String> {
-        let parsed_url = url::Url::parse(url).ok()?;
-
-        // Handle youtube.com/watch?v=VIDEO_ID
-        if parsed_url.host_str() == Some("www.youtube.com")
-            || parsed_url.host_str() == Some("youtube.com")
-        {
-            if parsed_url.path() == "/watch" {
-                let pairs = parsed_url.query_pairs();
-                for (key, value) in pairs {
-                    if key == "v" {
-                        return Some(value.to_string());
-                    }
-                }
-            }
-        }
-
-        // Handle youtu.be/VIDEO_ID
-        if parsed_url.host_str() == Some("youtu.be") {
-            let path = parsed_url.path();
-            if path.len() > 1 {
-                return Some(path[1..].to_string());
-            }
-        }
-
-        None
+        youtube_video_id(url)
     }
 
     /// Generate a thumbnail URL from a video ID
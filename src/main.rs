@@ -3,7 +3,7 @@
 use dioxus::prelude::*;
 
 use components::Navbar;
-use views::{Download, Downloads, Home, Search};
+use views::{Download, Downloads, Home, Playlist, Queue, Search, Settings, Subscriptions};
 
 mod components;
 mod database;
@@ -26,6 +26,18 @@ enum Route {
     
     #[route("/downloads")]
     Downloads {},
+
+    #[route("/queue")]
+    Queue {},
+
+    #[route("/playlist")]
+    Playlist {},
+
+    #[route("/subscriptions")]
+    Subscriptions {},
+
+    #[route("/settings")]
+    Settings {},
     
     #[route("/search")]
     Search {},
@@ -67,14 +79,43 @@ fn main() {
         LaunchBuilder::web().launch(App);
     }
 
-    #[cfg(not(any(feature = "desktop", feature = "web")))]
+    // The server build adds its own HTTP routes next to the app
+    #[cfg(all(feature = "server", not(any(feature = "desktop", feature = "web"))))]
+    {
+        launch_server();
+    }
+
+    #[cfg(not(any(feature = "desktop", feature = "web", feature = "server")))]
     {
         LaunchBuilder::new().launch(App);
     }
 }
 
+/// Serve the app, its server functions and the file download endpoint
+#[cfg(all(feature = "server", not(any(feature = "desktop", feature = "web"))))]
+fn launch_server() {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let address = dioxus::cli_config::fullstack_address_or_localhost();
+        let router = axum::Router::new()
+            .merge(server::download::files::router())
+            .serve_dioxus_application(ServeConfig::new().unwrap(), App);
+
+        let listener = tokio::net::TcpListener::bind(address).await.unwrap();
+        axum::serve(listener, router.into_make_service())
+            .await
+            .unwrap();
+    });
+}
+
 #[component]
 fn App() -> Element {
+    // Resume interrupted downloads and start checking subscriptions
+    use_future(|| async {
+        if let Err(e) = server::download::handlers::start_background_tasks().await {
+            tracing::error!("Failed to start background tasks: {}", e);
+        }
+    });
+
     rsx! {
         // Global app resources
         document::Link { rel: "icon", href: FAVICON }
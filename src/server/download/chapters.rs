@@ -0,0 +1,122 @@
+// Chapters of downloaded videos
+//
+// yt-dlp lists chapters in the info JSON and embeds them in the container with
+// `--embed-chapters`. Audio downloads can also be split into one file per
+// chapter, which ffmpeg does without re-encoding.
+
+use std::path::Path;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::storage;
+use crate::server::download::types::Chapter;
+
+/// Chapters from yt-dlp's info JSON, in order
+///
+/// Untitled chapters are numbered, and a missing end time is taken from the
+/// next chapter or, for the last one, the video's duration.
+pub fn from_info(chapters: &[youtube_dl::Chapter], duration_secs: u64) -> Vec<Chapter> {
+    let starts: Vec<f64> = chapters
+        .iter()
+        .map(|chapter| chapter.start_time.unwrap_or_default())
+        .collect();
+
+    chapters
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            let end_secs = chapter
+                .end_time
+                .or_else(|| starts.get(index + 1).copied())
+                .unwrap_or(duration_secs as f64);
+            Chapter {
+                title: chapter
+                    .title
+                    .clone()
+                    .filter(|title| !title.trim().is_empty())
+                    .unwrap_or_else(|| format!("Chapter {}", index + 1)),
+                start_secs: starts[index],
+                end_secs,
+                file_path: None,
+            }
+        })
+        .filter(|chapter| chapter.end_secs > chapter.start_secs)
+        .collect()
+}
+
+/// Split an audio file into one file per chapter
+///
+/// The files go into a folder named after the audio file, numbered and tagged
+/// with the chapter title and track number. Returns the chapters with the
+/// path of their file filled in.
+pub async fn split_audio(
+    media_path: &Path,
+    chapters: &[Chapter],
+) -> Result<Vec<Chapter>, DownloaderError> {
+    let (Some(parent), Some(stem)) = (media_path.parent(), media_path.file_stem()) else {
+        return Err(DownloaderError::Storage(format!(
+            "Can't split {} into chapters",
+            media_path.display()
+        )));
+    };
+    let extension = media_path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let folder = parent.join(stem);
+    tokio::fs::create_dir_all(&folder)
+        .await
+        .map_err(|e| DownloaderError::Storage(format!("Failed to create chapter folder: {}", e)))?;
+
+    let total = chapters.len();
+    let width = total.to_string().len().max(2);
+    let mut split = Vec::with_capacity(total);
+    for (index, chapter) in chapters.iter().enumerate() {
+        let track = index + 1;
+        let target = folder.join(format!(
+            "{:0width$} - {}.{}",
+            track,
+            storage::clean_path_component(&chapter.title),
+            extension,
+            width = width
+        ));
+
+        let output = tokio::process::Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-i"])
+            .arg(media_path)
+            .arg("-ss")
+            .arg(format!("{:.3}", chapter.start_secs))
+            .arg("-to")
+            .arg(format!("{:.3}", chapter.end_secs))
+            .args(["-map", "0:a", "-c", "copy", "-map_chapters", "-1"])
+            .arg("-metadata")
+            .arg(format!("title={}", chapter.title))
+            .arg("-metadata")
+            .arg(format!("track={}/{}", track, total))
+            .arg(&target)
+            .output()
+            .await
+            .map_err(|e| DownloaderError::Internal(format!("Failed to start ffmpeg: {}", e)))?;
+        if !output.status.success() {
+            return Err(DownloaderError::Internal(format!(
+                "ffmpeg failed to split chapter {}: {}",
+                track,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        split.push(Chapter {
+            file_path: Some(target.to_string_lossy().to_string()),
+            ..chapter.clone()
+        });
+    }
+
+    tracing::info!(
+        "Split {} into {} chapters in {}",
+        media_path.display(),
+        total,
+        folder.display()
+    );
+    Ok(split)
+}
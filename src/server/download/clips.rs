@@ -0,0 +1,92 @@
+// Clips: downloading only parts of a video
+//
+// yt-dlp fetches each range with `--download-sections`, writing one file per
+// range. When several ranges are asked for in one job, ffmpeg joins the files
+// into a single clip without re-encoding.
+
+use std::path::{Path, PathBuf};
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::ClipRange;
+use crate::server::download::ytdlp::YtDlpDownload;
+
+/// Add the yt-dlp options that download only the clip ranges
+///
+/// `output_name` is the job's usual output template; with several ranges each
+/// section gets a numbered file so they don't overwrite each other.
+pub fn configure(download: &mut YtDlpDownload, clips: &[ClipRange], output_name: &str) {
+    for clip in clips {
+        download.extra_arg("--download-sections");
+        download.extra_arg(clip.section());
+    }
+    // Cut where asked rather than at the nearest keyframe
+    download.extra_arg("--force-keyframes-at-cuts");
+    if clips.len() > 1 {
+        download.output_template(format!("{}-%(section_number)s", output_name));
+    }
+}
+
+/// Title with the clip ranges appended, used for the saved file's name
+pub fn clip_title(title: &str, clips: &[ClipRange]) -> String {
+    if clips.is_empty() {
+        return title.to_string();
+    }
+    let labels: Vec<String> = clips.iter().map(ClipRange::label).collect();
+    format!("{} [{}]", title, labels.join(", "))
+}
+
+/// Join the files of several sections into one, in order
+///
+/// The joined file is written next to the first section with the same
+/// extension.
+pub async fn join(parts: &[PathBuf]) -> Result<PathBuf, DownloaderError> {
+    let (Some(first), Some(extension)) = (
+        parts.first(),
+        parts.first().and_then(|part| part.extension()),
+    ) else {
+        return Err(DownloaderError::Storage(
+            "yt-dlp reported no clip files".to_string(),
+        ));
+    };
+    let dir = first.parent().unwrap_or(Path::new("."));
+    let list_path = dir.join("clips.txt");
+    let target = dir.join(format!("clips.{}", extension.to_string_lossy()));
+
+    // Lines of ffmpeg's concat list quote paths in single quotes
+    let list: String = parts
+        .iter()
+        .map(|part| format!("file '{}'\n", part.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    tokio::fs::write(&list_path, list)
+        .await
+        .map_err(|e| DownloaderError::Storage(format!("Failed to write clip list: {}", e)))?;
+
+    let output = tokio::process::Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+        ])
+        .arg(&list_path)
+        .args(["-map", "0", "-c", "copy"])
+        .arg(&target)
+        .output()
+        .await
+        .map_err(|e| DownloaderError::Internal(format!("Failed to start ffmpeg: {}", e)))?;
+    let _ = tokio::fs::remove_file(&list_path).await;
+    if !output.status.success() {
+        return Err(DownloaderError::Internal(format!(
+            "ffmpeg failed to join the clips: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    tracing::info!("Joined {} clips into {}", parts.len(), target.display());
+    Ok(target)
+}
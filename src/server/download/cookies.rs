@@ -0,0 +1,393 @@
+// Cookies for signed-in downloads
+//
+// Age-restricted, members-only and private videos need the cookies of a
+// signed-in browser session. They are imported from a Netscape `cookies.txt`
+// export and kept per site in the app data directory, readable only by the
+// current user. Every yt-dlp run for a URL on that site gets `--cookies`, and
+// ffmpeg gets them too when it records a live stream.
+
+use std::path::PathBuf;
+use tracing;
+
+use crate::database::{
+    get_database,
+    schema::{delete_setting, get_setting, set_setting},
+};
+use crate::server::download::error::DownloaderError;
+use crate::server::download::sites;
+use crate::server::download::types::CookieSite;
+use crate::server::download::utils;
+use crate::server::download::ytdlp::get_app_data_dir;
+
+/// Settings key prefix of the import time, stored as a Unix timestamp
+const IMPORTED_AT_SETTING: &str = "cookies.imported_at.";
+
+/// A line of a Netscape cookies file
+#[derive(Debug, Clone, PartialEq)]
+struct Cookie {
+    /// Domain without the leading dot, e.g. "youtube.com"
+    domain: String,
+    /// Unix timestamp, `None` for session cookies
+    expires: Option<i64>,
+    /// The line as it was imported
+    line: String,
+}
+
+/// Cookies read from a Netscape cookies file; comments and bad lines are skipped
+///
+/// Lines starting with `#HttpOnly_` are cookies, not comments.
+fn parse_cookies(content: &str) -> Vec<Cookie> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end_matches('\r');
+            let cookie = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if cookie.starts_with('#') || cookie.trim().is_empty() {
+                return None;
+            }
+
+            let fields: Vec<&str> = cookie.split('\t').collect();
+            if fields.len() != 7 {
+                return None;
+            }
+            let domain = fields[0]
+                .trim_start_matches('.')
+                .trim_start_matches("www.")
+                .to_lowercase();
+            let expires = fields[4].parse::<i64>().ok()?;
+            if domain.is_empty() || fields[5].is_empty() {
+                return None;
+            }
+
+            Some(Cookie {
+                domain,
+                expires: Some(expires).filter(|expires| *expires > 0),
+                line: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Whether a cookie is sent to the site, or to a parent domain of it
+fn applies_to(cookie: &Cookie, site: &str) -> bool {
+    sites::host_allowed(&cookie.domain, &[site.to_string()])
+        || sites::host_allowed(site, &[cookie.domain.clone()])
+}
+
+/// Site the cookies were most likely exported for
+///
+/// Takes the most common cookie domain, cut down to its last two labels.
+fn guess_site(cookies: &[Cookie]) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for cookie in cookies {
+        let labels: Vec<&str> = cookie.domain.split('.').collect();
+        let site = labels[labels.len().saturating_sub(2)..].join(".");
+        match counts.iter_mut().find(|(existing, _)| *existing == site) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((site, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(site, _)| site)
+}
+
+fn cookies_dir() -> Result<PathBuf, DownloaderError> {
+    Ok(get_app_data_dir()?.join("cookies"))
+}
+
+fn cookie_file(site: &str) -> Result<PathBuf, DownloaderError> {
+    // Sites come from the client too, so only domain characters may reach the path
+    let is_domain = !site.is_empty()
+        && !site.starts_with('.')
+        && site
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    if !is_domain {
+        return Err(DownloaderError::InvalidUrl(format!(
+            "\"{}\" is not a site domain",
+            site
+        )));
+    }
+    Ok(cookies_dir()?.join(format!("{}.txt", site)))
+}
+
+/// Store the cookies of a `cookies.txt` export for a site
+///
+/// Only the cookies sent to the site are kept. With no site given, it is taken
+/// from the cookies themselves. Cookies imported earlier for the site are
+/// replaced.
+pub async fn import(site: &str, content: &str) -> Result<CookieSite, DownloaderError> {
+    let cookies = parse_cookies(content);
+    if cookies.is_empty() {
+        return Err(DownloaderError::InvalidUrl(
+            "No cookies found. Export them from your browser in the Netscape cookies.txt format."
+                .to_string(),
+        ));
+    }
+
+    let site = match sites::parse_allowlist(site).into_iter().next() {
+        Some(site) => site,
+        None => guess_site(&cookies).ok_or_else(|| {
+            DownloaderError::InvalidUrl("Enter the site the cookies are for".to_string())
+        })?,
+    };
+    let lines: Vec<&str> = cookies
+        .iter()
+        .filter(|cookie| applies_to(cookie, &site))
+        .map(|cookie| cookie.line.as_str())
+        .collect();
+    if lines.is_empty() {
+        return Err(DownloaderError::InvalidUrl(format!(
+            "The file has no cookies for {}",
+            site
+        )));
+    }
+
+    let dir = cookies_dir()?;
+    create_private_dir(&dir).await?;
+    let content = format!("# Netscape HTTP Cookie File\n{}\n", lines.join("\n"));
+    write_private_file(&cookie_file(&site)?, &content).await?;
+
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    set_setting(
+        &pool,
+        &format!("{}{}", IMPORTED_AT_SETTING, site),
+        &now.to_string(),
+    )
+    .await
+    .map_err(|e| DownloaderError::Database(format!("Failed to save cookie import: {}", e)))?;
+
+    tracing::info!("Imported {} cookies for {}", lines.len(), site);
+    site_info(&site)
+        .await?
+        .ok_or_else(|| DownloaderError::Storage(format!("Cookies for {} were not saved", site)))
+}
+
+/// Delete the cookies of a site
+pub async fn remove(site: &str) -> Result<(), DownloaderError> {
+    let path = cookie_file(site)?;
+    match tokio::fs::remove_file(&path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(DownloaderError::NotFound(format!(
+                "No cookies saved for {}",
+                site
+            )));
+        }
+        Err(e) => {
+            return Err(DownloaderError::Storage(format!(
+                "Failed to delete cookies: {}",
+                e
+            )))
+        }
+    }
+
+    if let Ok(pool) = get_database().await {
+        let _ = delete_setting(&pool, &format!("{}{}", IMPORTED_AT_SETTING, site)).await;
+    }
+    tracing::info!("Removed cookies for {}", site);
+    Ok(())
+}
+
+/// Sites with saved cookies
+async fn stored_sites() -> Vec<String> {
+    let Ok(dir) = cookies_dir() else {
+        return Vec::new();
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return Vec::new();
+    };
+
+    let mut sites = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_some_and(|extension| extension == "txt") {
+            if let Some(stem) = path.file_stem() {
+                sites.push(stem.to_string_lossy().to_string());
+            }
+        }
+    }
+    sites.sort();
+    sites
+}
+
+/// Every site with saved cookies, for the Settings view
+pub async fn list() -> Vec<CookieSite> {
+    let mut list = Vec::new();
+    for site in stored_sites().await {
+        match site_info(&site).await {
+            Ok(Some(info)) => list.push(info),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read cookies for {}: {}", site, e),
+        }
+    }
+    list
+}
+
+async fn site_info(site: &str) -> Result<Option<CookieSite>, DownloaderError> {
+    let path = cookie_file(site)?;
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(DownloaderError::Storage(format!(
+                "Failed to read cookies: {}",
+                e
+            )))
+        }
+    };
+    let cookies = parse_cookies(&content);
+
+    // Files yt-dlp rewrote keep their import time in the settings
+    let imported_at = match get_database().await {
+        Ok(pool) => get_setting(&pool, &format!("{}{}", IMPORTED_AT_SETTING, site))
+            .await
+            .ok()
+            .flatten()
+            .and_then(|value| value.parse::<i64>().ok())
+            .and_then(|timestamp| time::OffsetDateTime::from_unix_timestamp(timestamp).ok()),
+        Err(_) => None,
+    };
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+
+    Ok(Some(CookieSite {
+        site: site.to_string(),
+        cookie_count: cookies.len(),
+        imported_at: imported_at
+            .and_then(|date| {
+                time::format_description::parse("[day] [month repr:short] [year] [hour]:[minute]")
+                    .ok()
+                    .and_then(|fmt| date.format(&fmt).ok())
+            })
+            .unwrap_or_else(|| "Unknown".to_string()),
+        expired: !cookies.is_empty()
+            && cookies
+                .iter()
+                .all(|cookie| cookie.expires.is_some_and(|expires| expires < now)),
+    }))
+}
+
+/// Cookie file saved for a host, if there is one
+///
+/// The most specific site wins, so cookies for `music.youtube.com` are used
+/// over those for `youtube.com`.
+async fn file_for_host(host: &str) -> Option<PathBuf> {
+    let site = stored_sites()
+        .await
+        .into_iter()
+        .filter(|site| sites::host_allowed(host, std::slice::from_ref(site)))
+        .max_by_key(|site| site.len())?;
+    cookie_file(&site).ok()
+}
+
+/// yt-dlp arguments passing the cookies saved for a host, if there are any
+pub async fn args_for_host(host: &str) -> Vec<String> {
+    match file_for_host(host).await {
+        Some(path) => vec!["--cookies".to_string(), path.to_string_lossy().to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// yt-dlp arguments passing the cookies saved for the site of a URL
+pub async fn args_for(url: &str) -> Vec<String> {
+    match utils::url_host(url) {
+        Some(host) => args_for_host(&host).await,
+        None => Vec::new(),
+    }
+}
+
+/// ffmpeg arguments passing the cookies saved for the site of a URL
+///
+/// ffmpeg's `-cookies` takes one `Set-Cookie` style line per cookie and sends
+/// each to the domains it names. Expired cookies are left out.
+pub async fn ffmpeg_args_for(url: &str) -> Vec<String> {
+    let Some(host) = utils::url_host(url) else {
+        return Vec::new();
+    };
+    let Some(path) = file_for_host(&host).await else {
+        return Vec::new();
+    };
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::warn!("Failed to read cookies {:?}: {}", path, e);
+            return Vec::new();
+        }
+    };
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let lines: Vec<String> = parse_cookies(&content)
+        .iter()
+        .filter(|cookie| !cookie.expires.is_some_and(|expires| expires < now))
+        .map(|cookie| {
+            let line = cookie
+                .line
+                .strip_prefix("#HttpOnly_")
+                .unwrap_or(&cookie.line);
+            let fields: Vec<&str> = line.split('\t').collect();
+            let secure = if fields[3].eq_ignore_ascii_case("TRUE") {
+                " secure;"
+            } else {
+                ""
+            };
+            format!(
+                "{}={}; path={}; domain={};{}",
+                fields[5], fields[6], fields[2], fields[0], secure
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        return Vec::new();
+    }
+    vec!["-cookies".to_string(), lines.join("\n")]
+}
+
+async fn create_private_dir(dir: &std::path::Path) -> Result<(), DownloaderError> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| DownloaderError::Storage(format!("Failed to create cookie folder: {}", e)))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .await
+            .map_err(|e| {
+                DownloaderError::Storage(format!("Failed to protect cookie folder: {}", e))
+            })?;
+    }
+    Ok(())
+}
+
+/// Write a file only the current user can read, replacing it if it exists
+async fn write_private_file(path: &std::path::Path, content: &str) -> Result<(), DownloaderError> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options
+        .open(path)
+        .await
+        .map_err(|e| DownloaderError::Storage(format!("Failed to save cookies: {}", e)))?;
+    file.write_all(content.as_bytes())
+        .await
+        .map_err(|e| DownloaderError::Storage(format!("Failed to save cookies: {}", e)))?;
+
+    // A file left over from before keeps its mode when opened, so set it again
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .await
+            .map_err(|e| DownloaderError::Storage(format!("Failed to protect cookies: {}", e)))?;
+    }
+    Ok(())
+}
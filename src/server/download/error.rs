@@ -0,0 +1,204 @@
+// Error type shared by the server functions
+//
+// Server functions return `ServerFnError<DownloaderError>`, so clients get the
+// variant back and can react to it, e.g. by offering a retry for timeouts.
+// server_fn transports custom errors through `Display` and `FromStr`, which
+// use the form `[code] message`; the codes are stable and must not change.
+
+use dioxus::prelude::ServerFnError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::server::download::types::FailureKind;
+
+/// Why a server function failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DownloaderError {
+    /// The URL is malformed or points at something we can't download
+    InvalidUrl(String),
+    /// An operation took too long and was given up
+    Timeout(String),
+    /// No working yt-dlp binary could be found or installed
+    YtDlpMissing(String),
+    /// yt-dlp ran but the download or info lookup failed
+    Download { kind: FailureKind, message: String },
+    /// The server or the site couldn't be reached
+    Network(String),
+    /// The download was cancelled by the user
+    Cancelled,
+    /// A job, download record or file doesn't exist
+    NotFound(String),
+    /// The video is already in the library or the queue in the requested format and quality
+    AlreadyDownloaded(String),
+    /// The database couldn't be opened or queried
+    Database(String),
+    /// Reading or writing files on disk failed
+    Storage(String),
+    /// The app was built without the server feature
+    ServerDisabled,
+    /// Anything else
+    Internal(String),
+}
+
+impl DownloaderError {
+    /// Stable identifier of the variant
+    pub fn code(&self) -> &'static str {
+        match self {
+            DownloaderError::InvalidUrl(_) => "invalid_url",
+            DownloaderError::Timeout(_) => "timeout",
+            DownloaderError::YtDlpMissing(_) => "ytdlp_missing",
+            DownloaderError::Download { .. } => "download_failed",
+            DownloaderError::Network(_) => "network",
+            DownloaderError::Cancelled => "cancelled",
+            DownloaderError::NotFound(_) => "not_found",
+            DownloaderError::AlreadyDownloaded(_) => "already_downloaded",
+            DownloaderError::Database(_) => "database",
+            DownloaderError::Storage(_) => "storage",
+            DownloaderError::ServerDisabled => "server_disabled",
+            DownloaderError::Internal(_) => "internal",
+        }
+    }
+
+    /// Message to show to the user
+    pub fn message(&self) -> String {
+        match self {
+            DownloaderError::Download { kind, message } => kind.describe(message),
+            DownloaderError::Cancelled => "Download cancelled".to_string(),
+            DownloaderError::ServerDisabled => "Server feature not enabled".to_string(),
+            DownloaderError::InvalidUrl(message)
+            | DownloaderError::Timeout(message)
+            | DownloaderError::YtDlpMissing(message)
+            | DownloaderError::Network(message)
+            | DownloaderError::NotFound(message)
+            | DownloaderError::AlreadyDownloaded(message)
+            | DownloaderError::Database(message)
+            | DownloaderError::Storage(message)
+            | DownloaderError::Internal(message) => message.clone(),
+        }
+    }
+
+    /// What the user can do about the error
+    pub fn guidance(&self) -> &'static str {
+        match self {
+            DownloaderError::InvalidUrl(_) => "Check the link and paste the full video URL.",
+            DownloaderError::Timeout(_) => "The site is slow to respond. Try again.",
+            DownloaderError::YtDlpMissing(_) => {
+                "The yt-dlp downloader couldn't be installed. Check your connection or install yt-dlp manually."
+            }
+            DownloaderError::Download { kind, .. } => kind.hint(),
+            DownloaderError::Network(_) => "Check your connection and try again.",
+            DownloaderError::Cancelled => "Start the download again whenever you like.",
+            DownloaderError::NotFound(_) => "It may have been removed. Refresh and try again.",
+            DownloaderError::AlreadyDownloaded(_) => {
+                "Find it in Downloads or the Queue, or paste the link on the Download page to download it again."
+            }
+            DownloaderError::Database(_) | DownloaderError::Storage(_) => {
+                "Check that the app can write to its data and download folders."
+            }
+            DownloaderError::ServerDisabled => "This build can't download on its own.",
+            DownloaderError::Internal(_) => "Something unexpected went wrong.",
+        }
+    }
+
+    /// Whether running the same request again may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DownloaderError::Timeout(_) | DownloaderError::Network(_) => true,
+            DownloaderError::Download { kind, .. } => kind.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Failure category used by the retry policy
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            DownloaderError::Download { kind, .. } => *kind,
+            DownloaderError::Timeout(_) | DownloaderError::Network(_) => FailureKind::Network,
+            _ => FailureKind::Other,
+        }
+    }
+
+    /// A failed yt-dlp run, classified from its error output
+    #[cfg(feature = "server")]
+    pub fn from_ytdlp_output(message: impl Into<String>) -> Self {
+        let message = message.into();
+        DownloaderError::Download {
+            kind: crate::server::download::retry::classify_failure(&message),
+            message,
+        }
+    }
+
+    /// Read back an error stored with `to_string`
+    ///
+    /// Jobs that failed before errors were typed only stored a yt-dlp message,
+    /// which is classified instead.
+    #[cfg(feature = "server")]
+    pub fn from_stored(value: &str) -> Self {
+        value
+            .parse()
+            .unwrap_or_else(|_| DownloaderError::from_ytdlp_output(value))
+    }
+}
+
+impl fmt::Display for DownloaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloaderError::Download { kind, message } => {
+                write!(f, "[{}/{}] {}", self.code(), kind.code(), message)
+            }
+            _ => write!(f, "[{}] {}", self.code(), self.message()),
+        }
+    }
+}
+
+impl FromStr for DownloaderError {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (code, message) = value
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .ok_or(())?;
+        let message = message.to_string();
+
+        let error = match code.split_once('/') {
+            Some(("download_failed", kind)) => DownloaderError::Download {
+                kind: FailureKind::from_code(kind),
+                message,
+            },
+            Some(_) => return Err(()),
+            None => match code {
+                "invalid_url" => DownloaderError::InvalidUrl(message),
+                "timeout" => DownloaderError::Timeout(message),
+                "ytdlp_missing" => DownloaderError::YtDlpMissing(message),
+                "network" => DownloaderError::Network(message),
+                "cancelled" => DownloaderError::Cancelled,
+                "not_found" => DownloaderError::NotFound(message),
+                "already_downloaded" => DownloaderError::AlreadyDownloaded(message),
+                "database" => DownloaderError::Database(message),
+                "storage" => DownloaderError::Storage(message),
+                "server_disabled" => DownloaderError::ServerDisabled,
+                "internal" => DownloaderError::Internal(message),
+                _ => return Err(()),
+            },
+        };
+        Ok(error)
+    }
+}
+
+impl std::error::Error for DownloaderError {}
+
+/// Unwrap the error a server function returned, including transport failures
+impl From<ServerFnError<DownloaderError>> for DownloaderError {
+    fn from(error: ServerFnError<DownloaderError>) -> Self {
+        match error {
+            ServerFnError::WrappedServerError(error) => error,
+            ServerFnError::Request(message) => {
+                DownloaderError::Network(format!("Could not reach the server: {}", message))
+            }
+            ServerFnError::ServerError(message) => DownloaderError::Internal(message),
+            other => DownloaderError::Internal(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// HTTP endpoint serving finished downloads
+//
+// Files are streamed from disk in chunks, so memory use stays flat whatever
+// the file size. Single byte ranges are supported so browsers can seek in
+// media and resume interrupted transfers.
+
+use std::io::SeekFrom;
+
+use axum::{
+    body::Body,
+    extract::Path,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use tracing;
+
+use crate::database::{
+    get_database,
+    schema::{get_download_by_id, get_subtitle_by_id},
+};
+use crate::server::download::utils::FILES_ROUTE;
+
+/// Routes for the file endpoint, to merge into the app's router
+pub fn router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(&format!("{}/:id", FILES_ROUTE), get(serve_file))
+        .route(
+            &format!("{}/subtitles/:id", FILES_ROUTE),
+            get(serve_subtitle),
+        )
+}
+
+/// Byte range requested by a client
+#[derive(Debug)]
+enum ByteRange {
+    /// No usable `Range` header; send the whole file
+    Full,
+    /// Inclusive start and end offsets
+    Partial(u64, u64),
+    /// The range lies outside the file
+    Unsatisfiable,
+}
+
+async fn serve_file(Path(id): Path<i64>, headers: HeaderMap) -> Response {
+    let pool = match get_database().await {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!("Database unavailable: {}", e);
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+    };
+
+    let download = match get_download_by_id(&pool, id).await {
+        Ok(Some(download)) => download,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Download not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load download {}: {}", id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    stream_file(&download.file_path, &download.filename, &headers).await
+}
+
+async fn serve_subtitle(Path(id): Path<i64>, headers: HeaderMap) -> Response {
+    let pool = match get_database().await {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!("Database unavailable: {}", e);
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+    };
+
+    // Embedded subtitles have no file of their own
+    let file_path = match get_subtitle_by_id(&pool, id).await {
+        Ok(Some(subtitle)) => match subtitle.file_path {
+            Some(path) => path,
+            None => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    "Subtitles are embedded in the media file",
+                )
+                    .into_response()
+            }
+        },
+        Ok(None) => return (StatusCode::NOT_FOUND, "Subtitles not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load subtitles {}: {}", id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let filename = std::path::Path::new(&file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    stream_file(&file_path, &filename, &headers).await
+}
+
+/// Stream a file from disk, honouring a single byte range
+async fn stream_file(file_path: &str, filename: &str, headers: &HeaderMap) -> Response {
+    let mut file = match tokio::fs::File::open(file_path).await {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::warn!("Failed to open {}: {}", file_path, e);
+            return (StatusCode::NOT_FOUND, "File is no longer on disk").into_response();
+        }
+    };
+    let size = match file.metadata().await {
+        Ok(metadata) => metadata.len(),
+        Err(e) => {
+            tracing::error!("Failed to read metadata of {}: {}", file_path, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| parse_range(value, size))
+        .unwrap_or(ByteRange::Full);
+
+    let (status, start, length) = match range {
+        ByteRange::Full => (StatusCode::OK, 0, size),
+        ByteRange::Partial(start, end) => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+        ByteRange::Unsatisfiable => {
+            return (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+            )
+                .into_response();
+        }
+    };
+
+    if start > 0 {
+        if let Err(e) = file.seek(SeekFrom::Start(start)).await {
+            tracing::error!("Failed to seek in {}: {}", file_path, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type(filename))
+        .header(header::CONTENT_LENGTH, length)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_DISPOSITION, content_disposition(filename));
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, start + length - 1, size),
+        );
+    }
+
+    let body = Body::from_stream(ReaderStream::new(file.take(length)));
+    response
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Parse a `Range` header for a file of `size` bytes
+///
+/// Only single ranges are honoured; anything else gets the whole file, which
+/// clients must accept.
+fn parse_range(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // `bytes=-500` asks for the last 500 bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) | Err(_) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => (size.saturating_sub(suffix), size.saturating_sub(1)),
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) => (start, end.min(size.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if size == 0 || start >= size || start > end {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(start, end)
+    }
+}
+
+/// MIME type for the media formats the downloader produces
+fn content_type(filename: &str) -> &'static str {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "opus" | "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "aac" => "audio/aac",
+        "flac" => "audio/flac",
+        "srt" => "application/x-subrip",
+        "vtt" => "text/vtt",
+        _ => "application/octet-stream",
+    }
+}
+
+/// `Content-Disposition` header with an ASCII fallback and the UTF-8 filename
+fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    // RFC 5987 encoding: keep attr-chars, percent-encode every other byte
+    let encoded: String = filename
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect();
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    )
+}
@@ -1,53 +1,139 @@
-use dioxus::prelude::*;
-use server_fn::error::NoCustomError;
 use tracing;
 
 #[cfg(feature = "server")]
-use crate::database::{get_database, models::Download as DbDownload, schema::save_download};
+use crate::database::{
+    get_database,
+    models::{Download as DbDownload, Subtitle},
+    schema::{insert_subtitle, save_download},
+};
+#[cfg(feature = "server")]
+use crate::server::download::error::DownloaderError;
+#[cfg(feature = "server")]
+use crate::server::download::types::{
+    Chapter, ClipRange, DownloadOptions, SubtitleOptions, VideoMetadata,
+};
 
-/// Save download info to database
+/// The file a download job saved
+#[cfg(feature = "server")]
+pub struct SavedFile<'a> {
+    pub file_name: &'a str,
+    pub file_path: &'a str,
+    pub file_size: i64,
+    /// Chapters of the file, which differ from the video's for clips and split audio
+    pub chapters: &'a [Chapter],
+    /// Parts of the video the file holds, empty for the whole video
+    pub clips: &'a [ClipRange],
+}
+
+/// Save download info to database, returning the new record ID when a database is available
+///
+/// `options` are the job's download options, which give the record its
+/// format, quality and playlist.
 #[cfg(feature = "server")]
 pub async fn save_download_info(
     url: &str,
-    title: &str,
-    filename: &str,
-    file_path: &str,
-    format_type: &str,
-    quality: &str,
-    file_size: i64,
-) -> Result<(), ServerFnError<NoCustomError>> {
-    let video_id = DbDownload::extract_video_id(url);
-
-    // Generate thumbnail URL if video ID is available
-    let thumbnail_url = video_id
-        .as_ref()
-        .map(|id| DbDownload::generate_thumbnail_url(id));
+    metadata: &VideoMetadata,
+    options: &DownloadOptions,
+    file: SavedFile<'_>,
+) -> Result<Option<i64>, DownloaderError> {
+    let title = metadata.title.as_str();
+    let format_type = match options.format_type.as_str() {
+        "" => "video",
+        format_type => format_type,
+    };
+    let quality = match options.quality.as_str() {
+        "" => "best",
+        quality => quality,
+    };
+
+    // Without metadata from yt-dlp, YouTube links still give away their video ID
+    let (extractor, video_id) = match &metadata.video_id {
+        Some(id) => (metadata.extractor.clone(), Some(id.clone())),
+        None => match DbDownload::extract_video_id(url) {
+            Some(id) => (Some("youtube".to_string()), Some(id)),
+            None => (None, None),
+        },
+    };
 
     // Set initial values for the download record
-    let download = DbDownload::new(
+    let mut download = DbDownload::new(
         url.to_string(),
         Some(title.to_string()),
-        filename.to_string(),
-        file_path.to_string(),
+        file.file_name.to_string(),
+        file.file_path.to_string(),
         format_type.to_string(),
         quality.to_string(),
-        Some(file_size),
-        thumbnail_url,
+        Some(file.file_size),
+        metadata.thumbnail_url.clone(),
         video_id,
-        None, // Duration not available
+        (metadata.duration_secs > 0).then(|| metadata.duration_secs as i64),
     );
+    download.extractor = extractor;
+    download.format_id = options.format_id.clone();
+    download.chapters = file.chapters.to_vec();
+    download.clips = file.clips.to_vec();
+    download.uploader = metadata.uploader.clone();
+    download.upload_date = metadata.upload_date.clone();
+    download.description = metadata.description.clone();
+    if let Some(playlist) = &options.playlist {
+        download.playlist_id = Some(playlist.playlist_id);
+        download.playlist_index = Some(playlist.index as i64);
+    }
 
     // Try to save to database
     if let Ok(pool) = get_database().await {
-        if let Err(e) = save_download(&pool, &download).await {
-            tracing::error!("Failed to save download history: {}", e);
-            return Err(ServerFnError::<NoCustomError>::ServerError(format!(
-                "Failed to save download history: {}",
-                e
-            )));
-        } else {
-            tracing::info!("Saved download history for: {}", title);
+        match save_download(&pool, &download).await {
+            Ok(id) => {
+                tracing::info!("Saved download history for: {}", title);
+                return Ok(Some(id));
+            }
+            Err(e) => {
+                tracing::error!("Failed to save download history: {}", e);
+                return Err(DownloaderError::Database(format!(
+                    "Failed to save download history: {}",
+                    e
+                )));
+            }
         }
     }
+    Ok(None)
+}
+
+/// Record the subtitles saved with a download
+///
+/// `subtitles` pairs each language with its sidecar file, or `None` when the
+/// subtitles were embedded in the media file.
+#[cfg(feature = "server")]
+pub async fn save_subtitle_info(
+    download_id: i64,
+    metadata: &VideoMetadata,
+    options: &SubtitleOptions,
+    subtitles: &[(String, Option<String>)],
+) -> Result<(), DownloaderError> {
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+
+    for (language, file_path) in subtitles {
+        // Captions are only fetched for languages without manual subtitles
+        let auto_generated =
+            options.auto_generated && !metadata.subtitle_languages.contains(language);
+        let subtitle = Subtitle::new(
+            download_id,
+            language.clone(),
+            options.format.as_str().to_string(),
+            auto_generated,
+            file_path.clone(),
+        );
+        insert_subtitle(&pool, &subtitle)
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Failed to save subtitles: {}", e)))?;
+    }
+
+    tracing::info!(
+        "Saved {} subtitles for download {}",
+        subtitles.len(),
+        download_id
+    );
     Ok(())
 }
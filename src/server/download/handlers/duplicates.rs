@@ -0,0 +1,150 @@
+use dioxus::prelude::*;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::DuplicateDownload;
+
+#[cfg(feature = "server")]
+use crate::database::{
+    get_database,
+    models::{Download, DownloadJob},
+    schema::{find_downloads_of_video, find_library_files, find_pending_jobs_for_url},
+};
+#[cfg(feature = "server")]
+use crate::server::download::youtube_url::normalize_url;
+#[cfg(feature = "server")]
+use sqlx::{Pool, Sqlite};
+#[cfg(feature = "server")]
+use std::collections::HashSet;
+
+/// Earlier downloads of a video in the same format and quality, newest first
+///
+/// Only whole-video downloads whose file is still on disk count; clips and
+/// files deleted since are left out. The download queue runs the same check
+/// before queueing, so every way of starting a download is covered.
+#[cfg(feature = "server")]
+pub async fn earlier_copies(
+    pool: &Pool<Sqlite>,
+    url: &str,
+    format_type: &str,
+    quality: &str,
+    format_id: Option<&str>,
+) -> Result<Vec<DuplicateDownload>, DownloaderError> {
+    let url = normalize_url(url);
+    let video_id = Download::extract_video_id(&url);
+    let downloads = find_downloads_of_video(
+        pool,
+        video_id.as_deref(),
+        &url,
+        format_type,
+        quality,
+        format_id,
+    )
+    .await
+    .map_err(|e| DownloaderError::Database(format!("Failed to look up downloads: {}", e)))?;
+
+    Ok(downloads
+        .into_iter()
+        .filter(|download| download.clips.is_empty() && download.file_exists())
+        .filter_map(|download| {
+            Some(DuplicateDownload {
+                download_id: download.id?,
+                title: download
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| download.filename.clone()),
+                file_name: download.filename.clone(),
+                file_path: download.file_path.clone(),
+                downloaded_at: download.format_date(),
+            })
+        })
+        .collect())
+}
+
+/// Jobs already queued or running for a video in the same format and quality
+///
+/// Like `earlier_copies`, only whole-video downloads count.
+#[cfg(feature = "server")]
+pub async fn queued_copies(
+    pool: &Pool<Sqlite>,
+    url: &str,
+    format_type: &str,
+    quality: &str,
+    format_id: Option<&str>,
+) -> Result<Vec<DownloadJob>, DownloaderError> {
+    let url = normalize_url(url);
+    let jobs = find_pending_jobs_for_url(pool, &url, format_type, quality)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to look up the queue: {}", e)))?;
+
+    Ok(jobs
+        .into_iter()
+        .filter(|job| {
+            let options = job.download_options();
+            options.clips.is_empty() && options.format_id.as_deref() == format_id
+        })
+        .collect())
+}
+
+/// Find earlier downloads of a video in the same format and quality
+#[server(FindDuplicates)]
+pub async fn find_duplicates(
+    url: String,
+    format_type: String,
+    quality: String,
+    format_id: Option<String>,
+) -> Result<Vec<DuplicateDownload>, ServerFnError<DownloaderError>> {
+    tracing::info!(
+        "Checking for earlier {} downloads of {} in {}",
+        format_type,
+        url,
+        quality
+    );
+
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let format_id = format_id
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        Ok(earlier_copies(&pool, &url, &format_type, &quality, format_id.as_deref()).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Which of the given YouTube video IDs are in the downloads library
+///
+/// Videos whose file was deleted since don't count.
+#[server(LibraryVideoIds)]
+pub async fn library_video_ids(
+    video_ids: Vec<String>,
+) -> Result<Vec<String>, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let files = find_library_files(&pool, &video_ids).await.map_err(|e| {
+            DownloaderError::Database(format!("Failed to look up downloads: {}", e))
+        })?;
+
+        let in_library: HashSet<&str> = files
+            .iter()
+            .filter(|(_, file_path)| std::path::Path::new(file_path).exists())
+            .map(|(video_id, _)| video_id.as_str())
+            .collect();
+
+        Ok(video_ids
+            .iter()
+            .filter(|video_id| in_library.contains(video_id.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
@@ -1,8 +1,9 @@
 use dioxus::prelude::*;
-use server_fn::error::NoCustomError;
+
+use crate::server::download::error::DownloaderError;
 
 /// Simple echo server function for testing
 #[server(Echo)]
-pub async fn echo(input: String) -> Result<String, ServerFnError<NoCustomError>> {
+pub async fn echo(input: String) -> Result<String, ServerFnError<DownloaderError>> {
     Ok(input)
 }
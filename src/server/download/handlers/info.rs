@@ -1,73 +1,193 @@
 use dioxus::prelude::*;
 use serde_json;
-use server_fn::error::NoCustomError;
 use std::time::Duration;
 
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::{SubtitleTrack, VideoFormats};
+
+#[cfg(feature = "server")]
+use crate::server::download::{
+    cookies, network, sites, subtitles::LIVE_CHAT, types::VideoFormat, youtube_url::normalize_url,
+};
 #[cfg(feature = "server")]
 use tokio::time::timeout;
 use tracing;
 
 #[cfg(feature = "server")]
-use youtube_dl::{YoutubeDl, YoutubeDlOutput};
+use youtube_dl::{SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+/// Fetch a video's metadata from yt-dlp, including its formats
+#[cfg(feature = "server")]
+async fn fetch_video(url: &str) -> Result<Box<SingleVideo>, DownloaderError> {
+    sites::ensure_url_allowed(url).await?;
+
+    // Set a timeout to prevent hanging indefinitely
+    let info_future = async {
+        let mut youtube_dl = YoutubeDl::new(url);
+
+        // Proxy, timeout and the rest of the network settings
+        let settings = network::load().await;
+        youtube_dl.socket_timeout(settings.socket_timeout());
+        for arg in settings.args() {
+            youtube_dl.extra_arg(arg);
+        }
+        youtube_dl.extra_arg("--no-playlist"); // Skip playlist processing
+        youtube_dl.extra_arg("--flat-playlist"); // Don't extract video info for each item
+
+        // Limit to essential fields to speed things up
+        youtube_dl.extra_arg("--write-info-json");
+        youtube_dl.extra_arg("--skip-download");
+        for arg in cookies::args_for(url).await {
+            youtube_dl.extra_arg(arg);
+        }
+
+        let output = youtube_dl.run_async().await.map_err(|e| {
+            DownloaderError::from_ytdlp_output(format!("Error fetching video info: {}", e))
+        })?;
+
+        match output {
+            YoutubeDlOutput::SingleVideo(video) => Ok(video.into()),
+            YoutubeDlOutput::Playlist(_) => Err(DownloaderError::InvalidUrl(
+                "URL points to a playlist, not a single video".to_string(),
+            )),
+        }
+    };
+
+    // Apply a timeout of 30 seconds to avoid hanging
+    match timeout(Duration::from_secs(30), info_future).await {
+        Ok(result) => result,
+        Err(_) => Err(DownloaderError::Timeout(
+            "Timed out while fetching video info".to_string(),
+        )),
+    }
+}
 
 /// Get video info without downloading
 #[server(GetVideoInfo)]
-pub async fn get_video_info(url: String) -> Result<String, ServerFnError<NoCustomError>> {
+pub async fn get_video_info(url: String) -> Result<String, ServerFnError<DownloaderError>> {
     tracing::info!("Getting video info for: {}", url);
 
     #[cfg(feature = "server")]
     {
-        // Set a timeout to prevent hanging indefinitely
-        let info_future = async {
-            let mut youtube_dl = YoutubeDl::new(&url);
-
-            // Add timeout options to make it faster
-            youtube_dl.socket_timeout("30");
-            youtube_dl.extra_arg("--no-playlist"); // Skip playlist processing
-            youtube_dl.extra_arg("--flat-playlist"); // Don't extract video info for each item
-
-            // Limit to essential fields to speed things up
-            youtube_dl.extra_arg("--write-info-json");
-            youtube_dl.extra_arg("--skip-download");
-
-            let output = youtube_dl.run_async().await.map_err(|e| {
-                ServerFnError::<NoCustomError>::ServerError(format!(
-                    "Error fetching video info: {}",
-                    e
-                ))
-            })?;
-
-            match output {
-                YoutubeDlOutput::SingleVideo(video) => Ok(video),
-                YoutubeDlOutput::Playlist(_) => Err(ServerFnError::<NoCustomError>::ServerError(
-                    "URL points to a playlist, not a single video".to_string(),
-                )),
-            }
-        };
-
-        // Apply a timeout of 30 seconds to avoid hanging
-        let result = match timeout(Duration::from_secs(30), info_future).await {
-            Ok(result) => result,
-            Err(_) => {
-                return Err(ServerFnError::<NoCustomError>::ServerError(
-                    "Timed out while fetching video info".to_string(),
-                ));
-            }
-        }?;
+        let result = fetch_video(&normalize_url(&url)).await?;
 
         // Convert the video info to JSON
         let json_str = serde_json::to_string(&result).map_err(|e| {
-            ServerFnError::<NoCustomError>::ServerError(format!(
-                "Error serializing video info: {}",
-                e
-            ))
+            DownloaderError::Internal(format!("Error serializing video info: {}", e))
         })?;
 
         Ok(json_str)
     }
 
     #[cfg(not(feature = "server"))]
-    Err(ServerFnError::<NoCustomError>::ServerError(
-        "Server feature not enabled".to_string(),
-    ))
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// List every format a video can be downloaded in
+#[server(ListFormats)]
+pub async fn list_formats(url: String) -> Result<VideoFormats, ServerFnError<DownloaderError>> {
+    tracing::info!("Listing formats for: {}", url);
+
+    #[cfg(feature = "server")]
+    {
+        let video = fetch_video(&normalize_url(&url)).await?;
+
+        // yt-dlp reports a missing stream as the codec "none"
+        let codec = |codec: &Option<String>| codec.clone().filter(|codec| codec != "none");
+        let formats: Vec<VideoFormat> = video
+            .formats
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|format| {
+                let vcodec = codec(&format.vcodec);
+                let acodec = codec(&format.acodec);
+                // Storyboards and other formats without media can't be downloaded
+                if vcodec.is_none() && acodec.is_none() {
+                    return None;
+                }
+
+                let (filesize, filesize_is_estimate) =
+                    match (format.filesize, format.filesize_approx) {
+                        (Some(size), _) => (Some(size as u64), false),
+                        (None, Some(size)) => (Some(size as u64), true),
+                        (None, None) => (None, false),
+                    };
+                let note = format.format_note.clone();
+                Some(VideoFormat {
+                    format_id: format.format_id.clone()?,
+                    ext: format.ext.clone().unwrap_or_else(|| "unknown".to_string()),
+                    width: format.width.map(|width| width as u32),
+                    height: format.height.map(|height| height as u32),
+                    fps: format.fps.map(|fps| fps as f64),
+                    hdr: note.as_deref().is_some_and(|note| note.contains("HDR")),
+                    vcodec,
+                    acodec,
+                    bitrate_kbps: format.tbr.map(|tbr| tbr as f64),
+                    filesize,
+                    filesize_is_estimate,
+                    note,
+                })
+            })
+            .collect();
+
+        if formats.is_empty() {
+            return Err(DownloaderError::NotFound(
+                "yt-dlp found no downloadable formats for this video".to_string(),
+            )
+            .into());
+        }
+
+        Ok(VideoFormats {
+            title: video.title.clone().unwrap_or_else(|| "Unknown".to_string()),
+            formats,
+        })
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// List the subtitle languages a video is available with
+///
+/// Languages with manual subtitles come first; the rest only have
+/// auto-generated captions.
+#[server(ListSubtitles)]
+pub async fn list_subtitles(
+    url: String,
+) -> Result<Vec<SubtitleTrack>, ServerFnError<DownloaderError>> {
+    tracing::info!("Listing subtitles for: {}", url);
+
+    #[cfg(feature = "server")]
+    {
+        let video = fetch_video(&normalize_url(&url)).await?;
+
+        let mut tracks: Vec<SubtitleTrack> = video
+            .subtitles
+            .iter()
+            .flat_map(|subtitles| subtitles.keys())
+            .filter(|language| language.as_str() != LIVE_CHAT)
+            .map(|language| SubtitleTrack {
+                language: language.clone(),
+                auto_generated: false,
+            })
+            .collect();
+
+        let automatic: Vec<SubtitleTrack> = video
+            .automatic_captions
+            .iter()
+            .flat_map(|captions| captions.keys())
+            .filter(|language| !tracks.iter().any(|track| &track.language == *language))
+            .map(|language| SubtitleTrack {
+                language: language.clone(),
+                auto_generated: true,
+            })
+            .collect();
+        tracks.extend(automatic);
+
+        Ok(tracks)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
 }
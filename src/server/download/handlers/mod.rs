@@ -16,10 +16,34 @@ pub use search::*;
 pub mod video;
 pub use video::*;
 
+// Download queue
+pub mod queue;
+pub use queue::*;
+
+// Playlist resolution
+pub mod playlist;
+pub use playlist::*;
+
+// Channel subscriptions
+pub mod subscription;
+pub use subscription::*;
+
+// App settings
+pub mod settings;
+pub use settings::*;
+
 // Progress tracking
 pub mod progress;
 pub use progress::*;
 
+// Earlier downloads of the same video
+pub mod duplicates;
+pub use duplicates::*;
+
+// Metadata tagging
+pub mod tags;
+pub use tags::*;
+
 // Database operations
 pub mod database;
 pub use database::*;
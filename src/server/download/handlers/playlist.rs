@@ -0,0 +1,193 @@
+use dioxus::prelude::*;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::PlaylistInfo;
+use crate::server::download::utils;
+
+#[cfg(feature = "server")]
+use crate::database::{get_database, models::Playlist, schema::insert_playlist};
+#[cfg(feature = "server")]
+use crate::server::download::{
+    cookies, job_queue, network,
+    types::{DownloadOptions, PlaylistEntry, PlaylistItem},
+};
+#[cfg(feature = "server")]
+use std::time::Duration;
+#[cfg(feature = "server")]
+use tokio::time::timeout;
+#[cfg(feature = "server")]
+use youtube_dl::{YoutubeDl, YoutubeDlOutput};
+
+/// List the videos of a playlist without downloading anything
+#[server(ResolvePlaylist)]
+pub async fn resolve_playlist(url: String) -> Result<PlaylistInfo, ServerFnError<DownloaderError>> {
+    tracing::info!("Resolving playlist: {}", url);
+
+    if !utils::is_playlist_url(&url) {
+        return Err(DownloaderError::InvalidUrl(
+            "Invalid playlist URL. Please provide a YouTube link containing list=...".to_string(),
+        )
+        .into());
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let url = url.trim().to_string();
+        let settings = network::load().await;
+        let mut youtube_dl = YoutubeDl::new(&url);
+        youtube_dl.socket_timeout(settings.socket_timeout());
+        for arg in settings.args() {
+            youtube_dl.extra_arg(arg);
+        }
+        // Only list the entries; each job fetches its own video info later
+        youtube_dl.extra_arg("--flat-playlist");
+        // Video links with a `list` parameter should resolve to the playlist too
+        youtube_dl.extra_arg("--yes-playlist");
+        // Private playlists need the cookies of a signed-in session
+        for arg in cookies::args_for(&url).await {
+            youtube_dl.extra_arg(arg);
+        }
+
+        // Long playlists are fetched page by page, so allow more time than for a video
+        let output = match timeout(Duration::from_secs(120), youtube_dl.run_async()).await {
+            Ok(output) => output.map_err(|e| {
+                DownloaderError::from_ytdlp_output(format!("Error resolving playlist: {}", e))
+            })?,
+            Err(_) => {
+                return Err(DownloaderError::Timeout(
+                    "Timed out while resolving the playlist".to_string(),
+                )
+                .into());
+            }
+        };
+
+        let playlist = match output {
+            YoutubeDlOutput::Playlist(playlist) => playlist,
+            YoutubeDlOutput::SingleVideo(_) => {
+                return Err(DownloaderError::InvalidUrl(
+                    "URL points to a single video, not a playlist".to_string(),
+                )
+                .into());
+            }
+        };
+
+        let entries: Vec<PlaylistEntry> = playlist
+            .entries
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(position, video)| PlaylistEntry {
+                index: position as u32 + 1,
+                url: utils::flat_entry_url(
+                    &video.id,
+                    video.webpage_url.as_deref(),
+                    video.url.as_deref(),
+                ),
+                video_id: video.id,
+                title: video.title,
+                duration: video
+                    .duration
+                    .and_then(|duration| duration.as_f64())
+                    .map(|seconds| seconds as u64),
+            })
+            .collect();
+
+        if entries.is_empty() {
+            return Err(DownloaderError::NotFound("The playlist has no videos".to_string()).into());
+        }
+
+        tracing::info!("Playlist {} has {} entries", url, entries.len());
+        Ok(PlaylistInfo {
+            url,
+            title: playlist
+                .title
+                .clone()
+                .unwrap_or_else(|| "Untitled playlist".to_string()),
+            uploader: playlist.uploader.clone(),
+            entries,
+        })
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Queue the chosen entries of a playlist as individual downloads
+///
+/// The jobs are grouped under a new `playlists` record, so their files can be
+/// named and listed by playlist. `indices` are the entries' 1-based positions;
+/// the job IDs are returned in playlist order. Entries already in the library
+/// in the same format and quality are skipped.
+#[server(EnqueuePlaylist)]
+pub async fn enqueue_playlist(
+    playlist: PlaylistInfo,
+    indices: Vec<u32>,
+    format_type: String,
+    quality: String,
+) -> Result<Vec<i64>, ServerFnError<DownloaderError>> {
+    tracing::info!(
+        "Enqueue request for {} of {} entries in playlist {}",
+        indices.len(),
+        playlist.entries.len(),
+        playlist.title
+    );
+
+    if indices.is_empty() {
+        return Err(DownloaderError::InvalidUrl(
+            "Select at least one video from the playlist".to_string(),
+        )
+        .into());
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let record = Playlist::new(
+            playlist.url.clone(),
+            playlist.title.clone(),
+            playlist.uploader.clone(),
+            playlist.entries.len() as i64,
+        );
+        let playlist_id = insert_playlist(&pool, &record)
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Failed to save playlist: {}", e)))?;
+
+        let count = playlist.entries.len() as u32;
+        let mut job_ids = Vec::new();
+        for entry in playlist
+            .entries
+            .iter()
+            .filter(|entry| indices.contains(&entry.index))
+        {
+            if !utils::is_supported_url(&entry.url) {
+                tracing::warn!("Skipping unsupported playlist entry: {}", entry.url);
+                continue;
+            }
+
+            let options = DownloadOptions::new(format_type.clone(), quality.clone()).in_playlist(
+                PlaylistItem {
+                    playlist_id,
+                    playlist_title: playlist.title.clone(),
+                    index: entry.index,
+                    count,
+                },
+            );
+            match job_queue::enqueue(entry.url.clone(), options).await {
+                Ok(job_id) => job_ids.push(job_id),
+                Err(DownloaderError::AlreadyDownloaded(message)) => {
+                    tracing::info!("Skipping playlist entry: {}", message);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(job_ids)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
@@ -1,50 +1,63 @@
 use dioxus::prelude::*;
-use serde_json;
+use futures::StreamExt;
+use server_fn::codec::{StreamingText, TextStream};
 use server_fn::error::NoCustomError;
+use tracing;
 
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::JobProgress;
 #[cfg(feature = "server")]
-use tokio::fs;
-use tracing;
+use crate::server::download::types::{DownloadProgress, JobState};
 
 #[cfg(feature = "server")]
-use crate::server::download::types::DownloadProgress;
+use crate::database::{get_database, schema::get_job_by_id};
+#[cfg(feature = "server")]
+use crate::server::download::jobs;
 
-/// Get progress information for an ongoing download
+/// Get progress information for a download job
 #[server(GetDownloadProgress)]
 pub async fn get_download_progress(
-    url: String,
-) -> Result<(u64, u64, u64, String), ServerFnError<NoCustomError>> {
-    tracing::info!("Checking progress for URL: {}", url);
+    job_id: i64,
+) -> Result<JobProgress, ServerFnError<DownloaderError>> {
+    tracing::info!("Checking progress for job: {}", job_id);
 
     #[cfg(feature = "server")]
     {
-        // Create a unique ID based on the URL to track this specific download
-        let progress_id = format!("download_{}", url.len());
-        let progress_file = std::env::temp_dir().join(format!("{}.progress", progress_id));
-
-        if progress_file.exists() {
-            match fs::read_to_string(&progress_file).await {
-                Ok(content) => match serde_json::from_str::<DownloadProgress>(&content) {
-                    Ok(progress) => {
-                        return Ok((
-                            progress.downloaded_bytes,
-                            progress.total_bytes,
-                            progress.eta_seconds,
-                            progress.status,
-                        ));
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to parse progress data: {}", e);
-                    }
-                },
-                Err(e) => {
-                    tracing::warn!("Failed to read progress file: {}", e);
-                }
-            }
+        Ok(job_snapshot(job_id).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Stream progress events for one download job as newline-delimited JSON
+///
+/// The stream starts with the job's current state and ends once the job has finished.
+/// Streaming responses only decode with the default error type, so errors are
+/// sent as their `DownloaderError` text.
+#[server(output = StreamingText)]
+pub async fn job_events(job_id: i64) -> Result<TextStream, ServerFnError<NoCustomError>> {
+    tracing::info!("Streaming progress for job: {}", job_id);
+
+    #[cfg(feature = "server")]
+    {
+        // Subscribe before reading the snapshot so no event falls in between
+        let receiver = jobs::subscribe(job_id);
+        let snapshot = job_snapshot(job_id)
+            .await
+            .map_err(|e| ServerFnError::<NoCustomError>::ServerError(e.to_string()))?;
+
+        if snapshot.state.is_finished() {
+            jobs::forget(job_id);
+            return Ok(TextStream::new(futures::stream::once(async move {
+                Ok(to_json_line(&snapshot))
+            })));
         }
 
-        // Return default values if no progress is found
-        Ok((0, 0, 0, "Initializing download...".to_string()))
+        let events = futures::stream::once(async move { snapshot })
+            .chain(broadcast_stream(receiver))
+            .map(|event| Ok(to_json_line(&event)));
+        Ok(TextStream::new(events))
     }
 
     #[cfg(not(feature = "server"))]
@@ -52,3 +65,117 @@ pub async fn get_download_progress(
         "Server feature not enabled".to_string(),
     ))
 }
+
+/// Stream progress events for every job in the queue as newline-delimited JSON
+#[server(output = StreamingText)]
+pub async fn queue_events() -> Result<TextStream, ServerFnError<NoCustomError>> {
+    #[cfg(feature = "server")]
+    {
+        let events = broadcast_stream(jobs::subscribe_all()).map(|event| Ok(to_json_line(&event)));
+        Ok(TextStream::new(events))
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(ServerFnError::<NoCustomError>::ServerError(
+        "Server feature not enabled".to_string(),
+    ))
+}
+
+/// Read a stream from `job_events` or `queue_events`, calling `on_event` for each event
+///
+/// Returns when the server closes the stream.
+pub async fn for_each_job_event(stream: TextStream, mut on_event: impl FnMut(JobProgress)) {
+    let mut stream = stream.into_inner();
+    let mut buffer = String::new();
+
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(text) => buffer.push_str(&text),
+            Err(e) => {
+                tracing::warn!("Progress stream error: {}", e);
+                break;
+            }
+        }
+
+        // Chunks don't necessarily line up with events, so only parse whole lines
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            match serde_json::from_str::<JobProgress>(line.trim()) {
+                Ok(event) => on_event(event),
+                Err(e) => tracing::warn!("Failed to parse progress event: {}", e),
+            }
+        }
+    }
+}
+
+/// Current progress of a job, from the live registry or the stored job state
+#[cfg(feature = "server")]
+async fn job_snapshot(job_id: i64) -> Result<JobProgress, DownloaderError> {
+    // Running jobs report live progress
+    if let Some(progress) = jobs::get_progress(job_id) {
+        return Ok(JobProgress {
+            job_id,
+            state: JobState::Running,
+            progress,
+        });
+    }
+
+    // Otherwise report what the queue has stored for the job
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    let job = get_job_by_id(&pool, job_id)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to load job: {}", e)))?
+        .ok_or_else(|| DownloaderError::NotFound(format!("Download job {} not found", job_id)))?;
+
+    let mut progress = DownloadProgress::default();
+    match job.status {
+        JobState::Queued => progress.status = "Waiting in queue...".to_string(),
+        JobState::Running => progress.status = "Initializing download...".to_string(),
+        JobState::Waiting => progress.status = "Waiting for the stream to start...".to_string(),
+        JobState::Paused => progress.status = "Paused".to_string(),
+        JobState::Completed => {
+            progress.percent = 100.0;
+            progress.status = "Download complete!".to_string();
+        }
+        JobState::Failed => {
+            let error = job.error.as_deref().map(DownloaderError::from_stored);
+            progress.status = format!("Error: {}", error.map(|e| e.message()).unwrap_or_default());
+        }
+        JobState::Cancelled => progress.status = "Cancelled".to_string(),
+    }
+
+    Ok(JobProgress {
+        job_id,
+        state: job.status,
+        progress,
+    })
+}
+
+/// Turn a broadcast receiver into a stream, skipping events a slow client missed
+#[cfg(feature = "server")]
+fn broadcast_stream(
+    receiver: tokio::sync::broadcast::Receiver<JobProgress>,
+) -> impl futures::Stream<Item = JobProgress> + Send {
+    use tokio::sync::broadcast::error::RecvError;
+
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Progress subscriber skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+#[cfg(feature = "server")]
+fn to_json_line(event: &JobProgress) -> String {
+    let mut line = serde_json::to_string(event).unwrap_or_default();
+    line.push('\n');
+    line
+}
@@ -0,0 +1,242 @@
+use dioxus::prelude::*;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::{DownloadOptions, DownloadedFile, QueueEntry};
+use crate::server::download::utils;
+
+#[cfg(feature = "server")]
+use crate::database::{get_database, schema::get_all_jobs};
+#[cfg(feature = "server")]
+use crate::server::download::{job_queue, live, sites};
+
+/// Add a single download to the queue and return its job ID
+///
+/// `options` come from the download form: a yt-dlp `format_id` from
+/// `list_formats`, subtitle languages from `list_subtitles`, whether to split
+/// audio into one file per chapter, clips of the video to join into one file,
+/// how to record the video if it turns out to be a live stream, a speed cap
+/// overriding the network settings, an earlier download of the video to delete
+/// once this one is saved and whether to download a video that is already in
+/// the library. Blank values are dropped; playlist items are queued through
+/// `enqueue_playlist` instead.
+#[server(EnqueueDownload)]
+pub async fn enqueue_download(
+    url: String,
+    options: DownloadOptions,
+) -> Result<i64, ServerFnError<DownloaderError>> {
+    tracing::info!(
+        "Enqueue request: URL={}, format={}",
+        url,
+        options.format_type
+    );
+
+    if !utils::is_supported_url(&url) {
+        return Err(utils::unsupported_url_error(&url).into());
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let options = DownloadOptions {
+            format_id: options
+                .format_id
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty()),
+            subtitles: options
+                .subtitles
+                .filter(|subtitles| !subtitles.languages.is_empty()),
+            rate_limit_kib: options.rate_limit_kib.filter(|rate| *rate > 0),
+            playlist: None,
+            ..options
+        };
+        Ok(job_queue::enqueue(url, options).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Add a batch of downloads to the queue, skipping blank lines, invalid URLs,
+/// blocked sites and videos already in the library
+#[server(EnqueueDownloads)]
+pub async fn enqueue_downloads(
+    urls: Vec<String>,
+    format_type: String,
+    quality: String,
+) -> Result<Vec<i64>, ServerFnError<DownloaderError>> {
+    tracing::info!("Batch enqueue request for {} URLs", urls.len());
+
+    #[cfg(feature = "server")]
+    {
+        let mut job_ids = Vec::new();
+        for url in urls.iter().map(|u| u.trim()).filter(|u| !u.is_empty()) {
+            if !utils::is_supported_url(url) {
+                tracing::warn!("Skipping unsupported URL in batch: {}", url);
+                continue;
+            }
+            if let Err(e) = sites::ensure_url_allowed(url).await {
+                tracing::warn!("Skipping URL in batch: {}", e);
+                continue;
+            }
+
+            let options = DownloadOptions::new(format_type.clone(), quality.clone());
+            match job_queue::enqueue(url.to_string(), options).await {
+                Ok(job_id) => job_ids.push(job_id),
+                Err(DownloaderError::AlreadyDownloaded(message)) => {
+                    tracing::info!("Skipping URL in batch: {}", message);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(job_ids)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// List every job in the queue, oldest first
+#[server(ListQueue)]
+pub async fn list_queue() -> Result<Vec<QueueEntry>, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        // Listing the queue also makes sure the workers are running
+        job_queue::ensure_queue_started().await;
+
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let jobs = get_all_jobs(&pool)
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Failed to load queue: {}", e)))?;
+
+        Ok(jobs.iter().map(|job| job.to_queue_entry()).collect())
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Get the number of downloads allowed to run in parallel
+#[server(GetQueueConcurrency)]
+pub async fn get_queue_concurrency() -> Result<usize, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        Ok(job_queue::concurrency().await)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Set the number of downloads allowed to run in parallel, returning the applied value
+#[server(SetQueueConcurrency)]
+pub async fn set_queue_concurrency(limit: usize) -> Result<usize, ServerFnError<DownloaderError>> {
+    tracing::info!("Setting queue concurrency to {}", limit);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(job_queue::set_concurrency(limit).await)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Remove finished jobs from the queue, along with any partial files they left
+#[server(ClearFinishedJobs)]
+pub async fn clear_finished_jobs() -> Result<u64, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        Ok(job_queue::clear_finished().await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Cancel a queued or running download
+///
+/// Returns false if the job had already finished.
+#[server(CancelDownload)]
+pub async fn cancel_download(job_id: i64) -> Result<bool, ServerFnError<DownloaderError>> {
+    tracing::info!("Cancel request for download job {}", job_id);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(job_queue::cancel(job_id).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Stop recording a live stream and save what was recorded so far
+///
+/// Returns false if the job isn't recording a live stream.
+#[server(StopRecording)]
+pub async fn stop_recording(job_id: i64) -> Result<bool, ServerFnError<DownloaderError>> {
+    tracing::info!("Stop request for recording job {}", job_id);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(live::request_stop(job_id))
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Pause a queued, waiting or running download, keeping its partial files
+///
+/// Returns false if the job is neither queued, waiting nor running.
+#[server(PauseDownload)]
+pub async fn pause_download(job_id: i64) -> Result<bool, ServerFnError<DownloaderError>> {
+    tracing::info!("Pause request for download job {}", job_id);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(job_queue::pause(job_id).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Resume a paused or failed download from where it stopped
+///
+/// Returns false if the job is neither paused nor failed.
+#[server(ResumeDownload)]
+pub async fn resume_download(job_id: i64) -> Result<bool, ServerFnError<DownloaderError>> {
+    tracing::info!("Resume request for download job {}", job_id);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(job_queue::resume(job_id).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Wait for a queued download to finish and return where its file was saved
+///
+/// The content is served separately by the file endpoint, see `utils::file_url`.
+#[server(WaitForDownload)]
+pub async fn wait_for_download(
+    job_id: i64,
+) -> Result<DownloadedFile, ServerFnError<DownloaderError>> {
+    tracing::info!("Waiting for download job {}", job_id);
+
+    #[cfg(feature = "server")]
+    {
+        let completed = job_queue::wait_for_job(job_id).await?;
+
+        tracing::info!("Job {} saved {} bytes", job_id, completed.file_size);
+        Ok(completed.into_downloaded_file()?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
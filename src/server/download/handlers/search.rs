@@ -1,8 +1,11 @@
 use dioxus::prelude::*;
 use serde_json;
-use server_fn::error::NoCustomError;
 use std::time::Duration;
 
+use crate::server::download::error::DownloaderError;
+
+#[cfg(feature = "server")]
+use crate::server::download::{cookies, network};
 #[cfg(feature = "server")]
 use tokio::time::timeout;
 use tracing;
@@ -12,7 +15,7 @@ use youtube_dl::{SearchOptions, YoutubeDl};
 
 /// Search YouTube videos
 #[server(SearchYoutube)]
-pub async fn search_youtube(query: String) -> Result<String, ServerFnError<NoCustomError>> {
+pub async fn search_youtube(query: String) -> Result<String, ServerFnError<DownloaderError>> {
     tracing::info!("Searching YouTube for: {}", query);
 
     #[cfg(feature = "server")]
@@ -23,38 +26,40 @@ pub async fn search_youtube(query: String) -> Result<String, ServerFnError<NoCus
         // Wrap the search in an async block for timeout
         let search_future = async {
             // Run the search with timeout settings
+            let settings = network::load().await;
             let mut dl = YoutubeDl::search_for(&search_options);
-            dl.socket_timeout("20"); // Set 20-second socket timeout
+            dl.socket_timeout(settings.socket_timeout());
+            for arg in settings.args() {
+                dl.extra_arg(arg);
+            }
             dl.extra_arg("--flat-playlist"); // Skip extracting detailed video info
+            for arg in cookies::args_for_host("youtube.com").await {
+                dl.extra_arg(arg);
+            }
 
-            dl.run_async().await.map_err(|e| {
-                ServerFnError::<NoCustomError>::ServerError(format!("Error searching: {}", e))
-            })
+            dl.run_async()
+                .await
+                .map_err(|e| DownloaderError::from_ytdlp_output(format!("Error searching: {}", e)))
         };
 
         // Apply a timeout of 30 seconds to avoid hanging
         let output = match timeout(Duration::from_secs(30), search_future).await {
             Ok(result) => result?,
             Err(_) => {
-                return Err(ServerFnError::<NoCustomError>::ServerError(
-                    "Search operation timed out".to_string(),
-                ));
+                return Err(
+                    DownloaderError::Timeout("Search operation timed out".to_string()).into(),
+                );
             }
         };
 
         // Convert the output to JSON
         let json_str = serde_json::to_string(&output).map_err(|e| {
-            ServerFnError::<NoCustomError>::ServerError(format!(
-                "Error serializing search results: {}",
-                e
-            ))
+            DownloaderError::Internal(format!("Error serializing search results: {}", e))
         })?;
 
         Ok(json_str)
     }
 
     #[cfg(not(feature = "server"))]
-    Err(ServerFnError::<NoCustomError>::ServerError(
-        "Server feature not enabled".to_string(),
-    ))
+    Err(DownloaderError::ServerDisabled.into())
 }
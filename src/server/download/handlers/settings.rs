@@ -0,0 +1,109 @@
+use dioxus::prelude::*;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::{CookieSite, NetworkSettings};
+
+#[cfg(feature = "server")]
+use crate::server::download::{cookies, network, sites};
+
+/// Get the domains downloads are allowed from; empty means every site
+#[server(GetSiteAllowlist)]
+pub async fn get_site_allowlist() -> Result<Vec<String>, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        Ok(sites::load_allowlist().await)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Replace the site allowlist, returning the normalised domains that were saved
+#[server(SetSiteAllowlist)]
+pub async fn set_site_allowlist(
+    domains: Vec<String>,
+) -> Result<Vec<String>, ServerFnError<DownloaderError>> {
+    tracing::info!("Updating site allowlist with {} entries", domains.len());
+
+    #[cfg(feature = "server")]
+    {
+        Ok(sites::save_allowlist(&domains).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Get the proxy, timeout and rate limit settings
+#[server(GetNetworkSettings)]
+pub async fn get_network_settings() -> Result<NetworkSettings, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        Ok(network::load().await)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Replace the network settings, returning what was saved
+#[server(SetNetworkSettings)]
+pub async fn set_network_settings(
+    settings: NetworkSettings,
+) -> Result<NetworkSettings, ServerFnError<DownloaderError>> {
+    tracing::info!("Updating network settings");
+
+    #[cfg(feature = "server")]
+    {
+        Ok(network::save(settings).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// List the sites with saved cookies
+#[server(ListCookieSites)]
+pub async fn list_cookie_sites() -> Result<Vec<CookieSite>, ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        Ok(cookies::list().await)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Import a Netscape `cookies.txt` export for a site
+///
+/// An empty `site` is worked out from the cookies themselves.
+#[server(ImportCookies)]
+pub async fn import_cookies(
+    site: String,
+    content: String,
+) -> Result<CookieSite, ServerFnError<DownloaderError>> {
+    tracing::info!("Importing cookies for {:?}", site);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(cookies::import(&site, &content).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Delete the saved cookies of a site
+#[server(RemoveCookies)]
+pub async fn remove_cookies(site: String) -> Result<(), ServerFnError<DownloaderError>> {
+    tracing::info!("Removing cookies for {}", site);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(cookies::remove(&site).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
@@ -0,0 +1,155 @@
+use dioxus::prelude::*;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::SubscriptionEntry;
+use crate::server::download::utils;
+
+#[cfg(feature = "server")]
+use crate::database::{
+    get_database,
+    schema::{delete_subscription, get_all_subscriptions, set_subscription_enabled},
+};
+#[cfg(feature = "server")]
+use crate::server::download::{
+    job_queue,
+    subscriptions::{self, YtDlpExtractor},
+};
+
+/// Start the download queue and the subscription checks
+///
+/// The app calls this once on launch so interrupted jobs resume and channels
+/// are checked without visiting the queue or subscriptions pages first.
+#[server(StartBackgroundTasks)]
+pub async fn start_background_tasks() -> Result<(), ServerFnError<DownloaderError>> {
+    #[cfg(feature = "server")]
+    {
+        job_queue::ensure_queue_started().await;
+        subscriptions::ensure_scheduler_started();
+        Ok(())
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Subscribe to a channel, downloading its future uploads with the given preset
+#[server(AddSubscription)]
+pub async fn add_subscription(
+    url: String,
+    format_type: String,
+    quality: String,
+) -> Result<SubscriptionEntry, ServerFnError<DownloaderError>> {
+    tracing::info!("Subscribe request: URL={}, format={}", url, format_type);
+
+    let Some(channel_url) = utils::channel_uploads_url(&url) else {
+        return Err(DownloaderError::InvalidUrl(
+            "Invalid channel URL. Please provide a link like youtube.com/@channel.".to_string(),
+        )
+        .into());
+    };
+
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let subscription =
+            subscriptions::subscribe(&pool, &YtDlpExtractor, channel_url, format_type, quality)
+                .await?;
+
+        subscriptions::ensure_scheduler_started();
+        Ok(subscription.to_entry())
+    }
+
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = channel_url;
+        Err(DownloaderError::ServerDisabled.into())
+    }
+}
+
+/// List every subscription, in the order they were added
+#[server(ListSubscriptions)]
+pub async fn list_subscriptions() -> Result<Vec<SubscriptionEntry>, ServerFnError<DownloaderError>>
+{
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let subscriptions = get_all_subscriptions(&pool).await.map_err(|e| {
+            DownloaderError::Database(format!("Failed to load subscriptions: {}", e))
+        })?;
+
+        Ok(subscriptions.iter().map(|s| s.to_entry()).collect())
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Turn the checks of a subscription on or off
+///
+/// Returns false if the subscription doesn't exist.
+#[server(ToggleSubscription)]
+pub async fn toggle_subscription(
+    id: i64,
+    enabled: bool,
+) -> Result<bool, ServerFnError<DownloaderError>> {
+    tracing::info!("Setting subscription {} enabled={}", id, enabled);
+
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        Ok(set_subscription_enabled(&pool, id, enabled)
+            .await
+            .map_err(|e| {
+                DownloaderError::Database(format!("Failed to update subscription: {}", e))
+            })?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Unsubscribe from a channel; downloads it already queued are kept
+///
+/// Returns false if the subscription doesn't exist.
+#[server(RemoveSubscription)]
+pub async fn remove_subscription(id: i64) -> Result<bool, ServerFnError<DownloaderError>> {
+    tracing::info!("Removing subscription {}", id);
+
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        Ok(delete_subscription(&pool, id).await.map_err(|e| {
+            DownloaderError::Database(format!("Failed to remove subscription: {}", e))
+        })?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Check every enabled subscription right away, returning the number of queued downloads
+#[server(CheckSubscriptionsNow)]
+pub async fn check_subscriptions_now() -> Result<usize, ServerFnError<DownloaderError>> {
+    tracing::info!("Checking all subscriptions");
+
+    #[cfg(feature = "server")]
+    {
+        let pool = get_database()
+            .await
+            .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+        let queue = job_queue::ensure_queue_started().await;
+        Ok(subscriptions::check_subscriptions(&pool, queue, &YtDlpExtractor, false).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
@@ -0,0 +1,22 @@
+use dioxus::prelude::*;
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+
+#[cfg(feature = "server")]
+use crate::server::download::tagging;
+
+/// Write the stored metadata and cover art into a download's file again,
+/// returning the new file size
+#[server(RetagDownload)]
+pub async fn retag_download(download_id: i64) -> Result<u64, ServerFnError<DownloaderError>> {
+    tracing::info!("Retagging download {}", download_id);
+
+    #[cfg(feature = "server")]
+    {
+        Ok(tagging::tag_download(download_id).await?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
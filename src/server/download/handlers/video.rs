@@ -1,6 +1,5 @@
 use dioxus::prelude::*;
 use serde_json;
-use server_fn::error::NoCustomError;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -9,16 +8,33 @@ use tokio::fs;
 use tracing;
 
 #[cfg(feature = "server")]
-use super::database::save_download_info;
+use super::database::{save_download_info, save_subtitle_info, SavedFile};
 #[cfg(feature = "server")]
-use crate::server::download::{storage, types::DownloadProgress, utils};
+use crate::database::{
+    get_database,
+    schema::{delete_download, delete_download_record, get_download_by_id, update_job_title},
+};
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::DownloadedFile;
+use crate::server::download::utils;
+#[cfg(feature = "server")]
+use crate::server::download::{
+    chapters, clips, cookies, job_queue, jobs, live, network,
+    retry::{self, RetryPolicy},
+    storage, subtitles, tagging,
+    types::{
+        AudioQuality, Container, DownloadOptions, DownloadProgress, FailureKind, SubtitleFormat,
+        VideoMetadata, VideoQuality, YtDlpEvent,
+    },
+    ytdlp::YtDlpDownload,
+};
 
 #[cfg(feature = "server")]
 use youtube_dl::{YoutubeDl, YoutubeDlOutput};
 
 /// Download video with highest quality
 #[server(DownloadVideo)]
-pub async fn download_video(url: String) -> Result<Vec<u8>, ServerFnError<NoCustomError>> {
+pub async fn download_video(url: String) -> Result<DownloadedFile, ServerFnError<DownloaderError>> {
     download_with_quality(url, "video".to_string(), "highest".to_string()).await
 }
 
@@ -27,7 +43,7 @@ pub async fn download_video(url: String) -> Result<Vec<u8>, ServerFnError<NoCust
 pub async fn download_with_options(
     url: String,
     audio_only: bool,
-) -> Result<Vec<u8>, ServerFnError<NoCustomError>> {
+) -> Result<DownloadedFile, ServerFnError<DownloaderError>> {
     tracing::info!(
         "Download options request: URL={}, audio_only={}",
         url,
@@ -41,12 +57,15 @@ pub async fn download_with_options(
 }
 
 /// Download with specific format_type and quality
+///
+/// The download runs through the shared queue so it respects the worker limit.
+/// The file stays on disk; fetch it from `utils::file_url` with the returned record ID.
 #[server(DownloadWithQuality)]
 pub async fn download_with_quality(
     url: String,
     format_type: String,
     quality: String,
-) -> Result<Vec<u8>, ServerFnError<NoCustomError>> {
+) -> Result<DownloadedFile, ServerFnError<DownloaderError>> {
     tracing::info!(
         "Download with format: {}, quality: {}, URL: {}",
         format_type,
@@ -55,89 +74,205 @@ pub async fn download_with_quality(
     );
 
     // Validate URL format
-    if !url.contains("youtube.com/watch?v=") && !url.contains("youtu.be/") {
-        return Err(ServerFnError::<NoCustomError>::ServerError(
-            "Invalid YouTube URL. Please provide a valid YouTube video URL.".to_string(),
-        ));
+    if !utils::is_supported_url(&url) {
+        return Err(utils::unsupported_url_error(&url).into());
     }
 
     #[cfg(feature = "server")]
     {
-        // Create a unique ID and progress file immediately
-        let progress_id = format!("download_{}", url.len());
-        let progress_file = std::env::temp_dir().join(format!("{}.progress", progress_id));
-
-        // Initialize progress file with 0% progress right away - THIS IS CRITICAL
-        let mut initial_progress = DownloadProgress::default();
-        initial_progress.status = "Initializing download...".to_string();
-        initial_progress.downloaded_bytes = 0; // Start at 0%
-        initial_progress.total_bytes = 100; // Set to 100 for percentage calculation
-        initial_progress.eta_seconds = 0;
-        if let Ok(json) = serde_json::to_string(&initial_progress) {
-            let _ = std::fs::write(&progress_file, json);
-        }
+        let options = DownloadOptions::new(format_type, quality);
+        let completed = job_queue::download_and_wait(url, options).await?;
+
+        tracing::info!("Downloaded {} bytes successfully", completed.file_size);
+        Ok(completed.into_downloaded_file()?)
+    }
+
+    #[cfg(not(feature = "server"))]
+    Err(DownloaderError::ServerDisabled.into())
+}
+
+/// Result of a finished download job
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct CompletedDownload {
+    /// Where the file was saved
+    pub file_path: String,
+    /// Filename given to the download
+    pub file_name: String,
+    /// Size of the saved file in bytes
+    pub file_size: u64,
+    /// The `downloads` record, if the database was available
+    pub download_id: Option<i64>,
+}
+
+/// How a run of a download job ended
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub enum DownloadRun {
+    /// The file was downloaded and saved
+    Completed(CompletedDownload),
+    /// The video is a scheduled stream; run the job again at this Unix time
+    Waiting { starts_at: i64 },
+}
+
+#[cfg(feature = "server")]
+impl CompletedDownload {
+    /// Convert to the DTO returned to clients, which needs the download record
+    pub fn into_downloaded_file(self) -> Result<DownloadedFile, DownloaderError> {
+        let download_id = self.download_id.ok_or_else(|| {
+            DownloaderError::Database("The download finished but could not be recorded".to_string())
+        })?;
+
+        Ok(DownloadedFile {
+            download_id,
+            file_name: self.file_name,
+            file_path: self.file_path,
+            file_size: self.file_size,
+        })
+    }
+}
+
+/// Run a queued download job to completion
+///
+/// Called by the queue workers; use `download_with_quality` or the queue
+/// server functions instead of calling this directly. `temp_dir` is the job's
+/// workspace: partial files left there by an earlier run are continued.
+#[cfg(feature = "server")]
+pub async fn perform_download(
+    job_id: i64,
+    url: String,
+    options: DownloadOptions,
+    temp_dir: PathBuf,
+) -> Result<DownloadRun, DownloaderError> {
+    let filename_template = options.filename_template().to_string();
+    let DownloadOptions {
+        format_type,
+        quality,
+        playlist,
+        format_id,
+        subtitles,
+        split_chapters,
+        clips: clip_ranges,
+        live: live_options,
+        rate_limit_kib,
+        replaces,
+        ..
+    } = options.clone();
+
+    {
+        // Report the job right away so polling clients see it
+        let mut progress = DownloadProgress::default();
+        progress.status = "Initializing download...".to_string();
+        jobs::update_progress(job_id, &progress);
 
-        // Create temporary directory for the download - this should be fast
-        let temp_dir = std::env::temp_dir().join(format!("youtube_dl_{}", std::process::id()));
+        // Create the job's workspace if this is its first run
         std::fs::create_dir_all(&temp_dir).map_err(|e| {
-            ServerFnError::<NoCustomError>::ServerError(format!(
-                "Failed to create temp directory: {}",
-                e
-            ))
+            DownloaderError::Storage(format!("Failed to create temp directory: {}", e))
         })?;
 
         let temp_dir_path = temp_dir.to_string_lossy().to_string();
-        tracing::info!("Created temp directory at {:?}", temp_dir_path);
+        tracing::info!("Using workspace at {:?}", temp_dir_path);
 
         // Store URL for background tasks
         let url_str = url.clone();
 
-        // Start a task to update the progress file periodically during initialization
-        tokio::spawn({
-            let progress_file = progress_file.clone();
-            async move {
-                // Update progress every 500ms while we initialize (show 0-5% progress)
-                for i in 0..30 {
-                    // timeout after 15 seconds
-                    let mut progress = DownloadProgress::default();
-                    let percent = ((i as f64) / 30.0 * 5.0).min(5.0) as u64; // Max 5% during init
-                    progress.downloaded_bytes = percent;
-                    progress.total_bytes = 100;
-                    progress.status = format!("Preparing download ({}s)...", i / 2);
-                    if let Ok(json) = serde_json::to_string(&progress) {
-                        let _ = std::fs::write(&progress_file, json);
-                    }
-                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                }
-            }
-        });
-
-        // Pre-configure youtube-dl with basic options common to all formats
-        let mut youtube_dl = YoutubeDl::new(&url);
+        // Pre-configure yt-dlp with basic options common to all formats
+        let mut youtube_dl = YtDlpDownload::new(&url);
         youtube_dl.output_directory(&temp_dir_path);
+        youtube_dl.extra_arg("--continue");
+        // Playlist items are queued one job per video, even when the URL has a `list=`
+        youtube_dl.extra_arg("--no-playlist");
+
+        // yt-dlp records the final path here once post-processing is done, so the
+        // result never depends on what else happens to be in the workspace
+        let output_record = temp_dir.join(storage::OUTPUT_RECORD_FILE);
+        let _ = std::fs::remove_file(&output_record);
+        // The file argument is an output template, so `%` must be escaped
+        youtube_dl.print_to_file(
+            "after_move:filepath",
+            output_record.to_string_lossy().replace('%', "%%"),
+        );
         youtube_dl.extra_arg("--verbose");
-        youtube_dl.socket_timeout("60");
 
-        // Get video info first to determine estimated size and title
+        // Proxy, timeout and speed cap from the network settings
+        let network_settings = network::load().await;
+        youtube_dl.socket_timeout(network_settings.socket_timeout());
+        for arg in network_settings.download_args(rate_limit_kib) {
+            youtube_dl.extra_arg(arg);
+        }
+
+        // Signed-in cookies for the site, for private and age-restricted videos
+        let cookie_args = cookies::args_for(&url).await;
+        for arg in &cookie_args {
+            youtube_dl.extra_arg(arg);
+        }
+
+        // Get video info first to determine the title
         // But do it in a way that doesn't block the UI
         let video_info_task = tokio::spawn({
             let url = url_str.clone();
+            let cookie_args = cookie_args.clone();
+            let network_settings = network_settings.clone();
             async move {
                 // Create a quick youtube-dl instance just for getting info
                 let mut info_dl = YoutubeDl::new(&url);
-                info_dl.socket_timeout("30");
+                info_dl.socket_timeout(network_settings.socket_timeout());
+                info_dl.extra_arg("--no-playlist");
+                for arg in network_settings.args().into_iter().chain(cookie_args) {
+                    info_dl.extra_arg(arg);
+                }
+
+                // Transient failures are retried; the rest are reported to the caller
+                let info = retry::retry(
+                    &RetryPolicy::INFO,
+                    || {
+                        let info_dl = info_dl.clone();
+                        async move {
+                            info_dl
+                                .run_async()
+                                .await
+                                .map_err(|e| DownloaderError::from_ytdlp_output(e.to_string()))
+                        }
+                    },
+                    |attempt, kind, delay| {
+                        let mut progress = DownloadProgress::default();
+                        progress.status = format!(
+                            "{} while fetching video information, retrying in {}s (attempt {} of {})",
+                            kind.label(),
+                            delay.as_secs(),
+                            attempt + 1,
+                            RetryPolicy::INFO.max_attempts
+                        );
+                        jobs::update_progress(job_id, &progress);
+                    },
+                )
+                .await;
 
                 // We only need certain fields, so let's extract those
-                match info_dl.run_async().await {
+                match info {
                     Ok(YoutubeDlOutput::SingleVideo(video)) => {
                         // Initialize result with defaults
-                        let mut title = String::from("Unknown");
-                        let mut size: u64 = 0;
-                        let mut duration_secs: u64 = 0;
+                        let mut metadata = VideoMetadata {
+                            title: String::from("Unknown"),
+                            extractor: video.extractor.clone(),
+                            video_id: Some(video.id.clone()),
+                            // Use the thumbnail the site reports rather than guessing one
+                            thumbnail_url: video.thumbnail.clone(),
+                            subtitle_languages: video
+                                .subtitles
+                                .iter()
+                                .flat_map(|subtitles| subtitles.keys().cloned())
+                                .collect(),
+                            uploader: video.uploader.clone(),
+                            upload_date: video.upload_date.clone(),
+                            description: video.description.clone(),
+                            is_live: video.is_live.unwrap_or(false),
+                            ..VideoMetadata::default()
+                        };
 
                         // Extract title
                         if let Some(video_title) = &video.title {
-                            title = video_title.clone();
+                            metadata.title = video_title.clone();
                         }
 
                         // Extract duration if available (for better progress estimation)
@@ -146,7 +281,7 @@ pub async fn download_with_quality(
                             match duration_float {
                                 serde_json::Value::Number(num) => {
                                     if let Some(n) = num.as_f64() {
-                                        duration_secs = n as u64;
+                                        metadata.duration_secs = n as u64;
                                     }
                                 }
                                 // Handle other cases gracefully
@@ -154,469 +289,563 @@ pub async fn download_with_quality(
                             }
                         }
 
+                        if let Some(video_chapters) = &video.chapters {
+                            metadata.chapters =
+                                chapters::from_info(video_chapters, metadata.duration_secs);
+                        }
+
                         // Extract estimated size
                         if let Some(formats) = video.formats {
                             for format in formats {
                                 if let Some(format_size) = format.filesize {
                                     // Make sure both values are the same type (u64)
                                     let size_u64 = format_size as u64;
-                                    if size_u64 > size {
-                                        size = size_u64;
+                                    if size_u64 > metadata.estimated_size {
+                                        metadata.estimated_size = size_u64;
                                     }
                                 }
                             }
                         }
 
-                        Some((title, size, duration_secs))
+                        Ok(Some(metadata))
                     }
-                    _ => None,
+                    Ok(_) => Ok(None),
+                    Err(e) => Err(e),
                 }
             }
         });
 
-        // Update progress to 5% - Video info fetch started
-        let mut progress = DownloadProgress::default();
-        progress.downloaded_bytes = 5;
-        progress.total_bytes = 100;
         progress.status = "Fetching video information...".to_string();
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = std::fs::write(&progress_file, json);
-        }
-
-        // Wait for video info (but don't block too long - max 10 seconds)
-        let (video_title, estimated_size, duration_secs) =
-            tokio::time::timeout(tokio::time::Duration::from_secs(10), video_info_task)
-                .await
-                .unwrap_or(Ok(None))
-                .unwrap_or(None)
-                .unwrap_or((String::from("Unknown"), 0, 0));
-
-        tracing::info!(
-            "Will download: {:?} (est. size: {}, duration: {} seconds)",
-            video_title,
-            estimated_size,
-            duration_secs
-        );
+        jobs::update_progress(job_id, &progress);
 
-        // Update progress file with title and move to 10% progress once we have video info
-        let mut progress = DownloadProgress::default();
-        progress.downloaded_bytes = 10; // 10% progress after getting video info
-        progress.total_bytes = 100;
-        progress.status = format!("Starting download: {}", video_title);
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = std::fs::write(&progress_file, json);
-        }
-
-        // Calculate approximate download size based on quality
-        let adjusted_estimated_size = if estimated_size == 0 {
-            // If we couldn't get the size, estimate based on duration and quality
-            match quality.to_lowercase().as_str() {
-                "lowest" => duration_secs * 50 * 1024, // ~50KB per second for lowest quality
-                "medium" => duration_secs * 250 * 1024, // ~250KB per second for medium quality
-                "highest" | _ => duration_secs * 500 * 1024, // ~500KB per second for highest quality
+        // Wait for video info (but don't block too long - max 30 seconds including retries)
+        let video_info_abort = video_info_task.abort_handle();
+        let video_info =
+            match tokio::time::timeout(tokio::time::Duration::from_secs(30), video_info_task).await
+            {
+                Ok(result) => result.unwrap_or(Ok(None)),
+                Err(_) => {
+                    video_info_abort.abort();
+                    Ok(None)
+                }
+            };
+
+        // Live and scheduled streams are recorded rather than downloaded. The
+        // info tells a live stream apart; a scheduled one fails it for lack of
+        // formats. Only those are checked further, so other videos cost no
+        // extra yt-dlp run
+        let maybe_live = match &video_info {
+            Ok(Some(metadata)) => metadata.is_live,
+            Ok(None) => false,
+            Err(e) => live::is_not_started_error(e),
+        };
+        let live_stream = if maybe_live {
+            match tokio::time::timeout(
+                tokio::time::Duration::from_secs(30),
+                live::probe(url_str.clone()),
+            )
+            .await
+            {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    tracing::warn!("Failed to check for a live stream: {}", e);
+                    None
+                }
+                Err(_) => {
+                    tracing::warn!("Timed out checking for a live stream");
+                    None
+                }
             }
         } else {
-            estimated_size
+            None
         };
+        // A scheduled stream goes back to the queue until it starts rather
+        // than keeping a worker busy
+        if let Some(stream) = live_stream
+            .as_ref()
+            .filter(|stream| stream.status == live::LiveStatus::Upcoming)
+        {
+            progress.status = stream.describe_wait();
+            jobs::update_progress(job_id, &progress);
+            return Ok(DownloadRun::Waiting {
+                starts_at: stream.next_check(),
+            });
+        }
 
-        // Launch a separate task to monitor the download progress by checking file size
-        let progress_file_clone = progress_file.clone();
-        let temp_dir_clone = temp_dir.clone();
-        let video_title_clone = video_title.clone();
-        let estimated_size_clone = adjusted_estimated_size.max(1024 * 1024); // Ensure at least 1MB to avoid division by zero
-
-        tokio::spawn(async move {
-            let start_time = std::time::Instant::now();
-            let mut last_size: u64 = 0;
-            let mut last_update = std::time::Instant::now();
-            let mut stalled_counter = 0;
-            let mut download_started = false;
-            let mut max_progress = 10; // Start at 10% (after getting video info)
-
-            loop {
-                // Check every 250ms for more responsive updates
-                tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
-
-                // Check if the temp directory still exists (download might have finished)
-                if !temp_dir_clone.exists() {
-                    break;
-                }
-
-                // Calculate current size of all files in the directory
-                let mut current_size: u64 = 0;
-                let mut is_processing = false;
-                let mut has_part_file = false;
-
-                // Read directory to find all files
-                if let Ok(mut entries) = fs::read_dir(&temp_dir_clone).await {
-                    while let Ok(Some(entry)) = entries.next_entry().await {
-                        let path = entry.path();
-                        if path.is_file() {
-                            // Check if file is a temporary/part file
-                            let filename = path.file_name().unwrap_or_default().to_string_lossy();
-
-                            // Detect if we're downloading (part files) or processing (media files)
-                            if filename.ends_with(".part") || filename.contains(".f") {
-                                has_part_file = true;
-                            }
-
-                            if filename.contains(".mkv")
-                                || filename.contains(".mp4")
-                                || filename.contains(".webm")
-                                || filename.contains(".mp3")
-                            {
-                                is_processing = true;
-                            }
+        // The stream as it was when it went live
+        let video_info = match &live_stream {
+            Some(stream) => Ok(Some(stream.metadata.clone())),
+            None => video_info,
+        };
 
-                            if let Ok(metadata) = fs::metadata(&path).await {
-                                current_size += metadata.len();
-                            }
-                        }
-                    }
-                } else {
-                    // Directory might be gone or inaccessible
-                    break;
+        // Downloading can't succeed if the video itself is out of reach
+        let video_info = match video_info {
+            Ok(info) => info,
+            Err(e) => match e.failure_kind() {
+                FailureKind::Unavailable
+                | FailureKind::Private
+                | FailureKind::AgeRestricted
+                | FailureKind::GeoBlocked => {
+                    tracing::error!("Video info unavailable: {}", e);
+                    return Err(e);
                 }
+                _ => {
+                    tracing::warn!("Failed to fetch video info, downloading anyway: {}", e);
+                    None
+                }
+            },
+        };
+        let metadata = video_info.unwrap_or_else(|| VideoMetadata {
+            title: String::from("Unknown"),
+            ..VideoMetadata::default()
+        });
+        let video_title = metadata.title.clone();
 
-                // Update progress
-                if last_update.elapsed().as_millis() > 250 {
-                    // Update progress status
-                    let mut progress = DownloadProgress::default();
-
-                    // Mark that download has started when we see a part file
-                    if has_part_file && !download_started {
-                        download_started = true;
-                        // Keep progress at 10% initially when download actually starts
-                        max_progress = 10;
-                    }
-
-                    // Calculate metrics based on download state
-                    if is_processing {
-                        // If we're processing (merging video and audio), set to 80-90%
-                        progress.status = "Processing video...".to_string();
-                        let process_percent = 80
-                            + (std::time::Instant::now()
-                                .duration_since(start_time)
-                                .as_secs()
-                                % 10);
-                        progress.downloaded_bytes = process_percent as u64;
-                        progress.total_bytes = 100;
-                        progress.eta_seconds = 0;
-                    } else if current_size > 0 && download_started {
-                        // Calculate download speed
-                        let elapsed_secs = start_time.elapsed().as_secs().max(1); // Avoid division by zero
-                        let download_speed = current_size as f64 / elapsed_secs as f64;
-
-                        // Calculate progress percentage between 10% and 80%
-                        let raw_percent = if estimated_size_clone > 0 {
-                            // Use min to cap at 80% for download phase
-                            ((current_size as f64 / estimated_size_clone as f64) * 70.0 + 10.0)
-                                .min(80.0)
-                        } else {
-                            // If we don't have a size estimate, use time-based estimation
-                            // Cap at 80% and ensure it doesn't decrease
-                            let time_based =
-                                ((elapsed_secs as f64).min(600.0) / 600.0 * 70.0 + 10.0).min(80.0);
-                            time_based
-                        };
-
-                        // Ensure progress never decreases and increases smoothly
-                        let percent = (raw_percent as u64).max(max_progress);
-                        max_progress = percent;
-
-                        // Set progress values
-                        progress.downloaded_bytes = percent;
-                        progress.total_bytes = 100;
-
-                        // Calculate ETA based on download speed and estimated remaining size
-                        if download_speed > 100.0 {
-                            // Only show ETA if speed is reasonable
-                            let remaining_bytes = estimated_size_clone.saturating_sub(current_size);
-                            let eta_secs = (remaining_bytes as f64 / download_speed) as u64;
-                            progress.eta_seconds = eta_secs;
-
-                            // Format ETA for display
-                            let eta_display = if eta_secs > 60 {
-                                format!("{:.1} min", eta_secs as f64 / 60.0)
-                            } else {
-                                format!("{} sec", eta_secs)
-                            };
-
-                            // Calculate and display download speed
-                            let speed_display = if download_speed > 1024.0 * 1024.0 {
-                                format!("{:.1} MB/s", download_speed / (1024.0 * 1024.0))
-                            } else {
-                                format!("{:.1} KB/s", download_speed / 1024.0)
-                            };
-
-                            progress.status = format!(
-                                "Downloading: {}% of {} ({}), ETA: {}",
-                                percent, video_title_clone, speed_display, eta_display
-                            );
-                        } else {
-                            progress.status =
-                                format!("Downloading: {}% of {}", percent, video_title_clone);
-                        }
-
-                        // Check if download is stalled
-                        if current_size == last_size && has_part_file {
-                            stalled_counter += 1;
-                            // After 10 seconds with no progress, indicate stalled download
-                            if stalled_counter >= 40 {
-                                // 10 seconds (40 * 250ms)
-                                progress.status = format!(
-                                    "Download stalled at {}% ({:.1} MB)...",
-                                    percent,
-                                    current_size as f64 / (1024.0 * 1024.0)
-                                );
-                            }
-                        } else {
-                            stalled_counter = 0;
-                            last_size = current_size;
-                        }
-                    } else {
-                        // Waiting for download to start
-                        if download_started {
-                            progress.downloaded_bytes = 10; // 10% while waiting for first bytes
-                        } else {
-                            // Gradually increase from 5% to 10% during initialization
-                            let elapsed_secs = start_time.elapsed().as_secs();
-                            progress.downloaded_bytes = 5 + (elapsed_secs.min(10) as u64).max(0);
-                            // 5-10% during waiting
-                        }
-                        progress.total_bytes = 100;
-                        progress.status = format!("Starting download of {}...", video_title_clone);
-                    }
-
-                    // Write progress to file
-                    if let Ok(json) = serde_json::to_string(&progress) {
-                        let _ = fs::write(&progress_file_clone, json).await;
-                    }
+        tracing::info!(
+            "Will download: {:?} from {} (est. size: {}, duration: {} seconds)",
+            video_title,
+            metadata.extractor.as_deref().unwrap_or("unknown site"),
+            metadata.estimated_size,
+            metadata.duration_secs
+        );
 
-                    last_update = std::time::Instant::now();
-                }
+        if let Ok(pool) = get_database().await {
+            if let Err(e) = update_job_title(&pool, job_id, &video_title).await {
+                tracing::warn!("Failed to store title for job {}: {}", job_id, e);
             }
-        });
+        }
+
+        progress.status = format!("Starting download: {}", video_title);
+        jobs::update_progress(job_id, &progress);
 
         // Configure format selection right away based on format_type and quality
         // This is fast and can be done synchronously
         match format_type.to_lowercase().as_str() {
             "audio" => {
+                let audio_quality = AudioQuality::parse(&quality);
                 youtube_dl.extract_audio(true);
-                youtube_dl.format("bestaudio");
-                youtube_dl.extra_arg("-x"); // Extract audio
-                youtube_dl.extra_arg("--audio-format"); // Specify format
-                youtube_dl.extra_arg("mp3"); // Force MP3 format
-                youtube_dl.extra_arg("--audio-quality"); // Specify quality
-                youtube_dl.extra_arg("0"); // Best quality (0=best, 9=worst)
+                youtube_dl.format(
+                    format_id
+                        .as_deref()
+                        .unwrap_or(audio_quality.format_selector()),
+                );
+                youtube_dl.extra_arg("--audio-format");
+                youtube_dl.extra_arg(audio_quality.ytdlp_audio_format());
+                if let Some(value) = audio_quality.ytdlp_audio_quality() {
+                    youtube_dl.extra_arg("--audio-quality");
+                    youtube_dl.extra_arg(value);
+                }
                 youtube_dl.output_template("audio");
-                tracing::info!("Set up audio download with highest quality (mp3 format)");
+                tracing::info!("Set up audio download as {}", audio_quality);
             }
             "video" => {
-                // Configure video quality
-                match quality.to_lowercase().as_str() {
-                    "lowest" => {
-                        youtube_dl.format("worstvideo[ext=mp4]+worstaudio[ext=m4a]/worst[ext=mp4]");
-                        tracing::info!("Set up video download with lowest quality");
-                    }
-                    "medium" => {
-                        youtube_dl.format("bestvideo[height<=720][ext=mp4]+bestaudio[ext=m4a]/best[height<=720][ext=mp4]");
-                        tracing::info!("Set up video download with medium quality (720p)");
+                let video_quality = VideoQuality::parse(&quality);
+                match &format_id {
+                    // A format picked from the video's format list wins over the quality
+                    Some(format_id) => {
+                        youtube_dl.format(format_id);
+                        tracing::info!("Set up video download with format {}", format_id);
                     }
-                    "highest" | _ => {
-                        youtube_dl.format("bestvideo[ext=mp4]+bestaudio[ext=m4a]/best[ext=mp4]");
-                        tracing::info!("Set up video download with highest quality");
+                    // Preferences sort the formats rather than filter them, so
+                    // sites without the preferred codec or container still work
+                    None => {
+                        let sort_order = video_quality.sort_order();
+                        youtube_dl.format(video_quality.format_selector());
+                        youtube_dl.extra_arg("-S");
+                        youtube_dl.extra_arg(&sort_order);
+                        tracing::info!(
+                            "Set up video download with quality {} (sorted by {})",
+                            video_quality,
+                            sort_order
+                        );
                     }
                 }
+                youtube_dl.extra_arg("--merge-output-format");
+                youtube_dl.extra_arg(video_quality.container().as_str());
                 youtube_dl.output_template("video");
             }
             _ => {
-                return Err(ServerFnError::<NoCustomError>::ServerError(
+                return Err(DownloaderError::InvalidUrl(
                     "Invalid format type. Please specify 'audio' or 'video'.".to_string(),
                 ));
             }
         }
 
-        // Update progress to 15% - Download about to start
-        let mut progress = DownloadProgress::default();
-        progress.downloaded_bytes = 15;
-        progress.total_bytes = 100;
-        progress.status = format!("Starting download: {}", video_title);
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = std::fs::write(&progress_file, json);
+        // Keep the video's chapters as container chapters
+        youtube_dl.extra_arg("--embed-chapters");
+
+        // Subtitles and clips only apply to regular downloads. Subtitles are
+        // only embedded in videos, and WebM only holds WebVTT
+        let is_video = format_type.eq_ignore_ascii_case("video");
+        let subtitles = subtitles
+            .filter(|_| live_stream.is_none())
+            .map(|mut options| {
+                options.embed &= is_video;
+                if options.embed && VideoQuality::parse(&quality).container() == Container::Webm {
+                    options.format = SubtitleFormat::Vtt;
+                }
+                options
+            });
+        if let Some(options) = &subtitles {
+            subtitles::configure(&mut youtube_dl, options, options.embed);
+            tracing::info!(
+                "Fetching {} subtitles for {}",
+                options.format.as_str(),
+                options.languages.join(", ")
+            );
         }
 
-        // Execute the download
-        tracing::info!("Starting download with yt-dlp...");
-        match youtube_dl.download_to_async(&temp_dir).await {
-            Ok(()) => tracing::info!("Download completed successfully"),
-            Err(e) => {
-                tracing::error!("Download error: {}", e);
+        let clip_ranges = if live_stream.is_some() {
+            Vec::new()
+        } else {
+            clip_ranges
+        };
 
-                // Update progress file with error
-                let mut progress = DownloadProgress::default();
-                progress.status = format!("Error: {}", e);
-                progress.downloaded_bytes = 100; // Set to 100% to indicate we're done (with error)
-                progress.total_bytes = 100;
-                if let Ok(json) = serde_json::to_string(&progress) {
-                    let _ = fs::write(&progress_file, json).await;
-                }
+        // Clips only fetch the requested parts of the video
+        if !clip_ranges.is_empty() {
+            clips::configure(
+                &mut youtube_dl,
+                &clip_ranges,
+                if is_video { "video" } else { "audio" },
+            );
+            tracing::info!(
+                "Downloading clips {}",
+                clip_ranges
+                    .iter()
+                    .map(|clip| clip.describe())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
 
-                // Try to clean up the temp directory
-                let _ = fs::remove_dir_all(&temp_dir).await;
-                return Err(ServerFnError::<NoCustomError>::ServerError(format!(
-                    "Download failed: {}",
-                    e
-                )));
+        // Live streams are recorded until they end, hit the maximum duration or
+        // are stopped by hand
+        let downloaded_file = if live_stream.is_some() {
+            progress.status = format!("Recording: {}", video_title);
+            progress.recording_secs = Some(0);
+            jobs::update_progress(job_id, &progress);
+
+            let recording = match network_settings.ffmpeg_args(rate_limit_kib) {
+                Ok(network_args) => {
+                    live::record(
+                        job_id,
+                        &url,
+                        &live_options,
+                        !is_video,
+                        &network_args,
+                        &temp_dir,
+                        |secs, bytes| {
+                            progress.recording_secs = Some(secs);
+                            progress.downloaded_bytes = bytes;
+                            jobs::update_progress(job_id, &progress);
+                        },
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            progress.recording_secs = None;
+            if let Err(e) = &recording {
+                progress.status = format!("Error: {}", e.message());
+                jobs::update_progress(job_id, &progress);
             }
-        }
+            recording?
+        } else {
+            // Execute the download, publishing every progress line yt-dlp prints.
+            // Transient failures are retried; `--continue` picks up the partial files.
+            tracing::info!("Starting download with yt-dlp...");
+            let policy = RetryPolicy::DOWNLOAD;
+            let mut attempt = 1;
+            let download_result = loop {
+                progress.stream_index = 0;
+                let result = youtube_dl
+                    .run(|event| {
+                        match event {
+                            YtDlpEvent::Destination(path) => {
+                                progress.stream_index += 1;
+                                progress.stream = std::path::Path::new(&path)
+                                    .file_name()
+                                    .map(|name| name.to_string_lossy().to_string());
+                                progress.downloaded_bytes = 0;
+                                progress.percent = 0.0;
+                                progress.fragment_index = None;
+                                progress.fragment_count = None;
+                            }
+                            YtDlpEvent::Progress(event) => {
+                                progress.percent = event.percent;
+                                progress.downloaded_bytes = event.downloaded_bytes;
+                                progress.total_bytes = event.total_bytes;
+                                progress.speed_bytes_per_sec =
+                                    event.speed_bytes_per_sec.unwrap_or(0);
+                                progress.eta_seconds = event.eta_seconds.unwrap_or(0);
+                                progress.fragment_index = event.fragment_index;
+                                progress.fragment_count = event.fragment_count;
+                                progress.status = format!("Downloading: {}", video_title);
+                            }
+                            YtDlpEvent::PostProcessing(step) => {
+                                progress.percent = 100.0;
+                                progress.eta_seconds = 0;
+                                progress.status = match step.as_str() {
+                                    "Merger" => "Merging video and audio...".to_string(),
+                                    "ExtractAudio" => "Extracting audio...".to_string(),
+                                    _ => "Processing video...".to_string(),
+                                };
+                            }
+                        }
+                        jobs::update_progress(job_id, &progress);
+                    })
+                    .await;
+
+                let Err(e) = result else {
+                    break Ok(());
+                };
+                let kind = e.failure_kind();
+                let Some(delay) = policy.retry_delay(attempt, kind) else {
+                    break Err(e);
+                };
+
+                tracing::warn!(
+                    "Download attempt {} of {} failed ({}), retrying in {:.1}s: {}",
+                    attempt,
+                    policy.max_attempts,
+                    kind.label(),
+                    delay.as_secs_f64(),
+                    e.message()
+                );
+                progress.speed_bytes_per_sec = 0;
+                progress.eta_seconds = 0;
+                progress.status = format!(
+                    "{}, retrying in {}s (attempt {} of {})",
+                    kind.label(),
+                    delay.as_secs(),
+                    attempt + 1,
+                    policy.max_attempts
+                );
+                jobs::update_progress(job_id, &progress);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            };
 
-        // Find the downloaded file
-        tracing::info!("Looking for downloaded file in {:?}", temp_dir);
-        let downloaded_file = utils::find_downloaded_file(&temp_dir).await.map_err(|e| {
-            ServerFnError::<NoCustomError>::ServerError(format!(
-                "Failed to find downloaded file: {}",
-                e
-            ))
-        })?;
+            if let Err(e) = download_result {
+                tracing::error!("Download error: {}", e);
 
-        tracing::info!("Found downloaded file: {}", downloaded_file.display());
+                // Report the error in the job progress
+                progress.status = format!("Error: {}", e.message());
+                jobs::update_progress(job_id, &progress);
 
-        // Update progress file with completion status - 90%
-        let mut progress = DownloadProgress::default();
-        progress.status = "Download complete, preparing file...".to_string();
-        progress.downloaded_bytes = 90;
-        progress.total_bytes = 100;
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = fs::write(&progress_file, json).await;
-        }
+                // Keep the partial files so the job can be resumed
+                return Err(e);
+            }
+            tracing::info!("Download completed successfully");
 
-        // Read the file content
-        tracing::info!("Reading file content");
-        let content = fs::read(&downloaded_file).await.map_err(|e| {
-            ServerFnError::<NoCustomError>::ServerError(format!(
-                "Failed to read downloaded file: {}",
-                e
-            ))
-        })?;
+            // Use the file yt-dlp reported rather than scanning the workspace.
+            // Several clip ranges come back as a file per range, joined into one.
+            if clip_ranges.len() > 1 {
+                let parts = utils::read_reported_outputs(&output_record)
+                    .await
+                    .map_err(|e| {
+                        DownloaderError::Storage(format!("Failed to find downloaded clips: {}", e))
+                    })?;
+                progress.status = format!("Joining {} clips...", parts.len());
+                jobs::update_progress(job_id, &progress);
+                clips::join(&parts).await?
+            } else {
+                utils::read_reported_output(&output_record)
+                    .await
+                    .map_err(|e| {
+                        DownloaderError::Storage(format!("Failed to find downloaded file: {}", e))
+                    })?
+            }
+        };
 
-        // Update progress file - 95%
-        progress.status = "Saving file to permanent location...".to_string();
-        progress.downloaded_bytes = 95;
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = std::fs::write(&progress_file, json);
-        }
+        // The transfer is done; the remaining steps only move the file
+        progress.percent = 100.0;
+        progress.eta_seconds = 0;
+        progress.speed_bytes_per_sec = 0;
+        progress.status = "Download complete, preparing file...".to_string();
+        jobs::update_progress(job_id, &progress);
 
-        // Create a permanent path for database record
-        let mut file_path_for_db = downloaded_file.to_string_lossy().to_string();
+        progress.status = "Saving file to permanent location...".to_string();
+        jobs::update_progress(job_id, &progress);
 
-        // Save to a permanent location with proper permissions
+        // Move the file into place; it is never read into memory
         let extension = downloaded_file
             .extension()
             .unwrap_or_default()
-            .to_string_lossy();
-        let file_name = storage::create_clean_filename(&video_title, &extension);
-        let mut saved_to_permanent = false;
+            .to_string_lossy()
+            .to_string();
+        // Clips carry their ranges in the name, so clips of one video don't clash
+        let file_title = clips::clip_title(&video_title, &clip_ranges);
+        let relative_path = storage::render_filename(
+            &filename_template,
+            &file_title,
+            playlist.as_ref(),
+            &extension,
+        );
+        let clean_name = relative_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| storage::create_clean_filename(&file_title, &extension));
+        let media_dir = storage::media_destination().ok_or_else(|| {
+            DownloaderError::Storage("No folder available to save the download to".to_string())
+        })?;
+        // Templates may put the file in a subfolder, e.g. one per playlist
+        let destination = match relative_path.parent() {
+            Some(folder) => media_dir.join(folder),
+            None => media_dir,
+        };
 
-        // For desktop apps, save in Documents folder next to database
-        #[cfg(feature = "desktop")]
-        {
-            if let Some(media_dir) = storage::ensure_media_directory() {
-                let permanent_path = media_dir.join(&file_name);
-
-                // Try to save the file with proper permissions
-                if storage::save_file_with_permissions(&permanent_path, &content) {
-                    tracing::info!("Media file saved to: {}", permanent_path.display());
-                    file_path_for_db = permanent_path.to_string_lossy().to_string();
-                    saved_to_permanent = true;
+        let saved_path =
+            storage::move_file_with_permissions(&downloaded_file, &destination, &clean_name)
+                .await
+                .map_err(|e| {
+                    DownloaderError::Storage(format!("Failed to save downloaded file: {}", e))
+                })?;
+        tracing::info!("Media file saved to: {}", saved_path.display());
+
+        // Sidecar subtitles go next to the media; embedded ones only need recording
+        let saved_subtitles: Vec<(String, Option<String>)> = match &subtitles {
+            Some(options) => {
+                let files = subtitles::find_in_workspace(&temp_dir, options.format);
+                if files.is_empty() {
+                    tracing::warn!("yt-dlp found none of the requested subtitles");
+                }
+                if options.embed {
+                    files
+                        .into_iter()
+                        .map(|(language, _)| (language, None))
+                        .collect()
+                } else {
+                    subtitles::save_sidecars(files, &saved_path)
+                        .await
+                        .into_iter()
+                        .map(|(language, path)| {
+                            (language, Some(path.to_string_lossy().to_string()))
+                        })
+                        .collect()
                 }
             }
-        }
+            None => Vec::new(),
+        };
 
-        // Also save to Downloads folder for convenience
-        if let Some(download_dir) = dirs::download_dir() {
-            let download_path = download_dir.join(&file_name);
-            if storage::save_file_with_permissions(&download_path, &content) {
-                tracing::info!(
-                    "Copy saved to Downloads folder: {}",
-                    download_path.display()
-                );
-                if !saved_to_permanent {
-                    file_path_for_db = download_path.to_string_lossy().to_string();
-                    saved_to_permanent = true;
-                }
+        let file_path = saved_path.to_string_lossy().to_string();
+        let file_name = saved_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(clean_name);
+        let mut file_size = fs::metadata(&saved_path)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+
+        // Long audio such as albums can be split into a file per chapter.
+        // The video's chapters don't line up with a clip, so clips have none.
+        let mut video_chapters = if clip_ranges.is_empty() {
+            metadata.chapters.clone()
+        } else {
+            Vec::new()
+        };
+        if split_chapters && !is_video && !video_chapters.is_empty() {
+            progress.status = format!("Splitting into {} chapters...", video_chapters.len());
+            jobs::update_progress(job_id, &progress);
+
+            match chapters::split_audio(&saved_path, &video_chapters).await {
+                Ok(split) => video_chapters = split,
+                Err(e) => tracing::warn!("Failed to split audio by chapter: {}", e),
             }
         }
 
-        // Update progress file - 99%
         progress.status = "Finalizing...".to_string();
-        progress.downloaded_bytes = 99;
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = std::fs::write(&progress_file, json);
-        }
+        jobs::update_progress(job_id, &progress);
 
         // Always clean up temporary files
         tracing::info!("Cleaning up temporary files");
         let _ = fs::remove_dir_all(&temp_dir).await;
 
-        // Set progress to 100% before removing the progress file
         progress.status = "Download complete!".to_string();
-        progress.downloaded_bytes = 100;
-        progress.total_bytes = 100;
-        if let Ok(json) = serde_json::to_string(&progress) {
-            let _ = std::fs::write(&progress_file, json);
-        }
+        jobs::update_progress(job_id, &progress);
+
+        // Save download info to database so the job can link to the record
+        let download_id = save_download_info(
+            &url,
+            &metadata,
+            &options,
+            SavedFile {
+                file_name: &file_name,
+                file_path: &file_path,
+                file_size: file_size as i64,
+                chapters: &video_chapters,
+                clips: &clip_ranges,
+            },
+        )
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Database error: {}", e);
+            None
+        });
 
-        // Keep the progress file around briefly to ensure UI can read the 100% completion
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        let _ = fs::remove_file(&progress_file).await;
+        // The copy being replaced only goes once the new one is saved and recorded
+        if let Some(old_id) = replaces {
+            match download_id {
+                Some(_) => remove_replaced_download(old_id, &file_path).await,
+                None => tracing::warn!(
+                    "Keeping download {} as the new copy wasn't recorded",
+                    old_id
+                ),
+            }
+        }
 
-        // Get file size
-        let file_size = content.len() as i64;
+        if let (Some(download_id), Some(options)) = (download_id, &subtitles) {
+            if let Err(e) =
+                save_subtitle_info(download_id, &metadata, options, &saved_subtitles).await
+            {
+                tracing::error!("Database error: {}", e);
+            }
+        }
 
-        // Save download info to database in the background
-        #[cfg(feature = "server")]
-        {
-            let url = url.clone();
-            let video_title = video_title.clone();
-            let file_name = file_name.clone();
-            let file_path_for_db = file_path_for_db.clone();
-            let format_type = format_type.clone();
-            let quality = quality.clone();
-
-            tokio::spawn(async move {
-                if let Err(e) = save_download_info(
-                    &url,
-                    &video_title,
-                    &file_name,
-                    &file_path_for_db,
-                    &if format_type.is_empty() {
-                        "video".to_string()
-                    } else {
-                        format_type
-                    },
-                    &if quality.is_empty() {
-                        "best".to_string()
-                    } else {
-                        quality
-                    },
-                    file_size,
-                )
-                .await
-                {
-                    tracing::error!("Database error: {}", e);
-                }
-            });
+        // Tags come from the stored record so they can be written again later
+        if let Some(download_id) = download_id {
+            match tagging::tag_download(download_id).await {
+                Ok(tagged_size) => file_size = tagged_size,
+                Err(e) => tracing::warn!("Failed to tag {}: {}", file_path, e),
+            }
         }
 
-        tracing::info!("Downloaded {} bytes successfully", content.len());
-        Ok(content)
+        tracing::info!("Job {} downloaded {} bytes successfully", job_id, file_size);
+        Ok(DownloadRun::Completed(CompletedDownload {
+            file_path,
+            file_name,
+            file_size,
+            download_id,
+        }))
     }
+}
 
-    #[cfg(not(feature = "server"))]
-    Err(ServerFnError::<NoCustomError>::ServerError(
-        "Server feature not enabled".to_string(),
-    ))
+/// Delete a download that a new copy replaces
+///
+/// If the old file was already gone, the new copy took its path, so only the
+/// old record is deleted.
+#[cfg(feature = "server")]
+async fn remove_replaced_download(old_id: i64, new_path: &str) {
+    let pool = match get_database().await {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!("Database unavailable, keeping download {}: {}", old_id, e);
+            return;
+        }
+    };
+
+    let result = match get_download_by_id(&pool, old_id).await {
+        Ok(Some(old)) if old.file_path == new_path => delete_download_record(&pool, old_id).await,
+        Ok(Some(_)) => delete_download(&pool, old_id).await,
+        Ok(None) => Ok(false),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(true) => tracing::info!("Replaced download {}", old_id),
+        Ok(false) => tracing::warn!("Download {} to replace was already gone", old_id),
+        Err(e) => tracing::error!("Failed to delete download {}: {}", old_id, e),
+    }
 }
 
 #[cfg(feature = "server")]
@@ -626,7 +855,7 @@ pub async fn download_video_with_progress(
     quality: Option<String>,
     path: Option<String>,
     set_progress: impl Fn(f32, Option<String>, Option<String>) + Clone + Send + 'static,
-) -> Result<String, String> {
+) -> Result<String, DownloaderError> {
     // Immediately update progress to show user something is happening - 0%
     set_progress(0.0, Some("Fetching video information...".to_string()), None);
 
@@ -634,13 +863,11 @@ pub async fn download_video_with_progress(
     let video_info_future = super::info::get_video_info(url.clone());
 
     // First fetch video information
-    let video_info = video_info_future
-        .await
-        .map_err(|e| format!("Error fetching video info: {}", e))?;
+    let video_info = video_info_future.await?;
 
     // Parse the JSON to get title and other metadata
     let video_data: serde_json::Value = serde_json::from_str(&video_info)
-        .map_err(|e| format!("Error parsing video info: {}", e))?;
+        .map_err(|e| DownloaderError::Internal(format!("Error parsing video info: {}", e)))?;
 
     let title = video_data
         .get("title")
@@ -654,170 +881,43 @@ pub async fn download_video_with_progress(
     // Start the video download - 10%
     set_progress(0.1, Some("Preparing download...".to_string()), None);
 
-    // Use a custom progress callback that updates both our local progress tracking
-    // and the provided set_progress callback
-    struct ProgressTracker {
-        progress: f32,
-        file_exists: bool,
-    }
-
-    let progress_tracker = std::sync::Arc::new(std::sync::Mutex::new(ProgressTracker {
-        progress: 0.1, // Start at 10%
-        file_exists: false,
-    }));
-
-    // Create a clone of the progress tracker for the monitoring task
-    let progress_tracker_clone = progress_tracker.clone();
+    // Queue the download so progress can be tracked by its job ID
+    let options = DownloadOptions::new(
+        format.unwrap_or_else(|| "video".to_string()),
+        quality.unwrap_or_else(|| "highest".to_string()),
+    );
+    let job_id = job_queue::enqueue(url.clone(), options).await?;
 
-    // Set up a task to update progress based on the progress file
-    let url_len = url.len();
+    // Forward the job's progress to the callback until the download finishes
     let set_progress_clone = set_progress.clone();
     let progress_updater = tokio::spawn(async move {
-        let progress_file = std::env::temp_dir().join(format!("download_{}.progress", url_len));
-
         // Check progress every 250ms
         loop {
             tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
 
-            if progress_file.exists() {
-                if let Ok(content) = std::fs::read_to_string(&progress_file) {
-                    if let Ok(progress) = serde_json::from_str::<DownloadProgress>(&content) {
-                        let mut tracker = progress_tracker_clone.lock().unwrap();
-
-                        // Convert to 0.0-1.0 range and ensure it never decreases
-                        let new_progress = (progress.downloaded_bytes as f32
-                            / progress.total_bytes as f32)
-                            .max(tracker.progress);
-
-                        // Update tracker
-                        tracker.progress = new_progress;
-
-                        // Pass progress and status to the callback
-                        set_progress_clone(new_progress, Some(progress.status), None);
-
-                        // Mark file as exists if we're past 50%
-                        if new_progress > 0.5 {
-                            tracker.file_exists = true;
-                        }
-                    }
-                }
-            } else if progress_tracker_clone.lock().unwrap().file_exists {
-                // Progress file gone but we already saw good progress
-                set_progress_clone(0.99, Some("Finalizing download...".to_string()), None);
-                break;
-            } else {
-                // Progress file doesn't exist yet, continue checking
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            if let Some(progress) = jobs::get_progress(job_id) {
+                // Pass the current stream's progress (0.0-1.0) and status to the callback
+                set_progress_clone(
+                    (progress.percent / 100.0) as f32,
+                    Some(progress.status),
+                    None,
+                );
             }
         }
     });
 
-    // Now actually perform the download
-    let download_future = download_with_quality(
-        url.clone(),
-        format.clone().unwrap_or_else(|| "video".to_string()),
-        quality.clone().unwrap_or_else(|| "highest".to_string()),
-    );
-    let result = download_future.await;
-
-    // Try to wait for progress updater to finish or timeout after 2 seconds
-    let _ = tokio::time::timeout(std::time::Duration::from_secs(2), progress_updater).await;
+    // Now wait for a worker to finish the download
+    let result = job_queue::wait_for_job(job_id).await;
+    progress_updater.abort();
 
     match result {
-        Ok(content) => {
-            // For database, we need to save some metadata
-            set_progress(0.95, Some("Saving video file...".to_string()), None);
-
-            // Since we have the content as Vec<u8>, we need to save it to a file first
-            let temp_file = std::env::temp_dir().join(format!(
-                "{}_{}.mp4",
-                title.replace(" ", "_"),
-                chrono::Utc::now().timestamp()
-            ));
-
-            let file_path = temp_file.to_string_lossy().to_string();
-            let filename = temp_file
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            // Create a permanent path for database record
-            let mut file_path_for_db = file_path.clone();
-
-            // Save to a permanent location with proper permissions
-            let file_name = storage::create_clean_filename(&title, "mp4");
-            let mut saved_to_permanent = false;
-
-            // For desktop apps, save in Documents folder next to database
-            #[cfg(feature = "desktop")]
-            {
-                if let Some(media_dir) = storage::ensure_media_directory() {
-                    let permanent_path = media_dir.join(&file_name);
-
-                    // Try to save the file with proper permissions
-                    if storage::save_file_with_permissions(&permanent_path, &content) {
-                        tracing::info!("Media file saved to: {}", permanent_path.display());
-                        file_path_for_db = permanent_path.to_string_lossy().to_string();
-                        saved_to_permanent = true;
-                    }
-                }
-            }
-
-            // Also save to Downloads folder for convenience
-            if let Some(download_dir) = dirs::download_dir() {
-                let download_path = download_dir.join(&file_name);
-                if storage::save_file_with_permissions(&download_path, &content) {
-                    tracing::info!(
-                        "Copy saved to Downloads folder: {}",
-                        download_path.display()
-                    );
-                    if !saved_to_permanent {
-                        file_path_for_db = download_path.to_string_lossy().to_string();
-                        saved_to_permanent = true;
-                    }
-                }
-            }
-
-            // Get file size
-            let file_size = content.len() as i64;
-
-            // Update progress to 99%
-            set_progress(0.99, Some("Updating database...".to_string()), None);
-
-            // Save download info to database (in background)
-            #[cfg(feature = "server")]
-            {
-                let url = url.clone();
-                let title = title.clone();
-                let filename = filename.clone();
-                let file_path_for_db = file_path_for_db.clone();
-                let format = format.clone();
-                let quality = quality.clone();
-
-                tokio::spawn(async move {
-                    if let Err(e) = save_download_info(
-                        &url,
-                        &title,
-                        &filename,
-                        &file_path_for_db,
-                        &format.clone().unwrap_or_else(|| "video".to_string()),
-                        &quality.clone().unwrap_or_else(|| "best".to_string()),
-                        file_size,
-                    )
-                    .await
-                    {
-                        tracing::error!("Database error: {}", e);
-                    }
-                });
-            }
-
+        Ok(completed) => {
             set_progress(1.0, Some("Download complete!".to_string()), None);
-            Ok(file_path)
+            Ok(completed.file_path)
         }
         Err(e) => {
-            set_progress(1.0, Some(format!("Error: {}", e)), None);
-            Err(format!("Download error: {}", e))
+            set_progress(1.0, Some(format!("Error: {}", e.message())), None);
+            Err(e)
         }
     }
 }
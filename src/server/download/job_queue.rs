@@ -0,0 +1,804 @@
+// Background download queue
+//
+// Jobs are persisted in the `download_jobs` table and picked up by a single
+// dispatcher task that keeps at most `concurrency` downloads running at once.
+// Each job downloads into its own workspace directory, which is kept while the
+// job is paused or failed so a later run can continue the partial files.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use tokio::sync::{oneshot, Notify};
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing;
+
+use crate::database::{
+    get_database,
+    models::DownloadJob,
+    schema::{
+        claim_next_queued_job, complete_job, delete_finished_jobs, get_all_jobs,
+        get_download_by_id, get_job_by_id, get_setting, insert_job, requeue_interrupted_jobs,
+        set_job_waiting, set_job_workspace, set_setting, transition_job, update_job_status,
+    },
+};
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::duplicates::{earlier_copies, queued_copies};
+use crate::server::download::handlers::video::{perform_download, CompletedDownload, DownloadRun};
+use crate::server::download::jobs;
+use crate::server::download::sites;
+use crate::server::download::storage::{job_workspace, workspaces_root};
+use crate::server::download::types::{DownloadOptions, DownloadProgress, JobState};
+use crate::server::download::youtube_url;
+
+/// Number of downloads that run in parallel unless the user changes it
+pub const DEFAULT_CONCURRENCY: usize = 2;
+/// Upper bound for the worker pool size
+pub const MAX_CONCURRENCY: usize = 8;
+
+const CONCURRENCY_SETTING: &str = "queue.concurrency";
+
+/// Outcome delivered to callers waiting on a job
+type JobOutcome = Result<CompletedDownload, DownloaderError>;
+
+/// Shared queue state used by the dispatcher and the enqueue functions
+pub struct DownloadQueue {
+    concurrency: AtomicUsize,
+    running: AtomicUsize,
+    wake: Notify,
+    waiters: Mutex<HashMap<i64, Vec<oneshot::Sender<JobOutcome>>>>,
+    /// Jobs the dispatcher has claimed, with their download task once it started
+    active: Mutex<HashMap<i64, Option<AbortHandle>>>,
+    /// State requested for claimed jobs that are being stopped
+    stop_requests: Mutex<HashMap<i64, JobState>>,
+    /// Held while the dispatcher claims a job, so stops find every claimed job in `active`
+    claiming: tokio::sync::Mutex<()>,
+}
+
+impl DownloadQueue {
+    /// A queue with no dispatcher running its jobs
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency: AtomicUsize::new(concurrency),
+            running: AtomicUsize::new(0),
+            wake: Notify::new(),
+            waiters: Mutex::new(HashMap::new()),
+            active: Mutex::new(HashMap::new()),
+            stop_requests: Mutex::new(HashMap::new()),
+            claiming: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Start the download task of a claimed job
+    ///
+    /// Returns `None` without starting it if a stop came in while the job was
+    /// being claimed.
+    fn start_task<T>(
+        &self,
+        job_id: i64,
+        spawn: impl FnOnce() -> JoinHandle<T>,
+    ) -> Option<JoinHandle<T>> {
+        let mut active = self.active.lock().unwrap();
+        if self.stop_requests.lock().unwrap().contains_key(&job_id) {
+            return None;
+        }
+        let task = spawn();
+        active.insert(job_id, Some(task.abort_handle()));
+        Some(task)
+    }
+
+    /// Forget a job whose run has ended, returning the state a stop asked for
+    ///
+    /// Stops are only recorded for jobs in `active`, so none outlives the run.
+    fn end_run(&self, job_id: i64) -> Option<JobState> {
+        let mut active = self.active.lock().unwrap();
+        active.remove(&job_id);
+        self.stop_requests.lock().unwrap().remove(&job_id)
+    }
+
+    /// Record a stop for a claimed job and abort its task if it has one
+    ///
+    /// Returns false if the job isn't claimed or its task has already finished.
+    fn request_stop(&self, job_id: i64, state: JobState) -> bool {
+        let active = self.active.lock().unwrap();
+        let task = match active.get(&job_id) {
+            Some(task) if !task.as_ref().is_some_and(AbortHandle::is_finished) => task,
+            _ => return false,
+        };
+
+        tracing::info!(
+            "Stopping running download job {} ({})",
+            job_id,
+            state.as_str()
+        );
+        self.stop_requests.lock().unwrap().insert(job_id, state);
+        if let Some(task) = task {
+            task.abort();
+        }
+        true
+    }
+}
+
+static QUEUE: OnceLock<DownloadQueue> = OnceLock::new();
+
+/// Get the shared queue, starting the dispatcher on first use
+pub async fn ensure_queue_started() -> &'static DownloadQueue {
+    let mut first_use = false;
+    let queue = QUEUE.get_or_init(|| {
+        first_use = true;
+        DownloadQueue::new(DEFAULT_CONCURRENCY)
+    });
+
+    if first_use {
+        start_dispatcher(queue).await;
+    }
+
+    queue
+}
+
+/// Restore the saved settings and interrupted jobs, then start running jobs
+async fn start_dispatcher(queue: &'static DownloadQueue) {
+    if let Ok(pool) = get_database().await {
+        // Restore the saved worker limit
+        if let Ok(Some(value)) = get_setting(&pool, CONCURRENCY_SETTING).await {
+            if let Ok(limit) = value.parse::<usize>() {
+                queue
+                    .concurrency
+                    .store(limit.clamp(1, MAX_CONCURRENCY), Ordering::SeqCst);
+            }
+        }
+
+        // Jobs that were running when the app stopped start over
+        match requeue_interrupted_jobs(&pool).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Re-queued {} interrupted download jobs", count),
+            Err(e) => tracing::error!("Failed to re-queue interrupted jobs: {}", e),
+        }
+
+        // No job is running yet, so anything not needed for a resume can go
+        sweep_stale_files(&pool).await;
+    }
+
+    tokio::spawn(dispatch_loop(queue));
+}
+
+/// Add a download to the queue and return its job ID
+///
+/// YouTube links are stored in their canonical form. Fails when the URL's
+/// site isn't on the allowlist, or with `AlreadyDownloaded` when the video is
+/// in the library or the queue in the same format and quality, unless the
+/// options allow it.
+pub async fn enqueue(url: String, options: DownloadOptions) -> Result<i64, DownloaderError> {
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    enqueue_into(&pool, ensure_queue_started().await, url, options).await
+}
+
+/// Add a download to the given queue, storing the job in `pool`
+///
+/// Works like `enqueue`; the job only runs if the queue's dispatcher has been
+/// started, which `ensure_queue_started` does for the shared queue.
+pub async fn enqueue_into(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    queue: &DownloadQueue,
+    url: String,
+    options: DownloadOptions,
+) -> Result<i64, DownloaderError> {
+    let url = youtube_url::normalize_url(&url);
+    sites::ensure_url_allowed_in(pool, &url).await?;
+    if options.checks_for_duplicates() {
+        let (format_type, quality) = (&options.format_type, &options.quality);
+        let format_id = options.format_id.as_deref();
+        let copies = earlier_copies(pool, &url, format_type, quality, format_id).await?;
+        if let Some(newest) = copies.first() {
+            return Err(DownloaderError::AlreadyDownloaded(format!(
+                "\"{}\" is already in your library as {} (downloaded {})",
+                newest.title, newest.file_name, newest.downloaded_at
+            )));
+        }
+        let queued = queued_copies(pool, &url, format_type, quality, format_id).await?;
+        if let Some(job) = queued.first() {
+            return Err(DownloaderError::AlreadyDownloaded(format!(
+                "\"{}\" is already in the queue",
+                job.title.as_deref().unwrap_or(&job.url)
+            )));
+        }
+    }
+    let job_id = insert_queued_job(pool, url, &options).await?;
+    jobs::announce(job_id, JobState::Queued, "Waiting in queue...");
+    queue.wake.notify_one();
+    Ok(job_id)
+}
+
+/// Add a download to the queue and wait until a worker has finished it
+pub async fn download_and_wait(
+    url: String,
+    options: DownloadOptions,
+) -> Result<CompletedDownload, DownloaderError> {
+    let job_id = enqueue(url, options).await?;
+    wait_for_job(job_id).await
+}
+
+/// Wait until a queued job has finished and return its result
+pub async fn wait_for_job(job_id: i64) -> Result<CompletedDownload, DownloaderError> {
+    let queue = ensure_queue_started().await;
+
+    // Register before checking the stored state so the outcome can't be missed
+    let (tx, rx) = oneshot::channel();
+    queue
+        .waiters
+        .lock()
+        .unwrap()
+        .entry(job_id)
+        .or_default()
+        .push(tx);
+
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    let job = get_job_by_id(&pool, job_id)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to load download job: {}", e)))?
+        .ok_or_else(|| DownloaderError::NotFound(format!("Download job {} not found", job_id)))?;
+
+    if job.status.is_finished() {
+        queue.waiters.lock().unwrap().remove(&job_id);
+    }
+
+    match job.status {
+        JobState::Completed => finished_job_output(&job).await,
+        JobState::Failed => Err(match job.error {
+            Some(error) => DownloaderError::from_stored(&error),
+            None => DownloaderError::Internal(format!("Download job {} failed", job_id)),
+        }),
+        JobState::Cancelled => Err(DownloaderError::Cancelled),
+        JobState::Queued | JobState::Running | JobState::Waiting | JobState::Paused => {
+            rx.await.unwrap_or_else(|_| {
+                Err(DownloaderError::Internal(format!(
+                    "Download job {} was dropped",
+                    job_id
+                )))
+            })
+        }
+    }
+}
+
+/// Cancel a queued, waiting, paused or running job and remove its partial files
+///
+/// A running job has its download task aborted, which kills yt-dlp. Returns
+/// false if the job has already finished.
+pub async fn cancel(job_id: i64) -> Result<bool, DownloaderError> {
+    let queue = ensure_queue_started().await;
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+
+    // Jobs that no worker is running are cancelled right here
+    for from in [JobState::Queued, JobState::Waiting, JobState::Paused] {
+        if transition(&pool, job_id, from, JobState::Cancelled).await? {
+            tracing::info!("Cancelled {} download job {}", from.as_str(), job_id);
+            if let Ok(Some(job)) = get_job_by_id(&pool, job_id).await {
+                remove_workspace(&job).await;
+            }
+
+            let mut progress = DownloadProgress::default();
+            progress.status = "Cancelled".to_string();
+            jobs::finish(job_id, JobState::Cancelled, progress);
+            notify_waiters(queue, job_id, Err(DownloaderError::Cancelled));
+            return Ok(true);
+        }
+    }
+
+    // The worker records the cancellation once the task has stopped
+    Ok(stop_running(queue, job_id, JobState::Cancelled).await)
+}
+
+/// Pause a queued, waiting or running job, keeping its partial files
+///
+/// Returns false if the job is neither queued, waiting nor running.
+pub async fn pause(job_id: i64) -> Result<bool, DownloaderError> {
+    let queue = ensure_queue_started().await;
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+
+    for from in [JobState::Queued, JobState::Waiting] {
+        if transition(&pool, job_id, from, JobState::Paused).await? {
+            tracing::info!("Paused {} download job {}", from.as_str(), job_id);
+            let mut progress = DownloadProgress::default();
+            progress.status = "Paused".to_string();
+            jobs::update_state(job_id, JobState::Paused, progress);
+            return Ok(true);
+        }
+    }
+
+    // The worker records the pause once the task has stopped
+    Ok(stop_running(queue, job_id, JobState::Paused).await)
+}
+
+/// Put a paused or failed job back into the queue
+///
+/// The job continues from the partial files in its workspace. Returns false
+/// if the job is neither paused nor failed.
+pub async fn resume(job_id: i64) -> Result<bool, DownloaderError> {
+    let queue = ensure_queue_started().await;
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+
+    for from in [JobState::Paused, JobState::Failed] {
+        if transition(&pool, job_id, from, JobState::Queued).await? {
+            tracing::info!("Resumed {} download job {}", from.as_str(), job_id);
+            let mut progress = DownloadProgress::default();
+            progress.status = "Waiting in queue...".to_string();
+            jobs::update_state(job_id, JobState::Queued, progress);
+            queue.wake.notify_one();
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Remove finished jobs from the queue along with any partial files they left
+pub async fn clear_finished() -> Result<u64, DownloaderError> {
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    let removed = delete_finished_jobs(&pool)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to clear queue: {}", e)))?;
+
+    for job in &removed {
+        remove_workspace(job).await;
+    }
+
+    Ok(removed.len() as u64)
+}
+
+/// Current worker pool size
+pub async fn concurrency() -> usize {
+    ensure_queue_started()
+        .await
+        .concurrency
+        .load(Ordering::SeqCst)
+}
+
+/// Change the worker pool size and persist it for the next launch
+pub async fn set_concurrency(limit: usize) -> usize {
+    let limit = limit.clamp(1, MAX_CONCURRENCY);
+    let queue = ensure_queue_started().await;
+    queue.concurrency.store(limit, Ordering::SeqCst);
+
+    if let Ok(pool) = get_database().await {
+        if let Err(e) = set_setting(&pool, CONCURRENCY_SETTING, &limit.to_string()).await {
+            tracing::error!("Failed to save queue concurrency: {}", e);
+        }
+    }
+
+    // A larger pool may be able to start waiting jobs right away
+    queue.wake.notify_one();
+    limit
+}
+
+async fn insert_queued_job(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    url: String,
+    options: &DownloadOptions,
+) -> Result<i64, DownloaderError> {
+    let job = DownloadJob::new(url, options);
+    let job_id = insert_job(pool, &job)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to queue download: {}", e)))?;
+
+    tracing::info!("Queued download job {} for {}", job_id, job.url);
+    Ok(job_id)
+}
+
+/// Rebuild the result of a job that finished before anyone waited on it
+async fn finished_job_output(job: &DownloadJob) -> Result<CompletedDownload, DownloaderError> {
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    let record_missing =
+        || DownloaderError::NotFound("Download record is no longer available".to_string());
+    let download_id = job.download_id.ok_or_else(record_missing)?;
+    let download = get_download_by_id(&pool, download_id)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to load download record: {}", e)))?
+        .ok_or_else(record_missing)?;
+
+    let file_size = tokio::fs::metadata(&download.file_path)
+        .await
+        .map_err(|e| {
+            DownloaderError::NotFound(format!("Downloaded file is no longer available: {}", e))
+        })?
+        .len();
+
+    Ok(CompletedDownload {
+        file_path: download.file_path,
+        file_name: download.filename,
+        file_size,
+        download_id: Some(download_id),
+    })
+}
+
+async fn dispatch_loop(queue: &'static DownloadQueue) {
+    loop {
+        let has_capacity =
+            queue.running.load(Ordering::SeqCst) < queue.concurrency.load(Ordering::SeqCst);
+
+        if has_capacity {
+            if let Some(job) = claim_next_job(queue).await {
+                queue.running.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(run_job(queue, job));
+                continue;
+            }
+        }
+
+        // Wait for a new job or a finished worker, re-checking periodically
+        let _ = tokio::time::timeout(Duration::from_secs(5), queue.wake.notified()).await;
+    }
+}
+
+async fn claim_next_job(queue: &DownloadQueue) -> Option<DownloadJob> {
+    let pool = get_database().await.ok()?;
+    let _claiming = queue.claiming.lock().await;
+    match claim_next_queued_job(&pool).await {
+        Ok(job) => {
+            if let Some(job_id) = job.as_ref().and_then(|job| job.id) {
+                queue.active.lock().unwrap().insert(job_id, None);
+            }
+            job
+        }
+        Err(e) => {
+            tracing::error!("Failed to read download queue: {}", e);
+            None
+        }
+    }
+}
+
+async fn run_job(queue: &'static DownloadQueue, job: DownloadJob) {
+    let job_id = job.id.unwrap_or_default();
+    tracing::info!("Starting download job {} for {}", job_id, job.url);
+
+    // Reuse the workspace of an earlier run so yt-dlp continues its partial files
+    let workspace = match &job.workspace {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let dir = job_workspace(job_id);
+            if let Ok(pool) = get_database().await {
+                if let Err(e) = set_job_workspace(&pool, job_id, &dir.to_string_lossy()).await {
+                    tracing::warn!("Failed to store workspace for job {}: {}", job_id, e);
+                }
+            }
+            dir
+        }
+    };
+
+    // Run the download in its own task so it can be paused or cancelled;
+    // dropping the task kills the yt-dlp child process. A stop requested while
+    // the job was being claimed ends it before the task starts.
+    let task = queue.start_task(job_id, || {
+        tokio::spawn(perform_download(
+            job_id,
+            job.url.clone(),
+            job.download_options(),
+            workspace,
+        ))
+    });
+
+    let result = match task {
+        Some(task) => Some(task.await),
+        None => None,
+    };
+    let stop_request = queue.end_run(job_id);
+
+    // A scheduled stream waits outside the worker pool until it is due
+    let result = match result {
+        Some(Ok(Ok(DownloadRun::Waiting { starts_at }))) => {
+            if let Ok(pool) = get_database().await {
+                if let Err(e) = set_job_waiting(&pool, job_id, starts_at).await {
+                    tracing::error!("Failed to update download job {}: {}", job_id, e);
+                }
+            }
+
+            let progress = jobs::get_progress(job_id).unwrap_or_default();
+            jobs::update_state(job_id, JobState::Waiting, progress);
+            tracing::info!("Download job {} waits for its stream to start", job_id);
+
+            queue.running.fetch_sub(1, Ordering::SeqCst);
+            queue.wake.notify_one();
+            return;
+        }
+        Some(Ok(Ok(DownloadRun::Completed(completed)))) => Some(Ok(Ok(completed))),
+        Some(Ok(Err(e))) => Some(Ok(Err(e))),
+        Some(Err(e)) => Some(Err(e)),
+        None => None,
+    };
+
+    // An aborted task, or one that never started, was either paused or cancelled
+    let stopped_as = match &result {
+        Some(Err(e)) if e.is_cancelled() => Some(stop_request.unwrap_or(JobState::Cancelled)),
+        None => Some(stop_request.unwrap_or(JobState::Cancelled)),
+        _ => None,
+    };
+
+    if stopped_as == Some(JobState::Paused) {
+        if let Ok(pool) = get_database().await {
+            if let Err(e) = update_job_status(&pool, job_id, JobState::Paused, None).await {
+                tracing::error!("Failed to update download job {}: {}", job_id, e);
+            }
+        }
+
+        // Keep the channel open for subscribers waiting on the resumed job
+        let mut progress = jobs::get_progress(job_id).unwrap_or_default();
+        progress.eta_seconds = 0;
+        progress.speed_bytes_per_sec = 0;
+        progress.status = "Paused".to_string();
+        jobs::update_state(job_id, JobState::Paused, progress);
+        tracing::info!("Download job {} paused", job_id);
+
+        queue.running.fetch_sub(1, Ordering::SeqCst);
+        queue.wake.notify_one();
+        return;
+    }
+
+    let cancelled = stopped_as == Some(JobState::Cancelled);
+    let outcome = match result {
+        Some(Ok(outcome)) => outcome,
+        Some(Err(e)) if !cancelled => Err(DownloaderError::Internal(format!(
+            "Download task failed: {}",
+            e
+        ))),
+        _ => Err(DownloaderError::Cancelled),
+    };
+
+    if cancelled {
+        // The aborted task can't clean up after itself
+        remove_workspace(&job).await;
+    }
+
+    if let Ok(pool) = get_database().await {
+        let result = match &outcome {
+            Ok(completed) => complete_job(&pool, job_id, completed.download_id).await,
+            Err(_) if cancelled => {
+                update_job_status(&pool, job_id, JobState::Cancelled, None).await
+            }
+            // Stored in its serialized form so `wait_for_job` can rebuild it
+            Err(e) => {
+                update_job_status(&pool, job_id, JobState::Failed, Some(&e.to_string())).await
+            }
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to update download job {}: {}", job_id, e);
+        }
+    }
+
+    // Push the final state to subscribers; the stored state answers later queries
+    let mut progress = jobs::get_progress(job_id).unwrap_or_default();
+    progress.eta_seconds = 0;
+    progress.speed_bytes_per_sec = 0;
+    let state = match &outcome {
+        Ok(_) => {
+            progress.percent = 100.0;
+            progress.status = "Download complete!".to_string();
+            JobState::Completed
+        }
+        Err(_) if cancelled => {
+            progress.status = "Cancelled".to_string();
+            JobState::Cancelled
+        }
+        Err(e) => {
+            progress.status = format!("Error: {}", e.message());
+            JobState::Failed
+        }
+    };
+    jobs::finish(job_id, state, progress);
+
+    match &outcome {
+        Ok(_) => tracing::info!("Download job {} completed", job_id),
+        Err(_) if cancelled => tracing::info!("Download job {} cancelled", job_id),
+        Err(e) => tracing::error!("Download job {} failed: {}", job_id, e),
+    }
+
+    notify_waiters(queue, job_id, outcome);
+
+    queue.running.fetch_sub(1, Ordering::SeqCst);
+    queue.wake.notify_one();
+}
+
+/// Abort the download task of a running job, recording the state it should end in
+///
+/// Waits for a claim in progress, so a job the dispatcher has just taken from
+/// the queue ends as soon as its worker picks it up. Returns false if the job
+/// isn't running or its download has already finished.
+async fn stop_running(queue: &DownloadQueue, job_id: i64, state: JobState) -> bool {
+    let _claiming = queue.claiming.lock().await;
+    queue.request_stop(job_id, state)
+}
+
+async fn transition(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    job_id: i64,
+    from: JobState,
+    to: JobState,
+) -> Result<bool, DownloaderError> {
+    transition_job(pool, job_id, from, to)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to update download job: {}", e)))
+}
+
+/// Remove files that earlier runs left behind
+///
+/// Covers the shared `youtube_dl_*` temp directories and `.progress` files of
+/// older versions, and workspaces of jobs that will never run again.
+async fn sweep_stale_files(pool: &sqlx::Pool<sqlx::Sqlite>) {
+    if let Ok(mut entries) = tokio::fs::read_dir(std::env::temp_dir()).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let result = if name.starts_with("youtube_dl_") && path.is_dir() {
+                tokio::fs::remove_dir_all(&path).await
+            } else if name.starts_with("download_") && name.ends_with(".progress") {
+                tokio::fs::remove_file(&path).await
+            } else {
+                continue;
+            };
+
+            match result {
+                Ok(()) => tracing::info!("Removed stale download file {:?}", path),
+                Err(e) => tracing::warn!("Failed to remove stale download file {:?}: {}", path, e),
+            }
+        }
+    }
+
+    // Queued, paused and failed jobs can still continue their partial files
+    let jobs = match get_all_jobs(pool).await {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            tracing::error!("Failed to load jobs for workspace cleanup: {}", e);
+            return;
+        }
+    };
+    let in_use: HashSet<PathBuf> = jobs
+        .iter()
+        .filter(|job| !matches!(job.status, JobState::Completed | JobState::Cancelled))
+        .map(|job| match &job.workspace {
+            Some(dir) => PathBuf::from(dir),
+            None => job_workspace(job.id.unwrap_or_default()),
+        })
+        .collect();
+
+    if let Ok(mut entries) = tokio::fs::read_dir(workspaces_root()).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if in_use.contains(&path) {
+                continue;
+            }
+            match tokio::fs::remove_dir_all(&path).await {
+                Ok(()) => tracing::info!("Removed orphaned workspace {:?}", path),
+                Err(e) => tracing::warn!("Failed to remove workspace {:?}: {}", path, e),
+            }
+        }
+    }
+}
+
+/// Delete a job's workspace and the partial files in it
+async fn remove_workspace(job: &DownloadJob) {
+    let workspace = match &job.workspace {
+        Some(dir) => PathBuf::from(dir),
+        None => job_workspace(job.id.unwrap_or_default()),
+    };
+    if workspace.exists() {
+        if let Err(e) = tokio::fs::remove_dir_all(&workspace).await {
+            tracing::warn!("Failed to remove workspace {:?}: {}", workspace, e);
+        }
+    }
+}
+
+/// Hand a job's result to anyone waiting on it
+fn notify_waiters(queue: &DownloadQueue, job_id: i64, outcome: JobOutcome) {
+    let waiters = queue.waiters.lock().unwrap().remove(&job_id);
+    for waiter in waiters.unwrap_or_default() {
+        let _ = waiter.send(outcome.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::get_memory_database;
+
+    #[tokio::test]
+    async fn stop_after_the_download_finished_leaves_the_next_run_alone() {
+        let queue = DownloadQueue::new(DEFAULT_CONCURRENCY);
+        let job_id = 1;
+
+        // The download finishes just before the pause comes in
+        queue.active.lock().unwrap().insert(job_id, None);
+        let task = queue.start_task(job_id, || tokio::spawn(async {})).unwrap();
+        task.await.unwrap();
+        assert!(!stop_running(&queue, job_id, JobState::Paused).await);
+        assert_eq!(queue.end_run(job_id), None);
+        assert!(!stop_running(&queue, job_id, JobState::Paused).await);
+
+        // Resumed later, the job runs instead of ending as paused
+        queue.active.lock().unwrap().insert(job_id, None);
+        assert!(queue
+            .start_task(job_id, || tokio::spawn(async {}))
+            .is_some());
+        assert_eq!(queue.end_run(job_id), None);
+    }
+
+    #[tokio::test]
+    async fn stop_while_claimed_ends_the_job_before_it_starts() {
+        let queue = DownloadQueue::new(DEFAULT_CONCURRENCY);
+        let job_id = 2;
+
+        queue.active.lock().unwrap().insert(job_id, None);
+        assert!(stop_running(&queue, job_id, JobState::Cancelled).await);
+        assert!(queue
+            .start_task(job_id, || tokio::spawn(async {}))
+            .is_none());
+        assert_eq!(queue.end_run(job_id), Some(JobState::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn stop_aborts_a_running_download() {
+        let queue = DownloadQueue::new(DEFAULT_CONCURRENCY);
+        let job_id = 3;
+
+        queue.active.lock().unwrap().insert(job_id, None);
+        let task = queue
+            .start_task(job_id, || tokio::spawn(std::future::pending::<()>()))
+            .unwrap();
+        assert!(stop_running(&queue, job_id, JobState::Paused).await);
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert_eq!(queue.end_run(job_id), Some(JobState::Paused));
+    }
+
+    #[tokio::test]
+    async fn enqueue_refuses_a_video_already_in_the_queue() {
+        let pool = get_memory_database().await.unwrap();
+        let queue = DownloadQueue::new(DEFAULT_CONCURRENCY);
+        let url = "https://www.youtube.com/watch?v=queuedVid01".to_string();
+        let options = DownloadOptions::new("video", "best");
+
+        enqueue_into(&pool, &queue, url.clone(), options.clone())
+            .await
+            .unwrap();
+        let again = enqueue_into(&pool, &queue, url.clone(), options.clone()).await;
+        assert!(matches!(again, Err(DownloaderError::AlreadyDownloaded(_))));
+
+        // Another hand-picked format is a different file
+        let other_format = DownloadOptions {
+            format_id: Some("137+140".to_string()),
+            ..options
+        };
+        assert!(enqueue_into(&pool, &queue, url, other_format).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn waiting_job_is_claimed_once_its_stream_is_due() {
+        let pool = get_memory_database().await.unwrap();
+        let options = DownloadOptions::new("video", "best");
+        let job_id = insert_queued_job(&pool, "https://example.com/live".to_string(), &options)
+            .await
+            .unwrap();
+        assert!(claim_next_queued_job(&pool).await.unwrap().is_some());
+
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        set_job_waiting(&pool, job_id, now + 3600).await.unwrap();
+        assert!(claim_next_queued_job(&pool).await.unwrap().is_none());
+
+        set_job_waiting(&pool, job_id, now - 1).await.unwrap();
+        let job = claim_next_queued_job(&pool).await.unwrap().unwrap();
+        assert_eq!(job.id, Some(job_id));
+        assert_eq!(job.status, JobState::Running);
+    }
+}
@@ -0,0 +1,130 @@
+// Live progress for download jobs
+//
+// Progress is keyed by the `download_jobs` row ID and pushed to subscribers
+// through in-memory broadcast channels: one per job, plus one carrying every
+// job's events for views that show the whole queue.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use tokio::sync::broadcast;
+
+use crate::server::download::types::{DownloadProgress, JobProgress, JobState};
+
+/// Events buffered per subscriber before slow clients start skipping updates
+const CHANNEL_CAPACITY: usize = 64;
+
+struct JobChannel {
+    latest: Option<DownloadProgress>,
+    sender: broadcast::Sender<JobProgress>,
+}
+
+impl JobChannel {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            latest: None,
+            sender,
+        }
+    }
+}
+
+static CHANNELS: OnceLock<Mutex<HashMap<i64, JobChannel>>> = OnceLock::new();
+static ALL_JOBS: OnceLock<broadcast::Sender<JobProgress>> = OnceLock::new();
+
+fn channels() -> &'static Mutex<HashMap<i64, JobChannel>> {
+    CHANNELS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn all_jobs() -> &'static broadcast::Sender<JobProgress> {
+    ALL_JOBS.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY * 4).0)
+}
+
+/// Record the latest progress for a running job and push it to subscribers
+pub fn update_progress(job_id: i64, progress: &DownloadProgress) {
+    let event = JobProgress {
+        job_id,
+        state: JobState::Running,
+        progress: progress.clone(),
+    };
+
+    let mut channels = channels().lock().unwrap();
+    let channel = channels.entry(job_id).or_insert_with(JobChannel::new);
+    channel.latest = Some(progress.clone());
+    let _ = channel.sender.send(event.clone());
+    let _ = all_jobs().send(event);
+}
+
+/// Announce a state change that doesn't carry download progress, e.g. a new queued job
+pub fn announce(job_id: i64, state: JobState, status: impl Into<String>) {
+    let mut progress = DownloadProgress::default();
+    progress.status = status.into();
+    let _ = all_jobs().send(JobProgress {
+        job_id,
+        state,
+        progress,
+    });
+}
+
+/// Push a state change for a job that stopped without finishing, e.g. a paused job
+///
+/// The job's channel stays open so subscribers follow it once it runs again.
+pub fn update_state(job_id: i64, state: JobState, progress: DownloadProgress) {
+    let event = JobProgress {
+        job_id,
+        state,
+        progress,
+    };
+
+    if let Some(channel) = channels().lock().unwrap().get_mut(&job_id) {
+        // Only running jobs report live progress
+        channel.latest = None;
+        let _ = channel.sender.send(event.clone());
+    }
+    let _ = all_jobs().send(event);
+}
+
+/// Push a job's final state and close its channel
+pub fn finish(job_id: i64, state: JobState, progress: DownloadProgress) {
+    let event = JobProgress {
+        job_id,
+        state,
+        progress,
+    };
+
+    // Dropping the sender ends every subscriber's stream after the final event
+    if let Some(channel) = channels().lock().unwrap().remove(&job_id) {
+        let _ = channel.sender.send(event.clone());
+    }
+    let _ = all_jobs().send(event);
+}
+
+/// Latest progress reported by a running job
+pub fn get_progress(job_id: i64) -> Option<DownloadProgress> {
+    channels()
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .and_then(|channel| channel.latest.clone())
+}
+
+/// Subscribe to a single job's events
+pub fn subscribe(job_id: i64) -> broadcast::Receiver<JobProgress> {
+    channels()
+        .lock()
+        .unwrap()
+        .entry(job_id)
+        .or_insert_with(JobChannel::new)
+        .sender
+        .subscribe()
+}
+
+/// Subscribe to the events of every job
+pub fn subscribe_all() -> broadcast::Receiver<JobProgress> {
+    all_jobs().subscribe()
+}
+
+/// Drop the channel of a job that has already finished
+pub fn forget(job_id: i64) {
+    channels().lock().unwrap().remove(&job_id);
+}
@@ -0,0 +1,383 @@
+// Live stream recording
+//
+// yt-dlp's info JSON tells whether a video is live or scheduled; it is only
+// asked when the regular info fetch hints at a stream. Scheduled streams wait
+// outside the worker pool and are queued again when they are due to start.
+// Live streams are recorded by ffmpeg
+// from the stream's HLS manifest into MPEG-TS, which stays playable however
+// the recording ends, and remuxed into MP4 or M4A once it does. A recording
+// is stopped by hand by sending ffmpeg `q`, which lets it finish the file.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Notify;
+use tracing;
+
+use crate::server::download::cookies;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::network;
+use crate::server::download::types::{LiveOptions, VideoMetadata};
+use crate::server::download::ytdlp::yt_dlp_binary;
+
+/// How long to wait before checking again on a stream that is late or has no start time
+const RECHECK_DELAY: Duration = Duration::from_secs(30);
+
+/// Whether a video is live right now or scheduled to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveStatus {
+    Live,
+    Upcoming,
+}
+
+/// A live or scheduled stream, as yt-dlp reported it
+#[derive(Debug, Clone)]
+pub struct LiveStream {
+    pub status: LiveStatus,
+    /// Scheduled start as a Unix timestamp, when the site gives one
+    pub starts_at: Option<i64>,
+    pub metadata: VideoMetadata,
+}
+
+impl LiveStream {
+    /// "Waiting for the stream to start (in 12 min)"
+    pub fn describe_wait(&self) -> String {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        match self.starts_at.map(|start| start - now) {
+            Some(secs) if secs > 3600 => format!(
+                "Waiting for the stream to start (in {}h {}min)",
+                secs / 3600,
+                (secs % 3600) / 60
+            ),
+            Some(secs) if secs > 60 => {
+                format!("Waiting for the stream to start (in {} min)", secs / 60)
+            }
+            _ => "Waiting for the stream to start...".to_string(),
+        }
+    }
+
+    /// When to check again whether the stream has started, as a Unix timestamp
+    pub fn next_check(&self) -> i64 {
+        let soonest =
+            time::OffsetDateTime::now_utc().unix_timestamp() + RECHECK_DELAY.as_secs() as i64;
+        self.starts_at.map_or(soonest, |start| start.max(soonest))
+    }
+}
+
+/// Whether a failed info fetch means the video is a stream that hasn't started
+pub fn is_not_started_error(error: &DownloaderError) -> bool {
+    let message = error.message().to_lowercase();
+    ["live event will begin", "premieres in", "this live event"]
+        .iter()
+        .any(|hint| message.contains(hint))
+}
+
+/// Check whether a video is a live or scheduled stream
+///
+/// Returns `None` for everything else, including streams that have ended.
+/// yt-dlp is killed if the check is dropped, e.g. when it times out.
+pub async fn probe(url: String) -> Result<Option<LiveStream>, DownloaderError> {
+    // Scheduled streams have no formats yet, which yt-dlp otherwise fails on
+    let settings = network::load().await;
+    let output = tokio::process::Command::new(yt_dlp_binary().await)
+        .args([
+            "--dump-single-json",
+            "--no-playlist",
+            "--ignore-no-formats-error",
+            "--socket-timeout",
+        ])
+        .arg(settings.socket_timeout())
+        .args(settings.args())
+        .args(cookies::args_for(&url).await)
+        .arg("--")
+        .arg(&url)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| DownloaderError::YtDlpMissing(format!("Failed to start yt-dlp: {}", e)))?;
+    if !output.status.success() {
+        return Err(DownloaderError::from_ytdlp_output(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| DownloaderError::Internal(format!("Failed to parse video info: {}", e)))?;
+    let status = match info["live_status"].as_str() {
+        Some("is_live") => LiveStatus::Live,
+        Some("is_upcoming") => LiveStatus::Upcoming,
+        _ => return Ok(None),
+    };
+
+    let text = |key: &str| info[key].as_str().map(str::to_string);
+    Ok(Some(LiveStream {
+        status,
+        starts_at: info["release_timestamp"].as_i64(),
+        metadata: VideoMetadata {
+            title: text("title").unwrap_or_else(|| "Live stream".to_string()),
+            extractor: text("extractor"),
+            video_id: text("id"),
+            thumbnail_url: text("thumbnail"),
+            uploader: text("uploader"),
+            upload_date: text("upload_date"),
+            description: text("description"),
+            is_live: status == LiveStatus::Live,
+            ..VideoMetadata::default()
+        },
+    }))
+}
+
+static STOP_SIGNALS: OnceLock<Mutex<HashMap<i64, Arc<Notify>>>> = OnceLock::new();
+
+fn stop_signals() -> &'static Mutex<HashMap<i64, Arc<Notify>>> {
+    STOP_SIGNALS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Ask the recording of a job to stop and keep what it has recorded
+///
+/// Returns false if the job isn't recording.
+pub fn request_stop(job_id: i64) -> bool {
+    match stop_signals().lock().unwrap().get(&job_id) {
+        Some(signal) => {
+            signal.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Record a live stream into the workspace, returning the finished file
+///
+/// `network_args` are ffmpeg's options from the network settings, see
+/// `NetworkSettings::ffmpeg_args`. `on_progress` gets the seconds and bytes
+/// recorded so far. Audio-only recordings keep just the audio track.
+pub async fn record(
+    job_id: i64,
+    url: &str,
+    options: &LiveOptions,
+    audio_only: bool,
+    network_args: &[String],
+    workspace: &Path,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<PathBuf, DownloaderError> {
+    let manifest = resolve_manifest(url, audio_only).await?;
+    let recording = workspace.join("live.ts");
+    // The same connection settings and signed-in session yt-dlp used to find
+    // the stream
+    let mut input_args = network_args.to_vec();
+    input_args.extend(cookies::ffmpeg_args_for(url).await);
+
+    let signal = Arc::new(Notify::new());
+    stop_signals()
+        .lock()
+        .unwrap()
+        .insert(job_id, signal.clone());
+    let result = run_ffmpeg(
+        &manifest,
+        &input_args,
+        options,
+        audio_only,
+        &recording,
+        &signal,
+        on_progress,
+    )
+    .await;
+    stop_signals().lock().unwrap().remove(&job_id);
+    result?;
+
+    let size = tokio::fs::metadata(&recording)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    if size == 0 {
+        return Err(DownloaderError::Internal(
+            "Nothing was recorded from the stream".to_string(),
+        ));
+    }
+
+    // MPEG-TS plays everywhere but tags and seeks poorly, so it's only kept
+    // when remuxing fails
+    let finished = workspace.join(if audio_only { "live.m4a" } else { "live.mp4" });
+    match remux(&recording, &finished).await {
+        Ok(()) => {
+            let _ = tokio::fs::remove_file(&recording).await;
+            Ok(finished)
+        }
+        Err(e) => {
+            tracing::warn!("Keeping the recording as MPEG-TS: {}", e);
+            Ok(recording)
+        }
+    }
+}
+
+/// URL of the HLS manifest yt-dlp picks for the stream
+async fn resolve_manifest(url: &str, audio_only: bool) -> Result<String, DownloaderError> {
+    // Live streams are mostly offered as combined formats only
+    let format = if audio_only { "bestaudio/best" } else { "best" };
+    let settings = network::load().await;
+    let output = tokio::process::Command::new(yt_dlp_binary().await)
+        .args([
+            "--get-url",
+            "--no-playlist",
+            "-f",
+            format,
+            "--socket-timeout",
+        ])
+        .arg(settings.socket_timeout())
+        .args(settings.args())
+        .args(cookies::args_for(url).await)
+        .arg("--")
+        .arg(url)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| DownloaderError::YtDlpMissing(format!("Failed to start yt-dlp: {}", e)))?;
+    if !output.status.success() {
+        return Err(DownloaderError::from_ytdlp_output(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| DownloaderError::NotFound("yt-dlp found no stream to record".to_string()))
+}
+
+/// Run ffmpeg until the stream ends, the maximum duration is reached or a
+/// stop is requested
+async fn run_ffmpeg(
+    manifest: &str,
+    input_args: &[String],
+    options: &LiveOptions,
+    audio_only: bool,
+    target: &Path,
+    stop: &Notify,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), DownloaderError> {
+    let mut command = tokio::process::Command::new("ffmpeg");
+    command.args([
+        "-y",
+        "-loglevel",
+        "error",
+        "-nostats",
+        "-progress",
+        "pipe:1",
+    ]);
+    if options.from_start {
+        // Start at the oldest segment the stream still offers
+        command.args(["-live_start_index", "0"]);
+    }
+    command.args(input_args).arg("-i").arg(manifest);
+    if let Some(max_secs) = options.max_duration_secs {
+        command.arg("-t").arg(max_secs.to_string());
+    }
+    if !audio_only {
+        command.args(["-map", "0:v?"]);
+    }
+    command
+        .args(["-map", "0:a?", "-c", "copy", "-f", "mpegts"])
+        .arg(target)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| DownloaderError::Internal(format!("Failed to start ffmpeg: {}", e)))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_task = tokio::spawn(async move {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output).await;
+        output
+    });
+
+    // `-progress` prints blocks of key=value lines, each ending in `progress=`
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let mut lines = BufReader::new(stdout).lines();
+    let (mut recorded_secs, mut recorded_bytes) = (0, 0);
+    let mut stopping = false;
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else { break };
+                match line.trim().split_once('=') {
+                    Some(("out_time_us", value)) => {
+                        recorded_secs = value.parse::<u64>().map(|us| us / 1_000_000).unwrap_or(recorded_secs);
+                    }
+                    Some(("total_size", value)) => {
+                        recorded_bytes = value.parse().unwrap_or(recorded_bytes);
+                    }
+                    Some(("progress", _)) => on_progress(recorded_secs, recorded_bytes),
+                    _ => {}
+                }
+            }
+            _ = stop.notified(), if !stopping => {
+                tracing::info!("Stopping the recording of {}", target.display());
+                stopping = true;
+                let _ = stdin.write_all(b"q").await;
+                let _ = stdin.flush().await;
+            }
+        }
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| DownloaderError::Internal(format!("Failed to wait for ffmpeg: {}", e)))?;
+    let stderr_output = stderr_task.await.unwrap_or_default();
+    // Streams that drop mid-way still leave a usable recording, so a failed
+    // exit is only an error when nothing was recorded
+    if !status.success() {
+        tracing::warn!(
+            "ffmpeg stopped recording with {}: {}",
+            status,
+            stderr_output.trim()
+        );
+        if recorded_bytes == 0 {
+            return Err(DownloaderError::Network(format!(
+                "Failed to record the stream: {}",
+                stderr_output
+                    .lines()
+                    .last()
+                    .unwrap_or("ffmpeg exited with an error")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Copy the recorded streams into a seekable container
+async fn remux(source: &Path, target: &Path) -> Result<(), DownloaderError> {
+    let output = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(source)
+        // Timed metadata tracks of HLS streams don't fit in MP4
+        .args([
+            "-map",
+            "0:v?",
+            "-map",
+            "0:a?",
+            "-c",
+            "copy",
+            "-movflags",
+            "+faststart",
+        ])
+        .arg(target)
+        .output()
+        .await
+        .map_err(|e| DownloaderError::Internal(format!("Failed to start ffmpeg: {}", e)))?;
+    if !output.status.success() {
+        return Err(DownloaderError::Internal(format!(
+            "ffmpeg failed to remux the recording: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -14,18 +14,78 @@ pub use web_services::*;
 pub mod types;
 pub use types::*;
 
+// Error type returned by the server functions
+pub mod error;
+pub use error::*;
+
 // Utilities
 pub mod utils;
 pub use utils::*;
 
+// YouTube URL normalization
+pub mod youtube_url;
+pub use youtube_url::*;
+
 // yt-dlp handling
 pub mod ytdlp;
 pub use ytdlp::*;
 
+// Download queue and worker pool
+#[cfg(feature = "server")]
+pub mod job_queue;
+
+// Allowlist of sites downloads may come from
+#[cfg(feature = "server")]
+pub mod sites;
+
+// Chapters, and splitting audio by chapter
+#[cfg(feature = "server")]
+pub mod chapters;
+
+// Downloading time ranges of a video
+#[cfg(feature = "server")]
+pub mod clips;
+
+// Cookies for signed-in downloads
+#[cfg(feature = "server")]
+pub mod cookies;
+
+// Proxy, timeout and rate limit settings
+#[cfg(feature = "server")]
+pub mod network;
+
+// Live stream recording
+#[cfg(feature = "server")]
+pub mod live;
+
+// Subtitles fetched along with downloads
+#[cfg(feature = "server")]
+pub mod subtitles;
+
+// Metadata and cover art written into downloaded files
+#[cfg(feature = "server")]
+pub mod tagging;
+
+// Channel subscriptions and their scheduler
+#[cfg(feature = "server")]
+pub mod subscriptions;
+
+// Retry policy and failure classification
+#[cfg(feature = "server")]
+pub mod retry;
+
+// Live progress for running jobs
+#[cfg(feature = "server")]
+pub mod jobs;
+
 // File storage handling
 pub mod storage;
 pub use storage::*;
 
+// HTTP endpoint streaming finished files
+#[cfg(feature = "server")]
+pub mod files;
+
 // Server handlers
 pub mod handlers;
 pub use handlers::*;
@@ -0,0 +1,192 @@
+// Network settings
+//
+// A proxy, the IP version and source address to connect with, the socket
+// timeout, a download speed cap and a pause between requests. They are stored
+// in the settings table and applied to every yt-dlp run, and as far as ffmpeg
+// allows to live recordings; the speed cap can be overridden per job.
+
+use std::net::IpAddr;
+use tracing;
+
+use crate::database::{
+    get_database,
+    schema::{get_setting, set_setting},
+};
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::{IpVersion, NetworkSettings};
+
+/// Settings key of the network settings, stored as JSON
+pub const NETWORK_SETTING: &str = "network";
+
+/// Proxy schemes yt-dlp understands
+const PROXY_SCHEMES: [&str; 6] = ["http", "https", "socks4", "socks4a", "socks5", "socks5h"];
+
+/// The saved settings, or the defaults when none are set or they can't be read
+pub async fn load() -> NetworkSettings {
+    let Ok(pool) = get_database().await else {
+        return NetworkSettings::default();
+    };
+
+    match get_setting(&pool, NETWORK_SETTING).await {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::error!("Ignoring unreadable network settings: {}", e);
+            NetworkSettings::default()
+        }),
+        Ok(None) => NetworkSettings::default(),
+        Err(e) => {
+            tracing::error!("Failed to load network settings: {}", e);
+            NetworkSettings::default()
+        }
+    }
+}
+
+/// Check and store the settings, returning what was saved
+///
+/// Blank text fields are cleared rather than rejected.
+pub async fn save(settings: NetworkSettings) -> Result<NetworkSettings, DownloaderError> {
+    let settings = validate(settings)?;
+    let value = serde_json::to_string(&settings).map_err(|e| {
+        DownloaderError::Internal(format!("Failed to serialize network settings: {}", e))
+    })?;
+
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    set_setting(&pool, NETWORK_SETTING, &value)
+        .await
+        .map_err(|e| {
+            DownloaderError::Database(format!("Failed to save network settings: {}", e))
+        })?;
+
+    tracing::info!("Network settings set to {:?}", settings);
+    Ok(settings)
+}
+
+fn validate(settings: NetworkSettings) -> Result<NetworkSettings, DownloaderError> {
+    let proxy = settings
+        .proxy
+        .map(|proxy| proxy.trim().to_string())
+        .filter(|proxy| !proxy.is_empty());
+    if let Some(proxy) = &proxy {
+        let scheme = url::Url::parse(proxy)
+            .ok()
+            .filter(|url| url.host_str().is_some())
+            .map(|url| url.scheme().to_string());
+        if !scheme.is_some_and(|scheme| PROXY_SCHEMES.contains(&scheme.as_str())) {
+            return Err(DownloaderError::InvalidUrl(format!(
+                "\"{}\" is not a proxy URL. Use e.g. http://proxy:8080 or socks5://proxy:1080",
+                proxy
+            )));
+        }
+    }
+
+    let source_address = settings
+        .source_address
+        .map(|address| address.trim().to_string())
+        .filter(|address| !address.is_empty());
+    if let Some(address) = &source_address {
+        let ip = address.parse::<IpAddr>().map_err(|_| {
+            DownloaderError::InvalidUrl(format!("\"{}\" is not an IP address", address))
+        })?;
+        let mismatch = match settings.ip_version {
+            IpVersion::V4 => ip.is_ipv6(),
+            IpVersion::V6 => ip.is_ipv4(),
+            IpVersion::Any => false,
+        };
+        if mismatch {
+            return Err(DownloaderError::InvalidUrl(format!(
+                "The source address {} doesn't match {}",
+                address,
+                settings.ip_version.label()
+            )));
+        }
+    }
+
+    if !(1..=600).contains(&settings.socket_timeout_secs) {
+        return Err(DownloaderError::InvalidUrl(
+            "The socket timeout must be between 1 and 600 seconds".to_string(),
+        ));
+    }
+
+    Ok(NetworkSettings {
+        proxy,
+        source_address,
+        rate_limit_kib: settings.rate_limit_kib.filter(|rate| *rate > 0),
+        sleep_requests_secs: settings
+            .sleep_requests_secs
+            .filter(|secs| secs.is_finite() && *secs > 0.0),
+        ..settings
+    })
+}
+
+impl NetworkSettings {
+    /// `--socket-timeout` value
+    pub fn socket_timeout(&self) -> String {
+        self.socket_timeout_secs.to_string()
+    }
+
+    /// yt-dlp arguments for the proxy, IP version, source address and request pause
+    ///
+    /// The socket timeout is set through the builders' `socket_timeout` instead.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(proxy) = &self.proxy {
+            args.push("--proxy".to_string());
+            args.push(proxy.clone());
+        }
+        match self.ip_version {
+            IpVersion::V4 => args.push("--force-ipv4".to_string()),
+            IpVersion::V6 => args.push("--force-ipv6".to_string()),
+            IpVersion::Any => {}
+        }
+        if let Some(address) = &self.source_address {
+            args.push("--source-address".to_string());
+            args.push(address.clone());
+        }
+        if let Some(secs) = self.sleep_requests_secs {
+            args.push("--sleep-requests".to_string());
+            args.push(secs.to_string());
+        }
+        args
+    }
+
+    /// `args` plus the speed cap, which `rate_limit_kib` overrides for one job
+    pub fn download_args(&self, rate_limit_kib: Option<u64>) -> Vec<String> {
+        let mut args = self.args();
+        if let Some(rate) = rate_limit_kib.or(self.rate_limit_kib) {
+            args.push("--limit-rate".to_string());
+            args.push(format!("{}K", rate));
+        }
+        args
+    }
+
+    /// ffmpeg input options for recording a live stream
+    ///
+    /// ffmpeg has no byte rate cap, so with a speed cap it reads no faster
+    /// than the stream plays. It only speaks HTTP proxies, so recording fails
+    /// rather than bypass a SOCKS one. The IP version and source address have
+    /// no ffmpeg equivalent and are left to the system.
+    pub fn ffmpeg_args(&self, rate_limit_kib: Option<u64>) -> Result<Vec<String>, DownloaderError> {
+        // In microseconds
+        let mut args = vec![
+            "-rw_timeout".to_string(),
+            (u64::from(self.socket_timeout_secs) * 1_000_000).to_string(),
+        ];
+        if let Some(proxy) = &self.proxy {
+            let is_http =
+                url::Url::parse(proxy).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !is_http {
+                return Err(DownloaderError::Network(format!(
+                    "Live streams are recorded with ffmpeg, which can't use the proxy {}. Set an HTTP proxy to record them.",
+                    proxy
+                )));
+            }
+            args.push("-http_proxy".to_string());
+            args.push(proxy.clone());
+        }
+        if rate_limit_kib.or(self.rate_limit_kib).is_some() {
+            args.push("-re".to_string());
+        }
+        Ok(args)
+    }
+}
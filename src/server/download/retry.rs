@@ -0,0 +1,188 @@
+// Retrying failed yt-dlp runs
+//
+// Failures are classified from yt-dlp's error output so that only transient
+// problems (network errors, rate limits, expired stream URLs) are retried.
+// Retries back off exponentially with random jitter, so parallel workers that
+// hit the same problem don't all retry at the same moment.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use tracing;
+
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::FailureKind;
+
+/// How often and how patiently a failed operation is retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further retry
+    pub base_delay: Duration,
+    /// Upper bound for the delay between attempts
+    pub max_delay: Duration,
+    /// Fraction of the delay that is randomised, e.g. 0.25 for ±25%
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Policy for downloads, which continue their partial files on every retry
+    pub const DOWNLOAD: RetryPolicy = RetryPolicy {
+        max_attempts: 4,
+        base_delay: Duration::from_secs(3),
+        max_delay: Duration::from_secs(60),
+        jitter: 0.25,
+    };
+
+    /// Policy for fetching video info, which is quick and should fail fast
+    pub const INFO: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(8),
+        jitter: 0.25,
+    };
+
+    /// Delay before the next attempt, or `None` if the operation should give up
+    ///
+    /// `attempt` is the 1-based number of the attempt that just failed.
+    pub fn retry_delay(&self, attempt: u32, kind: FailureKind) -> Option<Duration> {
+        if !kind.is_retryable() || attempt >= self.max_attempts {
+            return None;
+        }
+
+        let exponent = attempt.saturating_sub(1).min(16);
+        let mut delay = self.base_delay.saturating_mul(1 << exponent);
+        // Rate limits take a while to lift, so start from a longer delay
+        if kind == FailureKind::RateLimited {
+            delay = delay.saturating_mul(4);
+        }
+        let delay = delay.min(self.max_delay);
+
+        // Spread the delay over [1 - jitter, 1 + jitter]
+        let factor = 1.0 + self.jitter * (random_unit() * 2.0 - 1.0);
+        Some(delay.mul_f64(factor.max(0.0)))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::DOWNLOAD
+    }
+}
+
+/// Run `operation` until it succeeds or fails with an error not worth retrying
+///
+/// `on_retry` is called with the failed attempt's number, the failure kind and
+/// the delay before the next attempt, e.g. to report the retry to the user.
+pub async fn retry<T, Fut>(
+    policy: &RetryPolicy,
+    mut operation: impl FnMut() -> Fut,
+    mut on_retry: impl FnMut(u32, FailureKind, Duration),
+) -> Result<T, DownloaderError>
+where
+    Fut: Future<Output = Result<T, DownloaderError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(error) => {
+                let kind = error.failure_kind();
+                let Some(delay) = policy.retry_delay(attempt, kind) else {
+                    return Err(error);
+                };
+
+                tracing::warn!(
+                    "Attempt {} of {} failed ({}), retrying in {:.1}s: {}",
+                    attempt,
+                    policy.max_attempts,
+                    kind.label(),
+                    delay.as_secs_f64(),
+                    error.message()
+                );
+                on_retry(attempt, kind, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Work out why a yt-dlp run failed from its error output
+pub fn classify_failure(output: &str) -> FailureKind {
+    let output = output.to_lowercase();
+    let mentions = |patterns: &[&str]| patterns.iter().any(|pattern| output.contains(pattern));
+
+    // Specific reasons first: their messages often mention HTTP errors as well
+    if mentions(&[
+        "available in your country",
+        "geo restrict",
+        "geo-restrict",
+        "georestrict",
+        "blocked it in your country",
+    ]) {
+        FailureKind::GeoBlocked
+    } else if mentions(&[
+        "confirm your age",
+        "age-restricted",
+        "age restricted",
+        "inappropriate for some users",
+    ]) {
+        FailureKind::AgeRestricted
+    } else if mentions(&[
+        "private video",
+        "video is private",
+        "members-only",
+        "members only",
+        "join this channel",
+    ]) {
+        FailureKind::Private
+    } else if mentions(&["http error 429", "too many requests"]) {
+        FailureKind::RateLimited
+    } else if mentions(&["http error 403", "forbidden"]) {
+        FailureKind::Forbidden
+    } else if mentions(&[
+        "video unavailable",
+        "video is unavailable",
+        "no longer available",
+        "has been removed",
+        "account associated with this video has been terminated",
+        "does not exist",
+        "http error 404",
+        "http error 410",
+    ]) {
+        FailureKind::Unavailable
+    } else if mentions(&[
+        "unable to download webpage",
+        "unable to download video data",
+        "urlopen error",
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "connection aborted",
+        "remote end closed connection",
+        "incompleteread",
+        "network is unreachable",
+        "temporary failure in name resolution",
+        "name or service not known",
+        "getaddrinfo failed",
+        "http error 500",
+        "http error 502",
+        "http error 503",
+        "http error 504",
+    ]) {
+        FailureKind::Network
+    } else {
+        FailureKind::Other
+    }
+}
+
+/// A random number in `[0, 1)`, good enough for spreading out retries
+fn random_unit() -> f64 {
+    // Every `RandomState` is seeded differently, which avoids a `rand` dependency
+    let value = RandomState::new().build_hasher().finish();
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
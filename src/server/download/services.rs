@@ -1,9 +1,20 @@
 #[cfg(feature = "server")]
-use crate::database::{get_database, models::Download, schema::get_all_downloads};
+use crate::database::{
+    get_database,
+    models::Download,
+    schema::{get_all_downloads, get_all_subtitles},
+};
+#[cfg(feature = "server")]
+use crate::server::download::types::SubtitleFile;
+#[cfg(feature = "server")]
+use std::collections::HashMap;
 
 // Convert a Download database model to a DownloadItem DTO
 #[cfg(feature = "server")]
-pub fn convert_download_to_item(download: Download) -> crate::views::downloads::DownloadItem {
+pub fn convert_download_to_item(
+    download: Download,
+    subtitles: Vec<SubtitleFile>,
+) -> crate::views::downloads::DownloadItem {
     let file_exists = std::path::Path::new(&download.file_path).exists();
 
     crate::views::downloads::DownloadItem {
@@ -21,6 +32,11 @@ pub fn convert_download_to_item(download: Download) -> crate::views::downloads::
         date_downloaded: download.format_date(),
         thumbnail_url: download.thumbnail_url,
         file_exists,
+        playlist_title: download.playlist_title,
+        playlist_index: download.playlist_index,
+        chapters: download.chapters,
+        subtitles,
+        clips: download.clips,
     }
 }
 
@@ -28,8 +44,31 @@ pub fn convert_download_to_item(download: Download) -> crate::views::downloads::
 #[cfg(feature = "server")]
 pub async fn fetch_downloads() -> Vec<crate::views::downloads::DownloadItem> {
     if let Ok(pool) = get_database().await {
+        // Subtitles are shown with the download they were saved with
+        let mut subtitles: HashMap<i64, Vec<SubtitleFile>> = HashMap::new();
+        match get_all_subtitles(&pool).await {
+            Ok(records) => {
+                for record in records {
+                    subtitles
+                        .entry(record.download_id)
+                        .or_default()
+                        .push(record.to_file());
+                }
+            }
+            Err(e) => tracing::error!("Failed to get subtitles from database: {}", e),
+        }
+
         match get_all_downloads(&pool).await {
-            Ok(results) => results.into_iter().map(convert_download_to_item).collect(),
+            Ok(results) => results
+                .into_iter()
+                .map(|download| {
+                    let download_subtitles = download
+                        .id
+                        .and_then(|id| subtitles.remove(&id))
+                        .unwrap_or_default();
+                    convert_download_to_item(download, download_subtitles)
+                })
+                .collect(),
             Err(e) => {
                 tracing::error!("Failed to get downloads from database: {}", e);
                 Vec::new()
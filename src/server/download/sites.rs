@@ -0,0 +1,121 @@
+// Sites downloads are allowed from
+//
+// yt-dlp can extract video from well over a thousand sites. The allowlist
+// setting narrows that down to the domains the user trusts; while it is
+// empty every site is allowed.
+
+use sqlx::{Pool, Sqlite};
+use tracing;
+
+use crate::database::{
+    get_database,
+    schema::{get_setting, set_setting},
+};
+use crate::server::download::error::DownloaderError;
+use crate::server::download::utils;
+
+/// Settings key of the allowlist, stored as one domain per line
+pub const ALLOWLIST_SETTING: &str = "sites.allowlist";
+
+/// Normalise user input into a list of domains
+///
+/// Accepts bare domains as well as pasted links and `*.` wildcards, so
+/// `https://www.vimeo.com/channels`, `*.vimeo.com` and `vimeo.com` all become
+/// `vimeo.com`. Blank and duplicate entries are dropped.
+pub fn parse_allowlist(input: &str) -> Vec<String> {
+    let mut domains: Vec<String> = Vec::new();
+    for entry in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        let entry = entry.trim().to_lowercase();
+        let entry = entry
+            .split_once("://")
+            .map_or(entry.as_str(), |(_, rest)| rest);
+        let domain = entry.split(['/', '?', '#', ':']).next().unwrap_or_default();
+        let domain = domain.trim_start_matches("*.").trim_start_matches("www.");
+        let domain = domain.trim_matches('.');
+
+        if !domain.is_empty() && !domains.iter().any(|existing| existing == domain) {
+            domains.push(domain.to_string());
+        }
+    }
+    domains
+}
+
+/// Check a host against the allowlist; an empty list allows every host
+///
+/// Subdomains of an allowed domain are allowed too, and `youtu.be` links count
+/// as `youtube.com`.
+pub fn host_allowed(host: &str, allowlist: &[String]) -> bool {
+    if allowlist.is_empty() {
+        return true;
+    }
+
+    let host = host.trim_start_matches("www.");
+    let host = if host == "youtu.be" {
+        "youtube.com"
+    } else {
+        host
+    };
+    allowlist.iter().any(|domain| {
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+/// The saved allowlist, empty when none is set or the database is unavailable
+pub async fn load_allowlist() -> Vec<String> {
+    match get_database().await {
+        Ok(pool) => read_allowlist(&pool).await,
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The allowlist saved in `pool`, empty when none is set
+async fn read_allowlist(pool: &Pool<Sqlite>) -> Vec<String> {
+    match get_setting(pool, ALLOWLIST_SETTING).await {
+        Ok(Some(value)) => parse_allowlist(&value),
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            tracing::error!("Failed to load site allowlist: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Normalise and store the allowlist, returning what was saved
+pub async fn save_allowlist(domains: &[String]) -> Result<Vec<String>, DownloaderError> {
+    let domains = parse_allowlist(&domains.join("\n"));
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    set_setting(&pool, ALLOWLIST_SETTING, &domains.join("\n"))
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to save site allowlist: {}", e)))?;
+
+    tracing::info!("Site allowlist set to {:?}", domains);
+    Ok(domains)
+}
+
+/// Reject URLs whose site isn't on the allowlist
+pub async fn ensure_url_allowed(url: &str) -> Result<(), DownloaderError> {
+    check_url_allowed(url, &load_allowlist().await)
+}
+
+/// Reject URLs whose site isn't on the allowlist saved in `pool`
+pub async fn ensure_url_allowed_in(pool: &Pool<Sqlite>, url: &str) -> Result<(), DownloaderError> {
+    check_url_allowed(url, &read_allowlist(pool).await)
+}
+
+fn check_url_allowed(url: &str, allowlist: &[String]) -> Result<(), DownloaderError> {
+    let host = utils::url_host(url).ok_or_else(|| utils::unsupported_url_error(url))?;
+
+    if host_allowed(&host, allowlist) {
+        Ok(())
+    } else {
+        Err(DownloaderError::InvalidUrl(format!(
+            "Downloads from {} are not allowed. Add the site to the allowlist in Settings.",
+            host
+        )))
+    }
+}
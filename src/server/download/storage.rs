@@ -1,6 +1,9 @@
 use std::path::{Path, PathBuf};
 use tracing;
 
+#[cfg(feature = "server")]
+use crate::server::download::types::PlaylistItem;
+
 #[cfg(feature = "server")]
 pub fn ensure_media_directory() -> Option<PathBuf> {
     if let Some(home_dir) = dirs::home_dir() {
@@ -32,51 +35,90 @@ pub fn ensure_media_directory() -> Option<PathBuf> {
     None
 }
 
+/// Directory finished downloads are moved into
+///
+/// Desktop builds keep media next to the database; other builds use the
+/// Downloads folder, falling back to the media directory.
 #[cfg(feature = "server")]
-pub fn save_file_with_permissions(path: &Path, content: &[u8]) -> bool {
-    // Create parent directories if they don't exist
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                tracing::error!("Failed to create parent directories: {}", e);
-                return false;
-            }
-
-            // Set directory permissions
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if let Err(e) =
-                    std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o755))
-                {
-                    tracing::error!("Failed to set directory permissions: {}", e);
-                }
-            }
+pub fn media_destination() -> Option<PathBuf> {
+    #[cfg(feature = "desktop")]
+    {
+        if let Some(media_dir) = ensure_media_directory() {
+            return Some(media_dir);
         }
     }
 
-    // Write the file
-    if let Err(e) = std::fs::write(path, content) {
-        tracing::error!("Failed to write file: {}", e);
-        return false;
+    dirs::download_dir().or_else(ensure_media_directory)
+}
+
+/// Move a file into `dir` as `file_name` without reading it into memory
+///
+/// Picks a free name rather than overwriting an earlier download, and falls
+/// back to copy-and-delete when `dir` is on another filesystem. Returns the
+/// path the file ended up at.
+#[cfg(feature = "server")]
+pub async fn move_file_with_permissions(
+    source: &Path,
+    dir: &Path,
+    file_name: &str,
+) -> std::io::Result<PathBuf> {
+    tokio::fs::create_dir_all(dir).await?;
+    let target = available_path(dir, file_name);
+
+    if tokio::fs::rename(source, &target).await.is_err() {
+        // `rename` can't cross filesystems; `copy` streams the file instead
+        tokio::fs::copy(source, &target).await?;
+        let _ = tokio::fs::remove_file(source).await;
     }
 
     // Set file permissions
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o644)) {
+        if let Err(e) = std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o644)) {
             tracing::error!("Failed to set file permissions: {}", e);
         }
     }
 
-    true
+    Ok(target)
+}
+
+/// `dir/file_name`, or `dir/name (n).ext` if that file already exists
+#[cfg(feature = "server")]
+fn available_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let name = Path::new(file_name);
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = name
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
 }
 
 #[cfg(feature = "server")]
 pub fn create_clean_filename(title: &str, extension: &str) -> String {
-    let clean_title = title
-        .replace("/", "_")
+    let clean_title = clean_path_component(title);
+
+    if clean_title.is_empty() {
+        format!("video.{}", extension)
+    } else {
+        format!("{}.{}", clean_title, extension)
+    }
+}
+
+/// Replace the characters that aren't allowed in file names
+#[cfg(feature = "server")]
+pub fn clean_path_component(text: &str) -> String {
+    text.replace("/", "_")
         .replace("\\", "_")
         .replace(":", "_")
         .replace("*", "_")
@@ -84,11 +126,102 @@ pub fn create_clean_filename(title: &str, extension: &str) -> String {
         .replace("\"", "_")
         .replace("<", "_")
         .replace(">", "_")
-        .replace("|", "_");
+        .replace("|", "_")
+}
 
-    if clean_title.is_empty() {
-        format!("video.{}", extension)
-    } else {
-        format!("{}.{}", clean_title, extension)
+/// Build the path of a finished download, relative to the media folder
+///
+/// The template may use `{title}`, `{playlist}` and `{playlist_index}`; the
+/// playlist placeholders are empty for downloads that aren't part of a
+/// playlist, and the index is zero-padded to the length of the playlist.
+/// `/` in the template starts a subfolder; the substituted values are cleaned
+/// so they can't add folders of their own or leave the media folder.
+#[cfg(feature = "server")]
+pub fn render_filename(
+    template: &str,
+    title: &str,
+    playlist: Option<&PlaylistItem>,
+    extension: &str,
+) -> PathBuf {
+    let playlist_title = playlist
+        .map(|item| clean_path_component(&item.playlist_title))
+        .unwrap_or_default();
+    let playlist_index = playlist
+        .map(|item| {
+            let width = item.count.to_string().len().max(2);
+            format!("{:0width$}", item.index, width = width)
+        })
+        .unwrap_or_default();
+    let title = clean_path_component(title);
+
+    let mut parts: Vec<String> = template
+        .split(['/', '\\'])
+        .map(|part| {
+            let rendered = fill_placeholders(part, |name| match name {
+                "title" => Some(title.as_str()),
+                "playlist" => Some(playlist_title.as_str()),
+                "playlist_index" => Some(playlist_index.as_str()),
+                _ => None,
+            });
+            // Separators left over from empty placeholders, and `.`/`..`
+            rendered
+                .trim_matches(|c: char| c.is_whitespace() || c == '-' || c == '.')
+                .to_string()
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    let stem = parts.pop().unwrap_or_else(|| "video".to_string());
+    let mut path: PathBuf = parts.into_iter().collect();
+    path.push(format!("{}.{}", stem, extension));
+    path
+}
+
+/// Replace `{name}` placeholders in a single pass, keeping unknown ones as they are
+#[cfg(feature = "server")]
+fn fill_placeholders<'a>(template: &str, value: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| Some((value(&after[..end])?, end)))
+        {
+            Some((replacement, end)) => {
+                output.push_str(replacement);
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = after;
+            }
+        }
     }
+    output.push_str(rest);
+    output
+}
+
+/// File in a job's workspace where yt-dlp records the path of the finished file
+#[cfg(feature = "server")]
+pub const OUTPUT_RECORD_FILE: &str = ".output_path";
+
+/// Directory holding the workspaces of all download jobs
+#[cfg(feature = "server")]
+pub fn workspaces_root() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("youtube_downloader")
+        .join("jobs")
+}
+
+/// Directory yt-dlp writes a job's files to
+///
+/// Each job gets its own directory, kept in app data rather than the temp dir so
+/// partial files survive a restart and a paused job can continue where it stopped.
+#[cfg(feature = "server")]
+pub fn job_workspace(job_id: i64) -> PathBuf {
+    workspaces_root().join(format!("job_{}", job_id))
 }
@@ -0,0 +1,417 @@
+// Channel subscriptions
+//
+// A scheduler task periodically lists the latest uploads of every enabled
+// subscription and queues the ones that are neither in the downloads history
+// nor already seen by the subscription, using the subscription's format and
+// quality. Listing goes through the `ChannelExtractor` trait so the checks can
+// run against a stub instead of yt-dlp and the network.
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use sqlx::{Pool, Sqlite};
+use tokio::sync::Mutex;
+use tracing;
+
+use crate::database::{
+    get_database,
+    models::Subscription,
+    schema::{
+        get_all_subscriptions, get_downloaded_video_ids, get_seen_video_ids,
+        get_subscription_by_url, insert_subscription, mark_video_seen, update_subscription_check,
+    },
+};
+use crate::server::download::cookies;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::job_queue::{self, DownloadQueue};
+use crate::server::download::network;
+use crate::server::download::types::{ChannelUpload, ChannelUploads};
+use crate::server::download::utils;
+use youtube_dl::{YoutubeDl, YoutubeDlOutput};
+
+/// Number of recent uploads looked at on every check
+pub const UPLOADS_PER_CHECK: usize = 15;
+/// How long a subscription waits between checks
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How often the scheduler looks for subscriptions that are due
+const SCHEDULER_TICK: Duration = Duration::from_secs(5 * 60);
+
+/// Lists the uploads of a channel
+pub trait ChannelExtractor: Send + Sync {
+    /// The channel's name and up to `limit` of its most recent uploads, newest first
+    fn latest_uploads(
+        &self,
+        url: &str,
+        limit: usize,
+    ) -> impl Future<Output = Result<ChannelUploads, DownloaderError>> + Send;
+}
+
+/// Lists uploads with `yt-dlp --flat-playlist`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YtDlpExtractor;
+
+impl ChannelExtractor for YtDlpExtractor {
+    fn latest_uploads(
+        &self,
+        url: &str,
+        limit: usize,
+    ) -> impl Future<Output = Result<ChannelUploads, DownloaderError>> + Send {
+        let mut youtube_dl = YoutubeDl::new(url);
+        // Only the video IDs are needed; the jobs fetch the details later
+        youtube_dl.extra_arg("--flat-playlist");
+        youtube_dl.extra_arg("--playlist-end");
+        youtube_dl.extra_arg(limit.to_string());
+        let url = url.to_string();
+
+        async move {
+            let settings = network::load().await;
+            youtube_dl.socket_timeout(settings.socket_timeout());
+            for arg in settings.args() {
+                youtube_dl.extra_arg(arg);
+            }
+            for arg in cookies::args_for(&url).await {
+                youtube_dl.extra_arg(arg);
+            }
+
+            let output =
+                match tokio::time::timeout(Duration::from_secs(60), youtube_dl.run_async()).await {
+                    Ok(output) => output.map_err(|e| {
+                        DownloaderError::from_ytdlp_output(format!("Error listing uploads: {}", e))
+                    })?,
+                    Err(_) => {
+                        return Err(DownloaderError::Timeout(
+                            "Timed out while listing the channel's uploads".to_string(),
+                        ))
+                    }
+                };
+
+            let channel = match output {
+                YoutubeDlOutput::Playlist(channel) => channel,
+                YoutubeDlOutput::SingleVideo(_) => {
+                    return Err(DownloaderError::InvalidUrl(
+                        "URL points to a single video, not a channel".to_string(),
+                    ))
+                }
+            };
+
+            let uploads = channel
+                .entries
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .take(limit)
+                .map(|video| ChannelUpload {
+                    url: utils::flat_entry_url(
+                        &video.id,
+                        video.webpage_url.as_deref(),
+                        video.url.as_deref(),
+                    ),
+                    video_id: video.id,
+                    title: video.title,
+                })
+                .collect();
+
+            Ok(ChannelUploads {
+                // The playlist title names the tab, e.g. "Name - Videos"
+                title: channel
+                    .uploader
+                    .clone()
+                    .or_else(|| channel.title.clone())
+                    .unwrap_or_else(|| "Unknown channel".to_string()),
+                uploads,
+            })
+        }
+    }
+}
+
+/// Serialises checks, so a manual check can't queue an upload the scheduler is queueing too
+fn check_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// Subscribe to a channel's uploads page
+///
+/// The uploads the channel already has are marked as seen, so subscribing only
+/// downloads what the channel uploads from now on.
+pub async fn subscribe(
+    pool: &Pool<Sqlite>,
+    extractor: &impl ChannelExtractor,
+    url: String,
+    format_type: String,
+    quality: String,
+) -> Result<Subscription, DownloaderError> {
+    let database_error =
+        |e: sqlx::Error| DownloaderError::Database(format!("Failed to save subscription: {}", e));
+
+    if let Some(existing) = get_subscription_by_url(pool, &url)
+        .await
+        .map_err(database_error)?
+    {
+        return Err(DownloaderError::InvalidUrl(format!(
+            "Already subscribed to {}",
+            existing.title
+        )));
+    }
+
+    // Listing the uploads also checks that the URL is a channel we can read
+    let channel = extractor.latest_uploads(&url, UPLOADS_PER_CHECK).await?;
+
+    let mut subscription = Subscription::new(url, channel.title, format_type, quality);
+    let id = insert_subscription(pool, &subscription)
+        .await
+        .map_err(database_error)?;
+    subscription.id = Some(id);
+
+    for upload in &channel.uploads {
+        mark_video_seen(pool, id, &upload.video_id)
+            .await
+            .map_err(database_error)?;
+    }
+    update_subscription_check(pool, id, None)
+        .await
+        .map_err(database_error)?;
+    subscription.last_checked_at = Some(time::OffsetDateTime::now_utc());
+
+    tracing::info!(
+        "Subscribed to {} ({} existing uploads)",
+        subscription.title,
+        channel.uploads.len()
+    );
+    Ok(subscription)
+}
+
+/// List a subscription's latest uploads and queue the new ones, oldest first
+///
+/// Returns the IDs of the queued jobs. Uploads that are already in the
+/// downloads history are marked as seen without being queued.
+pub async fn check_subscription(
+    pool: &Pool<Sqlite>,
+    queue: &DownloadQueue,
+    extractor: &impl ChannelExtractor,
+    subscription: &Subscription,
+) -> Result<Vec<i64>, DownloaderError> {
+    let subscription_id = subscription.id.unwrap_or_default();
+    let channel = extractor
+        .latest_uploads(&subscription.url, UPLOADS_PER_CHECK)
+        .await?;
+
+    let database_error =
+        |e: sqlx::Error| DownloaderError::Database(format!("Failed to check subscription: {}", e));
+    let seen = get_seen_video_ids(pool, subscription_id)
+        .await
+        .map_err(database_error)?;
+    let downloaded = get_downloaded_video_ids(pool)
+        .await
+        .map_err(database_error)?;
+
+    let mut job_ids = Vec::new();
+    for upload in channel.uploads.iter().rev() {
+        if seen.contains(&upload.video_id) {
+            continue;
+        }
+
+        if downloaded.contains(&upload.video_id) {
+            tracing::info!(
+                "Skipping {} for {}: already downloaded",
+                upload.video_id,
+                subscription.title
+            );
+        } else {
+            let job_id = job_queue::enqueue_into(
+                pool,
+                queue,
+                upload.url.clone(),
+                subscription.download_options(),
+            )
+            .await?;
+            tracing::info!(
+                "Queued new upload {} from {} as job {}",
+                upload.video_id,
+                subscription.title,
+                job_id
+            );
+            job_ids.push(job_id);
+        }
+
+        // Only after queueing, so a failed enqueue is retried on the next check
+        mark_video_seen(pool, subscription_id, &upload.video_id)
+            .await
+            .map_err(database_error)?;
+    }
+
+    Ok(job_ids)
+}
+
+/// Check enabled subscriptions, returning the number of queued downloads
+///
+/// With `only_due`, subscriptions checked less than `CHECK_INTERVAL` ago are
+/// skipped. The outcome of each check is stored on the subscription.
+pub async fn check_subscriptions(
+    pool: &Pool<Sqlite>,
+    queue: &DownloadQueue,
+    extractor: &impl ChannelExtractor,
+    only_due: bool,
+) -> Result<usize, DownloaderError> {
+    let _guard = check_lock().lock().await;
+
+    let subscriptions = get_all_subscriptions(pool)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to load subscriptions: {}", e)))?;
+    let now = time::OffsetDateTime::now_utc();
+
+    let mut queued = 0;
+    for subscription in subscriptions.iter().filter(|s| s.enabled) {
+        let due = subscription
+            .last_checked_at
+            .map_or(true, |checked| now - checked >= CHECK_INTERVAL);
+        if only_due && !due {
+            continue;
+        }
+
+        let id = subscription.id.unwrap_or_default();
+        let error = match check_subscription(pool, queue, extractor, subscription).await {
+            Ok(job_ids) => {
+                queued += job_ids.len();
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to check subscription {}: {}", subscription.title, e);
+                Some(e.to_string())
+            }
+        };
+
+        if let Err(e) = update_subscription_check(pool, id, error.as_deref()).await {
+            tracing::error!("Failed to record check of subscription {}: {}", id, e);
+        }
+    }
+
+    Ok(queued)
+}
+
+/// Start the task that checks subscriptions in the background, once
+pub fn ensure_scheduler_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        tokio::spawn(schedule_loop(YtDlpExtractor));
+    });
+}
+
+async fn schedule_loop(extractor: YtDlpExtractor) {
+    let queue = job_queue::ensure_queue_started().await;
+    loop {
+        match get_database().await {
+            Ok(pool) => match check_subscriptions(&pool, queue, &extractor, true).await {
+                Ok(0) => {}
+                Ok(queued) => tracing::info!("Subscriptions queued {} new uploads", queued),
+                Err(e) => tracing::error!("Failed to check subscriptions: {}", e),
+            },
+            Err(e) => tracing::error!("Database unavailable for subscriptions: {}", e),
+        }
+
+        tokio::time::sleep(SCHEDULER_TICK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        get_memory_database,
+        models::Download,
+        schema::{get_all_jobs, save_download},
+    };
+    use crate::server::download::job_queue::DEFAULT_CONCURRENCY;
+
+    /// Lists a fixed set of uploads instead of asking yt-dlp
+    struct FakeExtractor {
+        /// Video IDs, newest first
+        uploads: Vec<&'static str>,
+    }
+
+    impl ChannelExtractor for FakeExtractor {
+        fn latest_uploads(
+            &self,
+            _url: &str,
+            limit: usize,
+        ) -> impl Future<Output = Result<ChannelUploads, DownloaderError>> + Send {
+            let uploads = self
+                .uploads
+                .iter()
+                .take(limit)
+                .map(|video_id| ChannelUpload {
+                    video_id: video_id.to_string(),
+                    url: format!("https://www.youtube.com/watch?v={}", video_id),
+                    title: Some(format!("Upload {}", video_id)),
+                })
+                .collect();
+
+            async move {
+                Ok(ChannelUploads {
+                    title: "Test channel".to_string(),
+                    uploads,
+                })
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn check_queues_only_uploads_not_downloaded() {
+        // A queue of its own without a dispatcher, so nothing gets downloaded
+        let pool = get_memory_database().await.unwrap();
+        let queue = DownloadQueue::new(DEFAULT_CONCURRENCY);
+        let mut subscription = Subscription::new(
+            "https://www.youtube.com/@subscription-test/videos".to_string(),
+            "Test channel".to_string(),
+            "audio".to_string(),
+            "lowest".to_string(),
+        );
+        subscription.id = Some(insert_subscription(&pool, &subscription).await.unwrap());
+
+        let downloaded = Download::new(
+            "https://www.youtube.com/watch?v=subOldVid01".to_string(),
+            Some("Downloaded before".to_string()),
+            "Downloaded before.mp4".to_string(),
+            "/tmp/Downloaded before.mp4".to_string(),
+            "video".to_string(),
+            "highest".to_string(),
+            None,
+            None,
+            Some("subOldVid01".to_string()),
+            None,
+        );
+        save_download(&pool, &downloaded).await.unwrap();
+
+        let extractor = FakeExtractor {
+            uploads: vec!["subNewVid02", "subOldVid01", "subNewVid01"],
+        };
+        let job_ids = check_subscription(&pool, &queue, &extractor, &subscription)
+            .await
+            .unwrap();
+
+        // Oldest first, with the subscription's format and quality
+        let jobs = get_all_jobs(&pool).await.unwrap();
+        let queued: Vec<_> = job_ids
+            .iter()
+            .map(|id| jobs.iter().find(|job| job.id == Some(*id)).unwrap())
+            .collect();
+        let urls: Vec<&str> = queued.iter().map(|job| job.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.youtube.com/watch?v=subNewVid01",
+                "https://www.youtube.com/watch?v=subNewVid02",
+            ]
+        );
+        for job in queued {
+            assert_eq!(job.download_options(), subscription.download_options());
+        }
+
+        // Everything listed is seen now, so a second check queues nothing
+        let job_ids = check_subscription(&pool, &queue, &extractor, &subscription)
+            .await
+            .unwrap();
+        assert!(job_ids.is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+// Subtitles fetched along with downloads
+//
+// yt-dlp writes subtitles into the job's workspace next to the media, named
+// `<output>.<language>.<format>`. They are then either embedded in the video
+// by yt-dlp or moved next to the saved file as sidecar files.
+
+use std::path::{Path, PathBuf};
+use tracing;
+
+use crate::server::download::storage;
+use crate::server::download::types::{SubtitleFormat, SubtitleOptions};
+use crate::server::download::ytdlp::YtDlpDownload;
+
+/// Language yt-dlp lists live chat replays under, which aren't subtitles
+pub const LIVE_CHAT: &str = "live_chat";
+
+/// Add the yt-dlp options that fetch the subtitles
+///
+/// `embed` is only honoured for video downloads by the caller; audio files
+/// always get sidecar files.
+pub fn configure(download: &mut YtDlpDownload, options: &SubtitleOptions, embed: bool) {
+    // `--write-subs` also keeps the files yt-dlp embeds, so they can be recorded
+    download.extra_arg("--write-subs");
+    if options.auto_generated {
+        download.extra_arg("--write-auto-subs");
+    }
+    download.extra_arg("--sub-langs");
+    download.extra_arg(options.languages.join(","));
+    download.extra_arg("--convert-subs");
+    download.extra_arg(options.format.as_str());
+    if embed {
+        download.extra_arg("--embed-subs");
+    }
+}
+
+/// Subtitle files yt-dlp wrote to a workspace, with their language
+pub fn find_in_workspace(workspace: &Path, format: SubtitleFormat) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(workspace) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == format.as_str()))
+        .filter_map(|path| {
+            // `video.en.srt` holds the English subtitles
+            let language = Path::new(path.file_stem()?)
+                .extension()?
+                .to_string_lossy()
+                .to_string();
+            Some((language, path))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Move subtitle files next to the saved media, named after it
+///
+/// `clip.mp4` gets `clip.en.srt`. Returns the language and new path of every
+/// file that was moved.
+pub async fn save_sidecars(
+    files: Vec<(String, PathBuf)>,
+    media_path: &Path,
+) -> Vec<(String, PathBuf)> {
+    let (Some(dir), Some(stem)) = (media_path.parent(), media_path.file_stem()) else {
+        return Vec::new();
+    };
+    let stem = stem.to_string_lossy();
+
+    let mut saved = Vec::new();
+    for (language, path) in files {
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        let name = format!("{}.{}.{}", stem, language, extension);
+        match storage::move_file_with_permissions(&path, dir, &name).await {
+            Ok(target) => saved.push((language, target)),
+            Err(e) => tracing::warn!("Failed to save {} subtitles: {}", language, e),
+        }
+    }
+    saved
+}
@@ -0,0 +1,313 @@
+// Metadata and cover art written into downloaded files
+//
+// Tags are taken from the stored download record rather than from yt-dlp, so
+// they can be written again later. ffmpeg copies the streams into a new file
+// with the tags, which then replaces the original: ID3 for MP3, atoms for
+// MP4/M4A and Vorbis comments for Opus and FLAC.
+
+use base64::{engine::general_purpose, Engine};
+use std::path::{Path, PathBuf};
+use tracing;
+
+use crate::database::{
+    get_database,
+    models::Download,
+    schema::{get_download_by_id, update_download_file_size},
+};
+use crate::server::download::error::DownloaderError;
+
+/// Tags written into a file
+#[derive(Debug, Clone, Default)]
+pub struct MediaTags {
+    pub title: String,
+    pub artist: Option<String>,
+    /// Upload date as "YYYY-MM-DD"
+    pub date: Option<String>,
+    pub description: Option<String>,
+    /// Link the file was downloaded from
+    pub comment: String,
+    pub cover_url: Option<String>,
+}
+
+impl MediaTags {
+    pub fn from_download(download: &Download) -> Self {
+        Self {
+            title: download.title.clone(),
+            artist: download.uploader.clone(),
+            date: download.upload_date.as_deref().map(format_date),
+            description: download
+                .description
+                .clone()
+                .filter(|description| !description.trim().is_empty()),
+            comment: download.url.clone(),
+            cover_url: download.thumbnail_url.clone(),
+        }
+    }
+
+    fn entries(&self) -> Vec<(&'static str, &str)> {
+        let mut entries = vec![("title", self.title.as_str())];
+        if let Some(artist) = &self.artist {
+            entries.push(("artist", artist));
+        }
+        if let Some(date) = &self.date {
+            entries.push(("date", date));
+        }
+        if let Some(description) = &self.description {
+            entries.push(("description", description));
+        }
+        entries.push(("comment", &self.comment));
+        entries
+    }
+}
+
+/// "20240131" becomes "2024-01-31"; anything else is kept as it is
+fn format_date(date: &str) -> String {
+    if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) {
+        format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])
+    } else {
+        date.to_string()
+    }
+}
+
+/// How the cover art gets into a container
+enum CoverArt {
+    /// Extra video stream flagged as the attached picture
+    AttachedPic,
+    /// Matroska file attachment
+    Attachment,
+    /// Base64 FLAC picture block in a Vorbis comment
+    PictureBlock,
+    None,
+}
+
+fn cover_art_for(extension: &str) -> CoverArt {
+    match extension {
+        "mp3" | "m4a" | "mp4" | "mov" | "flac" => CoverArt::AttachedPic,
+        "mkv" | "mka" => CoverArt::Attachment,
+        "opus" | "ogg" => CoverArt::PictureBlock,
+        _ => CoverArt::None,
+    }
+}
+
+/// Tag the file of a stored download and record its new size
+///
+/// Returns the size of the tagged file.
+pub async fn tag_download(download_id: i64) -> Result<u64, DownloaderError> {
+    let pool = get_database()
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Database unavailable: {}", e)))?;
+    let download = get_download_by_id(&pool, download_id)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to load download: {}", e)))?
+        .ok_or_else(|| DownloaderError::NotFound(format!("Download {} not found", download_id)))?;
+
+    let path = PathBuf::from(&download.file_path);
+    if !path.exists() {
+        return Err(DownloaderError::NotFound(format!(
+            "File not found: {}",
+            download.file_path
+        )));
+    }
+
+    let is_video = download.format_type.eq_ignore_ascii_case("video");
+    let file_size = tag_file(&path, &MediaTags::from_download(&download), is_video).await?;
+
+    update_download_file_size(&pool, download_id, file_size as i64)
+        .await
+        .map_err(|e| DownloaderError::Database(format!("Failed to update file size: {}", e)))?;
+    Ok(file_size)
+}
+
+/// Write tags and cover art into a file in place, returning its new size
+///
+/// The cover is left out when the thumbnail can't be fetched, and the
+/// original file is kept untouched when ffmpeg fails.
+pub async fn tag_file(
+    path: &Path,
+    tags: &MediaTags,
+    is_video: bool,
+) -> Result<u64, DownloaderError> {
+    let (Some(dir), Some(stem), Some(extension)) =
+        (path.parent(), path.file_stem(), path.extension())
+    else {
+        return Err(DownloaderError::Storage(format!(
+            "Can't tag {}",
+            path.display()
+        )));
+    };
+    let stem = stem.to_string_lossy();
+    let extension = extension.to_string_lossy().to_lowercase();
+    let metadata_path = dir.join(format!(".{}.tags.txt", stem));
+    let cover_path = dir.join(format!(".{}.cover.jpg", stem));
+    let tagged_path = dir.join(format!(".{}.tagging.{}", stem, extension));
+
+    let cover_art = cover_art_for(&extension);
+    let has_cover = match (&cover_art, &tags.cover_url) {
+        (CoverArt::None, _) | (_, None) => false,
+        (_, Some(url)) => match fetch_cover(url, &cover_path).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Leaving out cover art for {}: {}", path.display(), e);
+                false
+            }
+        },
+    };
+
+    let mut entries: Vec<(&str, String)> = tags
+        .entries()
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+    if has_cover && matches!(cover_art, CoverArt::PictureBlock) {
+        match tokio::fs::read(&cover_path).await {
+            Ok(image) => entries.push(("METADATA_BLOCK_PICTURE", picture_block(&image))),
+            Err(e) => tracing::warn!("Failed to read cover art: {}", e),
+        }
+    }
+
+    let result = async {
+        tokio::fs::write(&metadata_path, ffmetadata(&entries))
+            .await
+            .map_err(|e| DownloaderError::Storage(format!("Failed to write tags: {}", e)))?;
+
+        let mut command = tokio::process::Command::new("ffmpeg");
+        command
+            .args(["-y", "-loglevel", "error", "-i"])
+            .arg(path)
+            .arg("-i")
+            .arg(&metadata_path);
+        let attach_pic = has_cover && matches!(cover_art, CoverArt::AttachedPic);
+        if attach_pic {
+            command.arg("-i").arg(&cover_path);
+        }
+
+        // `V` leaves out pictures the file already had, which ours replaces
+        command.args([
+            "-map",
+            "0:V?",
+            "-map",
+            "0:a?",
+            "-map",
+            "0:s?",
+            "-map_metadata",
+            "1",
+        ]);
+        if attach_pic {
+            let index = if is_video { 1 } else { 0 };
+            command
+                .args(["-map", "2:v"])
+                .arg(format!("-disposition:v:{}", index))
+                .arg("attached_pic");
+        }
+        if has_cover && matches!(cover_art, CoverArt::Attachment) {
+            command
+                .arg("-attach")
+                .arg(&cover_path)
+                .args(["-metadata:s:t", "mimetype=image/jpeg"])
+                .args(["-metadata:s:t", "filename=cover.jpg"]);
+        }
+        command.args(["-c", "copy"]);
+        if extension == "mp3" {
+            // Some players only read ID3v2.3, not the v2.4 ffmpeg writes by default
+            command.args(["-id3v2_version", "3"]);
+        }
+        command.arg(&tagged_path);
+
+        let output = command
+            .output()
+            .await
+            .map_err(|e| DownloaderError::Internal(format!("Failed to start ffmpeg: {}", e)))?;
+        if !output.status.success() {
+            return Err(DownloaderError::Internal(format!(
+                "ffmpeg failed to tag {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        tokio::fs::rename(&tagged_path, path)
+            .await
+            .map_err(|e| DownloaderError::Storage(format!("Failed to replace file: {}", e)))?;
+        tokio::fs::metadata(path)
+            .await
+            .map(|metadata| metadata.len())
+            .map_err(|e| DownloaderError::Storage(format!("Failed to read file size: {}", e)))
+    }
+    .await;
+
+    for leftover in [&metadata_path, &cover_path, &tagged_path] {
+        let _ = tokio::fs::remove_file(leftover).await;
+    }
+
+    if result.is_ok() {
+        tracing::info!("Tagged {}", path.display());
+    }
+    result
+}
+
+/// Fetch a thumbnail and convert it to JPEG, which every container takes
+async fn fetch_cover(url: &str, target: &Path) -> Result<(), DownloaderError> {
+    let output = tokio::process::Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-i",
+            url,
+            "-frames:v",
+            "1",
+            "-q:v",
+            "2",
+        ])
+        .arg(target)
+        .output()
+        .await
+        .map_err(|e| DownloaderError::Internal(format!("Failed to start ffmpeg: {}", e)))?;
+    if !output.status.success() {
+        return Err(DownloaderError::Network(format!(
+            "Failed to fetch thumbnail: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// ffmpeg metadata file holding the tags
+///
+/// Using a file keeps long descriptions and the picture block off the command
+/// line.
+fn ffmetadata(entries: &[(&str, String)]) -> String {
+    let mut content = String::from(";FFMETADATA1\n");
+    for (key, value) in entries {
+        content.push_str(key);
+        content.push('=');
+        for c in value.chars() {
+            if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                content.push('\\');
+            }
+            content.push(c);
+        }
+        content.push('\n');
+    }
+    content
+}
+
+/// FLAC picture block holding a JPEG front cover, base64-encoded
+///
+/// Width, height and colour depth may be left at zero, which players accept.
+fn picture_block(image: &[u8]) -> String {
+    const FRONT_COVER: u32 = 3;
+    const MIME_TYPE: &str = "image/jpeg";
+
+    let mut block = Vec::with_capacity(image.len() + 32 + MIME_TYPE.len());
+    block.extend_from_slice(&FRONT_COVER.to_be_bytes());
+    block.extend_from_slice(&(MIME_TYPE.len() as u32).to_be_bytes());
+    block.extend_from_slice(MIME_TYPE.as_bytes());
+    // Empty description, then width, height, depth and colour count
+    for field in [0u32; 5] {
+        block.extend_from_slice(&field.to_be_bytes());
+    }
+    block.extend_from_slice(&(image.len() as u32).to_be_bytes());
+    block.extend_from_slice(image);
+    general_purpose::STANDARD.encode(block)
+}
@@ -2,23 +2,1110 @@ use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 
 /// Structure to track download progress
-#[cfg(feature = "server")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DownloadProgress {
+    /// Bytes downloaded of the current stream
     pub downloaded_bytes: u64,
+    /// Size of the current stream, 0 when unknown
     pub total_bytes: u64,
+    /// Percentage of the current stream reported by yt-dlp
+    pub percent: f64,
+    pub speed_bytes_per_sec: u64,
     pub eta_seconds: u64,
+    pub fragment_index: Option<u32>,
+    pub fragment_count: Option<u32>,
+    /// Stream being downloaded, e.g. "video.f137.mp4"
+    pub stream: Option<String>,
+    /// 1-based position of the stream among those yt-dlp downloads
+    pub stream_index: u32,
+    /// Seconds recorded so far, for live streams; they have no percentage
+    #[serde(default)]
+    pub recording_secs: Option<u64>,
     pub status: String,
 }
 
-#[cfg(feature = "server")]
 impl Default for DownloadProgress {
     fn default() -> Self {
         Self {
             downloaded_bytes: 0,
             total_bytes: 0,
+            percent: 0.0,
+            speed_bytes_per_sec: 0,
             eta_seconds: 0,
+            fragment_index: None,
+            fragment_count: None,
+            stream: None,
+            stream_index: 0,
+            recording_secs: None,
             status: "Initializing...".to_string(),
         }
     }
 }
+
+/// Progress reported by a yt-dlp `[download]` line
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub percent: f64,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+    /// Whether yt-dlp marked the total as an estimate (`~`)
+    pub total_is_estimate: bool,
+    pub speed_bytes_per_sec: Option<u64>,
+    pub eta_seconds: Option<u64>,
+    pub fragment_index: Option<u32>,
+    pub fragment_count: Option<u32>,
+}
+
+/// A line of yt-dlp output we know how to interpret
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum YtDlpEvent {
+    /// A new stream started downloading to this path
+    Destination(String),
+    /// Progress of the current stream
+    Progress(ProgressEvent),
+    /// A post-processor (merger, audio extraction, ...) is running
+    PostProcessing(String),
+}
+
+/// What yt-dlp reported about a video before downloading it
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VideoMetadata {
+    pub title: String,
+    /// yt-dlp extractor handling the site, e.g. "youtube" or "vimeo"
+    pub extractor: Option<String>,
+    /// ID the extractor gives the video
+    pub video_id: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub duration_secs: u64,
+    /// Size of the largest format, 0 when unknown
+    pub estimated_size: u64,
+    /// Languages with manual subtitles; the others are auto-generated
+    pub subtitle_languages: Vec<String>,
+    pub chapters: Vec<Chapter>,
+    /// Channel or user that uploaded the video
+    pub uploader: Option<String>,
+    /// Upload date as "YYYYMMDD"
+    pub upload_date: Option<String>,
+    pub description: Option<String>,
+    /// Whether the video is a stream that is live right now
+    pub is_live: bool,
+}
+
+/// Options describing how a queued download should be fetched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadOptions {
+    /// Format type (video or audio)
+    pub format_type: String,
+    /// Quality setting (highest, medium or lowest)
+    pub quality: String,
+    /// The playlist the download was queued from, if any
+    #[serde(default)]
+    pub playlist: Option<PlaylistItem>,
+    /// Template for the saved file's name, see `storage::render_filename`
+    ///
+    /// Defaults to `DEFAULT_FILENAME_TEMPLATE`, or `PLAYLIST_FILENAME_TEMPLATE`
+    /// for playlist items.
+    #[serde(default)]
+    pub filename_template: Option<String>,
+    /// yt-dlp format to download, e.g. "137+140", overriding `quality`
+    #[serde(default)]
+    pub format_id: Option<String>,
+    /// Subtitles to fetch with the download
+    #[serde(default)]
+    pub subtitles: Option<SubtitleOptions>,
+    /// Split audio downloads into one file per chapter
+    #[serde(default)]
+    pub split_chapters: bool,
+    /// Only download these parts of the video, joined into one file
+    #[serde(default)]
+    pub clips: Vec<ClipRange>,
+    /// How to record the video if it turns out to be a live stream
+    #[serde(default)]
+    pub live: LiveOptions,
+    /// Download speed cap in KiB/s, overriding the one in the network settings
+    #[serde(default)]
+    pub rate_limit_kib: Option<u64>,
+    /// Earlier download the new file replaces; it is deleted once the new one is in
+    #[serde(default)]
+    pub replaces: Option<i64>,
+    /// Download even if the video is already in the library in this format and quality
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+impl DownloadOptions {
+    pub fn new(format_type: impl Into<String>, quality: impl Into<String>) -> Self {
+        Self {
+            format_type: format_type.into(),
+            quality: quality.into(),
+            playlist: None,
+            filename_template: None,
+            format_id: None,
+            subtitles: None,
+            split_chapters: false,
+            clips: Vec::new(),
+            live: LiveOptions::default(),
+            rate_limit_kib: None,
+            replaces: None,
+            allow_duplicate: false,
+        }
+    }
+
+    /// Whether an earlier copy in the library stops the download from being queued
+    ///
+    /// Clips and replacements are new files by design, so only whole-video
+    /// downloads are checked.
+    pub fn checks_for_duplicates(&self) -> bool {
+        !self.allow_duplicate && self.replaces.is_none() && self.clips.is_empty()
+    }
+
+    /// Mark the download as an item of a playlist
+    pub fn in_playlist(mut self, playlist: PlaylistItem) -> Self {
+        self.playlist = Some(playlist);
+        self
+    }
+
+    /// The filename template to use, falling back to the defaults
+    pub fn filename_template(&self) -> &str {
+        match (&self.filename_template, &self.playlist) {
+            (Some(template), _) => template,
+            (None, Some(_)) => PLAYLIST_FILENAME_TEMPLATE,
+            (None, None) => DEFAULT_FILENAME_TEMPLATE,
+        }
+    }
+}
+
+/// Filename template for single downloads
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{title}";
+/// Filename template for playlist items: a folder per playlist, numbered files
+pub const PLAYLIST_FILENAME_TEMPLATE: &str = "{playlist}/{playlist_index} - {title}";
+
+/// Video codec to prefer when several are offered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VideoCodec {
+    Av1,
+    Vp9,
+    H264,
+}
+
+impl VideoCodec {
+    pub const ALL: [VideoCodec; 3] = [VideoCodec::Av1, VideoCodec::Vp9, VideoCodec::H264];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoCodec::Av1 => "av1",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::H264 => "h264",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|codec| codec.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VideoCodec::Av1 => "AV1",
+            VideoCodec::Vp9 => "VP9",
+            VideoCodec::H264 => "H.264",
+        }
+    }
+
+    /// Name of the codec in yt-dlp's format sorting
+    fn sort_name(&self) -> &'static str {
+        match self {
+            VideoCodec::Av1 => "av01",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::H264 => "h264",
+        }
+    }
+}
+
+/// Container the video and audio streams are merged into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Container {
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl Container {
+    pub const ALL: [Container; 3] = [Container::Mp4, Container::Mkv, Container::Webm];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|container| container.as_str() == value)
+    }
+}
+
+/// Resolution cap, codec preference and container of a video download
+///
+/// Stored in the `quality` column as space separated words, e.g. "highest",
+/// "1080p" or "2160p av1 mkv". "medium" is kept as the older name of "720p".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VideoQuality {
+    /// Tallest resolution to download, `None` for the best available
+    pub max_height: Option<u32>,
+    /// Pick the smallest formats instead of the best ones
+    pub lowest: bool,
+    pub codec: Option<VideoCodec>,
+    /// Defaults to mp4
+    pub container: Option<Container>,
+}
+
+impl VideoQuality {
+    /// Resolution caps offered for video downloads
+    pub const HEIGHT_PRESETS: [u32; 5] = [480, 720, 1080, 1440, 2160];
+
+    /// Parse a `quality` value; unknown words are ignored
+    pub fn parse(value: &str) -> Self {
+        let mut quality = Self::default();
+        for word in value.to_lowercase().split_whitespace() {
+            match word {
+                "lowest" => quality.lowest = true,
+                "medium" => quality.max_height = Some(720),
+                _ => {
+                    if let Some(height) = word.strip_suffix('p').and_then(|h| h.parse().ok()) {
+                        quality.max_height = Some(height);
+                    } else if let Some(codec) = VideoCodec::from_name(word) {
+                        quality.codec = Some(codec);
+                    } else if let Some(container) = Container::from_name(word) {
+                        quality.container = Some(container);
+                    }
+                }
+            }
+        }
+        quality
+    }
+
+    pub fn container(&self) -> Container {
+        self.container.unwrap_or(Container::Mp4)
+    }
+
+    /// yt-dlp format selector; which formats win is decided by `sort_order`
+    pub fn format_selector(&self) -> &'static str {
+        if self.lowest {
+            "worstvideo*+worstaudio/worst"
+        } else {
+            "bestvideo*+bestaudio/best"
+        }
+    }
+
+    /// yt-dlp format sort order (`-S`) implementing the cap and preferences
+    ///
+    /// Resolution always comes first, so a codec or container preference never
+    /// costs resolution. Without a codec preference, mp4 output prefers the
+    /// H.264/AAC streams that merge into mp4 without re-encoding.
+    pub fn sort_order(&self) -> String {
+        let mut fields = vec![match self.max_height {
+            Some(height) => format!("res:{}", height),
+            None => "res".to_string(),
+        }];
+        match (self.codec, self.container()) {
+            (Some(codec), _) => fields.push(format!("vcodec:{}", codec.sort_name())),
+            (None, Container::Mp4) => fields.push("ext:mp4:m4a".to_string()),
+            (None, Container::Webm) => fields.push("ext:webm:webm".to_string()),
+            (None, Container::Mkv) => {}
+        }
+        fields.join(",")
+    }
+}
+
+impl std::fmt::Display for VideoQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut words = vec![match (self.lowest, self.max_height) {
+            (true, _) => "lowest".to_string(),
+            (false, Some(height)) => format!("{}p", height),
+            (false, None) => "highest".to_string(),
+        }];
+        if let Some(codec) = self.codec {
+            words.push(codec.as_str().to_string());
+        }
+        if let Some(container) = self.container {
+            words.push(container.as_str().to_string());
+        }
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// File format audio downloads are saved in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AudioFormat {
+    #[default]
+    Mp3,
+    Opus,
+    M4a,
+    Flac,
+    Wav,
+    /// The audio stream as the site serves it, without re-encoding
+    Original,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 6] = [
+        AudioFormat::Mp3,
+        AudioFormat::Opus,
+        AudioFormat::M4a,
+        AudioFormat::Flac,
+        AudioFormat::Wav,
+        AudioFormat::Original,
+    ];
+    /// Target bitrates offered for lossy formats, in kbit/s
+    pub const BITRATE_PRESETS: [u32; 6] = [96, 128, 160, 192, 256, 320];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Original => "original",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "MP3",
+            AudioFormat::Opus => "Opus",
+            AudioFormat::M4a => "M4A (AAC)",
+            AudioFormat::Flac => "FLAC",
+            AudioFormat::Wav => "WAV",
+            AudioFormat::Original => "Original",
+        }
+    }
+
+    /// Extension of the saved file
+    ///
+    /// Original audio is taken from the m4a stream when there is one, which
+    /// every YouTube video has; other sites may serve another container.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Original => "m4a",
+            format => format.as_str(),
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Opus => "audio/ogg",
+            AudioFormat::M4a | AudioFormat::Original => "audio/mp4",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Wav => "audio/wav",
+        }
+    }
+
+    /// Whether the format takes a target bitrate
+    pub fn is_lossy(&self) -> bool {
+        matches!(
+            self,
+            AudioFormat::Mp3 | AudioFormat::Opus | AudioFormat::M4a
+        )
+    }
+}
+
+/// Format and bitrate of an audio download
+///
+/// Stored in the `quality` column as e.g. "mp3", "opus 160k" or "flac". The
+/// older "highest", "medium" and "lowest" values all mean MP3 at the best
+/// variable bitrate, which is what audio downloads used to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AudioQuality {
+    pub format: AudioFormat,
+    /// Target bitrate in kbit/s for lossy formats, `None` for the best VBR
+    pub bitrate_kbps: Option<u32>,
+}
+
+impl AudioQuality {
+    /// Parse a `quality` value; unknown words are ignored
+    pub fn parse(value: &str) -> Self {
+        let mut quality = Self::default();
+        for word in value.to_lowercase().split_whitespace() {
+            if let Some(format) = AudioFormat::from_name(word) {
+                quality.format = format;
+            } else if let Some(kbps) = word.strip_suffix('k').and_then(|k| k.parse().ok()) {
+                quality.bitrate_kbps = Some(kbps);
+            }
+        }
+        quality
+    }
+
+    /// yt-dlp format selector, preferring streams that need no conversion
+    pub fn format_selector(&self) -> &'static str {
+        match self.format {
+            AudioFormat::Opus => "bestaudio[acodec=opus]/bestaudio/best",
+            AudioFormat::M4a | AudioFormat::Original => "bestaudio[ext=m4a]/bestaudio/best",
+            _ => "bestaudio/best",
+        }
+    }
+
+    /// Value for yt-dlp's `--audio-format`
+    pub fn ytdlp_audio_format(&self) -> &'static str {
+        match self.format {
+            // "best" keeps the stream as it is whenever possible
+            AudioFormat::Original => "best",
+            format => format.as_str(),
+        }
+    }
+
+    /// Value for yt-dlp's `--audio-quality`, `None` when it doesn't apply
+    pub fn ytdlp_audio_quality(&self) -> Option<String> {
+        if !self.format.is_lossy() {
+            return None;
+        }
+        Some(match self.bitrate_kbps {
+            Some(kbps) => format!("{}K", kbps),
+            // 0 is the best variable bitrate
+            None => "0".to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for AudioQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.bitrate_kbps {
+            Some(kbps) if self.format.is_lossy() => {
+                write!(f, "{} {}k", self.format.as_str(), kbps)
+            }
+            _ => write!(f, "{}", self.format.as_str()),
+        }
+    }
+}
+
+/// A chapter of a video, as listed in yt-dlp's info JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    /// Start of the chapter, in seconds from the start of the video
+    pub start_secs: f64,
+    /// End of the chapter, in seconds from the start of the video
+    pub end_secs: f64,
+    /// File the chapter was split into, for audio downloads split by chapter
+    #[serde(default)]
+    pub file_path: Option<String>,
+}
+
+impl Chapter {
+    /// Start time as "m:ss" or "h:mm:ss"
+    pub fn format_start(&self) -> String {
+        format_timestamp(self.start_secs)
+    }
+}
+
+/// Format a position in a video as "m:ss" or "h:mm:ss"
+pub fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// How a live stream is recorded
+///
+/// Scheduled streams are always waited for; the recording stops when the
+/// stream ends, the maximum duration is reached or it is stopped by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LiveOptions {
+    /// Record from the start of the stream rather than from the live edge
+    pub from_start: bool,
+    /// Stop recording after this many seconds
+    pub max_duration_secs: Option<u64>,
+}
+
+/// Part of a video to download instead of the whole video
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClipRange {
+    /// Start of the clip, in seconds from the start of the video
+    pub start_secs: f64,
+    /// End of the clip, `None` to run to the end of the video
+    pub end_secs: Option<f64>,
+}
+
+impl ClipRange {
+    /// Build a range, rejecting ones that end before they start
+    pub fn new(start_secs: f64, end_secs: Option<f64>) -> Result<Self, String> {
+        if start_secs < 0.0 {
+            return Err("The start time can't be negative".to_string());
+        }
+        match end_secs {
+            Some(end) if end <= start_secs => Err(format!(
+                "The clip ends at {} before it starts at {}",
+                format_timestamp(end),
+                format_timestamp(start_secs)
+            )),
+            _ => Ok(Self {
+                start_secs,
+                end_secs,
+            }),
+        }
+    }
+
+    /// Value for yt-dlp's `--download-sections`, e.g. "*60-180"
+    pub fn section(&self) -> String {
+        match self.end_secs {
+            Some(end) => format!("*{}-{}", self.start_secs, end),
+            None => format!("*{}-inf", self.start_secs),
+        }
+    }
+
+    /// The range as shown to people, e.g. "1:00 - 3:00"
+    pub fn describe(&self) -> String {
+        match self.end_secs {
+            Some(end) => format!(
+                "{} - {}",
+                format_timestamp(self.start_secs),
+                format_timestamp(end)
+            ),
+            None => format!("{} - end", format_timestamp(self.start_secs)),
+        }
+    }
+
+    /// The range as it appears in file names, e.g. "1m00s-3m00s"
+    ///
+    /// File names can't hold the colons of `describe`.
+    pub fn label(&self) -> String {
+        // "1:02:03" becomes "1h02m03s" and "1:30" becomes "1m30s"
+        let stamp = |secs: f64| {
+            let clock = format_timestamp(secs);
+            let parts: Vec<&str> = clock.split(':').collect();
+            let units = &["h", "m", "s"][3 - parts.len()..];
+            parts
+                .iter()
+                .zip(units)
+                .map(|(part, unit)| format!("{}{}", part, unit))
+                .collect::<String>()
+        };
+        match self.end_secs {
+            Some(end) => format!("{}-{}", stamp(self.start_secs), stamp(end)),
+            None => format!("{}-end", stamp(self.start_secs)),
+        }
+    }
+}
+
+/// File format subtitles are saved in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SubtitleFormat {
+    #[default]
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub const ALL: [SubtitleFormat; 2] = [SubtitleFormat::Srt, SubtitleFormat::Vtt];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.as_str() == name.trim().to_lowercase())
+    }
+}
+
+/// Subtitles to fetch along with a download
+///
+/// Manual subtitles are always preferred; auto-generated captions are only
+/// used for languages without them, and only when `auto_generated` is set.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SubtitleOptions {
+    /// Language codes as yt-dlp reports them, e.g. "en" or "pt-BR"
+    pub languages: Vec<String>,
+    /// Fall back to auto-generated captions
+    pub auto_generated: bool,
+    pub format: SubtitleFormat,
+    /// Embed the subtitles in the video instead of saving them next to it
+    pub embed: bool,
+}
+
+/// A subtitle language a video is available with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleTrack {
+    /// Language code, e.g. "en"
+    pub language: String,
+    /// Only available as auto-generated captions
+    pub auto_generated: bool,
+}
+
+/// Subtitles saved with a finished download
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleFile {
+    /// Record ID, used by `utils::subtitle_url`
+    pub id: i64,
+    pub language: String,
+    /// File format, e.g. "srt"
+    pub format: String,
+    pub auto_generated: bool,
+    /// Embedded in the media file rather than saved as a sidecar file
+    pub embedded: bool,
+}
+
+/// Where a queued download sits in a playlist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistItem {
+    /// The `playlists` record
+    pub playlist_id: i64,
+    pub playlist_title: String,
+    /// 1-based position in the playlist
+    pub index: u32,
+    /// Number of entries in the playlist, used to pad the index
+    pub count: u32,
+}
+
+/// A video listed in a playlist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// 1-based position in the playlist
+    pub index: u32,
+    pub video_id: String,
+    pub url: String,
+    pub title: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<u64>,
+}
+
+impl PlaylistEntry {
+    /// Format the duration as "m:ss" or "h:mm:ss"
+    pub fn format_duration(&self) -> String {
+        self.duration
+            .map(|seconds| format_timestamp(seconds as f64))
+            .unwrap_or_else(|| "--:--".to_string())
+    }
+}
+
+/// A playlist resolved into its entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistInfo {
+    pub url: String,
+    pub title: String,
+    pub uploader: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// One of the formats yt-dlp can download a video in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoFormat {
+    pub format_id: String,
+    /// Container, e.g. "mp4", "webm" or "m4a"
+    pub ext: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    /// Video codec, `None` for audio-only formats
+    pub vcodec: Option<String>,
+    /// Audio codec, `None` for video-only formats
+    pub acodec: Option<String>,
+    /// Average total bitrate in kbit/s
+    pub bitrate_kbps: Option<f64>,
+    /// Size in bytes
+    pub filesize: Option<u64>,
+    /// Whether `filesize` is yt-dlp's estimate rather than the exact size
+    pub filesize_is_estimate: bool,
+    pub hdr: bool,
+    /// yt-dlp's description, e.g. "1080p60" or "medium"
+    pub note: Option<String>,
+}
+
+impl VideoFormat {
+    pub fn has_video(&self) -> bool {
+        self.vcodec.is_some()
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.acodec.is_some()
+    }
+
+    /// "1920x1080", or "audio only"
+    pub fn resolution(&self) -> String {
+        match (self.width, self.height) {
+            _ if !self.has_video() => "audio only".to_string(),
+            (Some(width), Some(height)) => format!("{}x{}", width, height),
+            (None, Some(height)) => format!("{}p", height),
+            _ => "unknown".to_string(),
+        }
+    }
+
+    /// File size as "12.3 MB", prefixed with "~" when estimated
+    pub fn format_filesize(&self) -> String {
+        let Some(bytes) = self.filesize else {
+            return "-".to_string();
+        };
+        let size = if bytes >= 1024 * 1024 * 1024 {
+            format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+        } else {
+            format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+        };
+        if self.filesize_is_estimate {
+            format!("~{}", size)
+        } else {
+            size
+        }
+    }
+}
+
+/// A video's title and the formats it is available in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoFormats {
+    pub title: String,
+    /// Ordered from worst to best, as yt-dlp lists them
+    pub formats: Vec<VideoFormat>,
+}
+
+/// Lifecycle state of a queued download job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobState {
+    Queued,
+    Running,
+    /// A scheduled live stream, queued again once it is due to start
+    Waiting,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    /// Value stored in the `status` column of `download_jobs`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Waiting => "waiting",
+            JobState::Paused => "paused",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    /// Parse a `status` column value, treating unknown values as failed
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "queued" => JobState::Queued,
+            "running" => JobState::Running,
+            "waiting" => JobState::Waiting,
+            "paused" => JobState::Paused,
+            "completed" => JobState::Completed,
+            "cancelled" => JobState::Cancelled,
+            _ => JobState::Failed,
+        }
+    }
+
+    /// Whether the job has reached a terminal state
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobState::Queued => "Queued",
+            JobState::Running => "Downloading",
+            JobState::Waiting => "Waiting for stream",
+            JobState::Paused => "Paused",
+            JobState::Completed => "Completed",
+            JobState::Failed => "Failed",
+            JobState::Cancelled => "Cancelled",
+        }
+    }
+}
+
+/// Why a download failed, worked out from yt-dlp's error output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureKind {
+    /// Connection problems, timeouts and server errors
+    Network,
+    /// HTTP 403, usually an expired stream URL
+    Forbidden,
+    /// HTTP 429, the site is throttling requests
+    RateLimited,
+    /// The video was removed or never existed
+    Unavailable,
+    /// The video is private or for channel members only
+    Private,
+    /// The video requires signing in to confirm the viewer's age
+    AgeRestricted,
+    /// The video isn't available in this region
+    GeoBlocked,
+    /// Anything we don't recognise
+    Other,
+}
+
+impl FailureKind {
+    /// Stable identifier used when the failure is serialized
+    pub fn code(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "network",
+            FailureKind::Forbidden => "forbidden",
+            FailureKind::RateLimited => "rate_limited",
+            FailureKind::Unavailable => "unavailable",
+            FailureKind::Private => "private",
+            FailureKind::AgeRestricted => "age_restricted",
+            FailureKind::GeoBlocked => "geo_blocked",
+            FailureKind::Other => "other",
+        }
+    }
+
+    /// Parse a value returned by `code`, treating unknown codes as `Other`
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "network" => FailureKind::Network,
+            "forbidden" => FailureKind::Forbidden,
+            "rate_limited" => FailureKind::RateLimited,
+            "unavailable" => FailureKind::Unavailable,
+            "private" => FailureKind::Private,
+            "age_restricted" => FailureKind::AgeRestricted,
+            "geo_blocked" => FailureKind::GeoBlocked,
+            _ => FailureKind::Other,
+        }
+    }
+
+    /// Whether trying again later may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            FailureKind::Network | FailureKind::Forbidden | FailureKind::RateLimited
+        )
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "Network error",
+            FailureKind::Forbidden => "Access denied (HTTP 403)",
+            FailureKind::RateLimited => "Rate limited (HTTP 429)",
+            FailureKind::Unavailable => "Video unavailable",
+            FailureKind::Private => "Private video",
+            FailureKind::AgeRestricted => "Age-restricted",
+            FailureKind::GeoBlocked => "Blocked in your region",
+            FailureKind::Other => "Download error",
+        }
+    }
+
+    /// Short advice shown next to the failure
+    pub fn hint(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "Check your connection and retry.",
+            FailureKind::Forbidden => "The site refused the request. Retrying often helps.",
+            FailureKind::RateLimited => "Too many requests. Wait a few minutes before retrying.",
+            FailureKind::Unavailable => "The video was removed or the link is wrong.",
+            FailureKind::Private => {
+                "Only the uploader or channel members can watch this video. Import your cookies for the site in Settings to download it signed in."
+            }
+            FailureKind::AgeRestricted => {
+                "The site requires signing in to watch this video. Import your cookies for the site in Settings."
+            }
+            FailureKind::GeoBlocked => "The uploader doesn't offer this video in your country.",
+            FailureKind::Other => "See the error message for details.",
+        }
+    }
+
+    /// Error message shown to the user, prefixed with the failure category
+    pub fn describe(&self, message: &str) -> String {
+        match self {
+            FailureKind::Other => message.to_string(),
+            _ => format!("{}: {}", self.label(), message),
+        }
+    }
+}
+
+/// Queue entry sent to the client for the queue view
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub format_type: String,
+    pub quality: String,
+    pub state: JobState,
+    pub error: Option<String>,
+    /// Why the job failed, for failed jobs
+    pub failure: Option<FailureKind>,
+    /// The playlist the job was queued from, if any
+    pub playlist: Option<PlaylistItem>,
+    pub download_id: Option<i64>,
+    pub created_at: String,
+    /// When a waiting live stream is due to start
+    pub starts_at: Option<String>,
+}
+
+/// Subscription sent to the client for the subscriptions view
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionEntry {
+    pub id: i64,
+    pub url: String,
+    pub title: String,
+    /// Format type new uploads are downloaded as
+    pub format_type: String,
+    /// Quality new uploads are downloaded in
+    pub quality: String,
+    pub enabled: bool,
+    pub last_checked: String,
+    /// Why the last check failed, if it did
+    pub last_error: Option<String>,
+}
+
+/// A video listed on a channel's uploads page
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelUpload {
+    pub video_id: String,
+    pub url: String,
+    pub title: Option<String>,
+}
+
+/// A channel's name and its most recent uploads, newest first
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelUploads {
+    pub title: String,
+    pub uploads: Vec<ChannelUpload>,
+}
+
+/// Progress snapshot for a single download job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub job_id: i64,
+    pub state: JobState,
+    pub progress: DownloadProgress,
+}
+
+/// Internet protocol version yt-dlp connects with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IpVersion {
+    /// Whatever the system prefers
+    #[default]
+    Any,
+    V4,
+    V6,
+}
+
+impl IpVersion {
+    pub const ALL: [IpVersion; 3] = [IpVersion::Any, IpVersion::V4, IpVersion::V6];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpVersion::Any => "any",
+            IpVersion::V4 => "ipv4",
+            IpVersion::V6 => "ipv6",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|version| version.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            IpVersion::Any => "System default",
+            IpVersion::V4 => "IPv4 only",
+            IpVersion::V6 => "IPv6 only",
+        }
+    }
+}
+
+/// Network settings applied to every yt-dlp run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// HTTP, HTTPS or SOCKS proxy, e.g. "socks5://127.0.0.1:1080"
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub ip_version: IpVersion,
+    /// Local IP address to connect from
+    #[serde(default)]
+    pub source_address: Option<String>,
+    /// Seconds to wait for a response before giving up
+    #[serde(default = "NetworkSettings::default_socket_timeout")]
+    pub socket_timeout_secs: u32,
+    /// Download speed cap in KiB/s
+    #[serde(default)]
+    pub rate_limit_kib: Option<u64>,
+    /// Seconds to wait between requests yt-dlp makes while extracting
+    #[serde(default)]
+    pub sleep_requests_secs: Option<f64>,
+}
+
+impl NetworkSettings {
+    pub const DEFAULT_SOCKET_TIMEOUT: u32 = 30;
+
+    fn default_socket_timeout() -> u32 {
+        Self::DEFAULT_SOCKET_TIMEOUT
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            ip_version: IpVersion::Any,
+            source_address: None,
+            socket_timeout_secs: Self::DEFAULT_SOCKET_TIMEOUT,
+            rate_limit_kib: None,
+            sleep_requests_secs: None,
+        }
+    }
+}
+
+/// An earlier download of the video being downloaded, in the same format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateDownload {
+    pub download_id: i64,
+    pub title: String,
+    pub file_name: String,
+    pub file_path: String,
+    pub downloaded_at: String,
+}
+
+/// Cookies imported for a site, as listed in Settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CookieSite {
+    /// Domain the cookies are used for, e.g. "youtube.com"
+    pub site: String,
+    pub cookie_count: usize,
+    pub imported_at: String,
+    /// Every cookie has passed its expiry date, so they need importing again
+    pub expired: bool,
+}
+
+/// A finished download saved on disk
+///
+/// Clients fetch the file itself from `utils::file_url(download_id)` rather than
+/// receiving its content from a server function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadedFile {
+    /// The `downloads` record
+    pub download_id: i64,
+    pub file_name: String,
+    pub file_path: String,
+    pub file_size: u64,
+}
@@ -1,5 +1,7 @@
+use super::error::DownloaderError;
 #[cfg(feature = "server")]
-use super::types::DownloadProgress;
+use super::types::{ProgressEvent, YtDlpEvent};
+use super::youtube_url::{is_youtube_host, youtube_video_id, YoutubeUrl};
 use std::io;
 use std::path::{Path, PathBuf};
 
@@ -7,101 +9,297 @@ use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing;
 
-#[cfg(feature = "server")]
-pub async fn find_downloaded_file(dir: impl AsRef<Path>) -> io::Result<PathBuf> {
-    let dir_path = dir.as_ref();
-    let mut entries = fs::read_dir(dir_path).await?;
-
-    tracing::info!(
-        "Scanning directory {} for downloaded files",
-        dir_path.display()
-    );
-
-    // First try to find any media file
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        tracing::info!("Found file: {:?}", path);
-
-        if path.is_file() {
-            // Check the file extension for media types
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy().to_lowercase();
-                // Add more audio formats to the list
-                if [
-                    "mp4", "mp3", "m4a", "webm", "mkv", "opus", "ogg", "wav", "aac", "flac",
-                ]
-                .contains(&ext_str.as_str())
-                {
-                    // Get file size for logging
-                    if let Ok(metadata) = fs::metadata(&path).await {
-                        tracing::info!(
-                            "Found media file: {} ({} bytes)",
-                            path.file_name().unwrap_or_default().to_string_lossy(),
-                            metadata.len()
-                        );
-                    }
-                    return Ok(path);
+/// Path the server streams finished downloads from
+pub const FILES_ROUTE: &str = "/api/files";
+
+/// URL of the file endpoint for a download record
+pub fn file_url(download_id: i64) -> String {
+    format!("{}/{}", FILES_ROUTE, download_id)
+}
+
+/// URL of the file endpoint for a sidecar subtitle file
+pub fn subtitle_url(subtitle_id: i64) -> String {
+    format!("{}/subtitles/{}", FILES_ROUTE, subtitle_id)
+}
+
+/// Check whether a URL points to something the downloader accepts
+///
+/// Any web page may hold a video yt-dlp can extract, so every http(s) link is
+/// accepted. YouTube links must name a video in one of the forms
+/// `youtube_url` understands; playlists and channels have their own pages.
+/// Which sites are actually allowed is decided by the allowlist setting.
+pub fn is_supported_url(url: &str) -> bool {
+    let Some(host) = url_host(url) else {
+        return false;
+    };
+    if host == "youtu.be" || is_youtube_host(&host) {
+        return youtube_video_id(url).is_some();
+    }
+
+    url::Url::parse(url.trim()).is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"))
+}
+
+/// Error for a URL the single video endpoints don't accept
+pub fn unsupported_url_error(url: &str) -> DownloaderError {
+    if is_playlist_url(url) {
+        DownloaderError::InvalidUrl(
+            "This link points to a playlist. Use the Playlist page to choose the videos to download."
+                .to_string(),
+        )
+    } else if channel_uploads_url(url).is_some() {
+        DownloaderError::InvalidUrl(
+            "This link points to a channel. Use the Subscriptions page to follow its uploads."
+                .to_string(),
+        )
+    } else {
+        DownloaderError::InvalidUrl(
+            "Invalid URL. Please provide a link to a video page.".to_string(),
+        )
+    }
+}
+
+/// Host of a URL without a leading `www.`, lowercased
+pub fn url_host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+/// Check whether a URL refers to a YouTube playlist
+///
+/// Accepts `youtube.com/playlist?list=...` as well as video links carrying a
+/// `list` parameter, which the playlist view resolves to the whole playlist.
+pub fn is_playlist_url(url: &str) -> bool {
+    YoutubeUrl::parse(url).is_some_and(|youtube_url| youtube_url.list.is_some())
+}
+
+/// Canonical uploads page of a YouTube channel URL, or `None` for other URLs
+///
+/// `youtube.com/@name`, `/channel/ID`, `/c/name` and `/user/name` map to the
+/// channel's Videos tab; links to its Shorts or Live tab keep that tab.
+pub fn channel_uploads_url(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    let host = parsed.host_str()?;
+    if host != "youtube.com" && !host.ends_with(".youtube.com") {
+        return None;
+    }
+
+    let segments: Vec<&str> = parsed
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let channel_len = match *segments.first()? {
+        handle if handle.starts_with('@') => 1,
+        "channel" | "c" | "user" if segments.len() >= 2 => 2,
+        _ => return None,
+    };
+    let tab = match segments.get(channel_len) {
+        Some(&tab @ ("shorts" | "streams")) => tab,
+        _ => "videos",
+    };
+
+    Some(format!(
+        "https://www.youtube.com/{}/{}",
+        segments[..channel_len].join("/"),
+        tab
+    ))
+}
+
+/// Link to a video listed with `--flat-playlist`
+///
+/// Uses the page URL yt-dlp reported for the entry. Entries that only carry an
+/// ID or a relative URL fall back to the YouTube watch page for that ID.
+pub fn flat_entry_url(id: &str, webpage_url: Option<&str>, url: Option<&str>) -> String {
+    webpage_url
+        .into_iter()
+        .chain(url)
+        .find(|candidate| {
+            url::Url::parse(candidate)
+                .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"))
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={}", id))
+}
+
+/// Parse a time in a video, in seconds
+///
+/// Accepts plain seconds ("90", "90.5"), clock times ("1:30", "1:02:03") and
+/// the unit form YouTube links use ("90s", "1m30s", "1h2m3s").
+pub fn parse_timestamp(text: &str) -> Option<f64> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return None;
+    }
+
+    let seconds = if text.contains(':') {
+        let parts: Vec<&str> = text.split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+        let mut total = 0.0;
+        for part in parts {
+            total = total * 60.0 + part.parse::<f64>().ok()?;
+        }
+        total
+    } else if text.ends_with(['h', 'm', 's']) {
+        let mut total = 0.0;
+        let mut number = String::new();
+        for c in text.chars() {
+            match c {
+                'h' | 'm' | 's' => {
+                    let value: f64 = number.parse().ok()?;
+                    total += value
+                        * match c {
+                            'h' => 3600.0,
+                            'm' => 60.0,
+                            _ => 1.0,
+                        };
+                    number.clear();
                 }
+                _ => number.push(c),
             }
         }
-    }
+        total
+    } else {
+        text.parse().ok()?
+    };
+
+    (seconds.is_finite() && seconds >= 0.0).then_some(seconds)
+}
+
+/// Start time carried by a video link, e.g. `t=90` or `#t=1m30s`, in seconds
+pub fn url_start_time(url: &str) -> Option<f64> {
+    let start_time = match YoutubeUrl::parse(url) {
+        Some(youtube_url) => youtube_url.start_time?,
+        None => url::Url::parse(url.trim())
+            .ok()?
+            .query_pairs()
+            .find(|(key, _)| key == "t")
+            .map(|(_, value)| value.to_string())?,
+    };
+    parse_timestamp(&start_time).filter(|seconds| *seconds > 0.0)
+}
 
-    // Try again to find ANY file if we didn't find a media file
-    let mut entries = fs::read_dir(dir_path).await?;
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        if path.is_file() {
-            tracing::info!(
-                "Falling back to non-media file: {}",
-                path.file_name().unwrap_or_default().to_string_lossy(),
-            );
-            return Ok(path);
+/// Read the path of the finished file from the record yt-dlp wrote with `--print-to-file`
+///
+/// yt-dlp appends one line per file it finishes, so the last line names the
+/// final output after post-processing.
+#[cfg(feature = "server")]
+pub async fn read_reported_output(record: impl AsRef<Path>) -> io::Result<PathBuf> {
+    read_reported_outputs(record)
+        .await?
+        .pop()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "yt-dlp reported no output file"))
+}
+
+/// Read every file yt-dlp reported in its `--print-to-file` record, in order
+///
+/// Downloads of several sections report one file per section. Files reported
+/// again by a retried run are only listed once.
+#[cfg(feature = "server")]
+pub async fn read_reported_outputs(record: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let contents = fs::read_to_string(record.as_ref()).await?;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for reported in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let path = PathBuf::from(reported);
+        if !paths.contains(&path) {
+            paths.push(path);
         }
     }
 
-    tracing::error!("No files found in directory: {}", dir_path.display());
-    Err(io::Error::new(
-        io::ErrorKind::NotFound,
-        "No downloaded file found",
-    ))
+    for path in &paths {
+        let metadata = fs::metadata(path).await?;
+        tracing::info!(
+            "yt-dlp reported output file: {} ({} bytes)",
+            path.display(),
+            metadata.len()
+        );
+    }
+    Ok(paths)
 }
 
+/// Parse a line of `yt-dlp --newline` output into a typed event
+///
+/// Understands lines such as:
+/// `[download] Destination: /tmp/job/video.f137.mp4`
+/// `[download]  42.0% of ~  10.00MiB at    1.50MiB/s ETA 00:04 (frag 21/50)`
+/// `[Merger] Merging formats into "/tmp/job/video.mp4"`
 #[cfg(feature = "server")]
-pub fn parse_progress_line(line: &str) -> Option<DownloadProgress> {
-    let mut progress = DownloadProgress::default();
-
-    // Check if the line contains progress information
-    if line.starts_with("[download]") && line.contains("%") {
-        // Parse percentage
-        if let Some(percent_idx) = line.find('%') {
-            if let Some(percent_start) = line[..percent_idx].rfind(' ') {
-                if let Ok(percent) = line[percent_start + 1..percent_idx].trim().parse::<f64>() {
-                    progress.status = format!("Downloading: {:.1}%", percent);
-
-                    // Try to parse the file size
-                    if let Some(of_idx) = line.find(" of ") {
-                        if let Some(size_end) = line[of_idx + 4..].find(' ') {
-                            let size_str = line[of_idx + 4..of_idx + 4 + size_end].trim();
-                            progress.total_bytes = parse_size(size_str).unwrap_or(0);
-                            progress.downloaded_bytes =
-                                ((percent / 100.0) * progress.total_bytes as f64) as u64;
-                        }
-                    }
-
-                    // Try to parse ETA
-                    if let Some(eta_idx) = line.find(" ETA ") {
-                        let eta_str = line[eta_idx + 5..].trim();
-                        progress.eta_seconds = parse_eta(eta_str).unwrap_or(0);
-                    }
-
-                    return Some(progress);
-                }
-            }
+pub fn parse_progress_line(line: &str) -> Option<YtDlpEvent> {
+    let line = line.trim();
+
+    if let Some(rest) = line.strip_prefix("[download]") {
+        let rest = rest.trim();
+
+        if let Some(path) = rest.strip_prefix("Destination:") {
+            return Some(YtDlpEvent::Destination(path.trim().to_string()));
         }
-    } else if line.contains("Merger") || line.contains("ffmpeg") {
-        progress.status = "Processing video...".to_string();
-        progress.downloaded_bytes = progress.total_bytes; // Assume download is complete
-        return Some(progress);
+
+        let percent_idx = rest.find('%')?;
+        let percent = rest[..percent_idx].trim().parse::<f64>().ok()?;
+        let after_percent = &rest[percent_idx + 1..];
+
+        // Total size, marked with `~` when yt-dlp is estimating it
+        let mut total_bytes = 0;
+        let mut total_is_estimate = false;
+        if let Some(of_idx) = after_percent.find(" of ") {
+            let size_str = after_percent[of_idx + 4..].trim_start();
+            total_is_estimate = size_str.starts_with('~');
+            let size_str = size_str.trim_start_matches('~').trim_start();
+            let size_end = size_str.find(' ').unwrap_or(size_str.len());
+            total_bytes = parse_size(&size_str[..size_end]).unwrap_or(0);
+        }
+
+        let speed_bytes_per_sec = after_percent.find(" at ").and_then(|at_idx| {
+            let speed_str = after_percent[at_idx + 4..].trim_start();
+            let speed_end = speed_str.find(' ').unwrap_or(speed_str.len());
+            parse_size(speed_str[..speed_end].trim_end_matches("/s"))
+        });
+
+        let eta_seconds = after_percent.find(" ETA ").and_then(|eta_idx| {
+            let eta_str = after_percent[eta_idx + 5..].trim_start();
+            let eta_end = eta_str.find(' ').unwrap_or(eta_str.len());
+            parse_eta(&eta_str[..eta_end])
+        });
+
+        let (fragment_index, fragment_count) = match after_percent.find("(frag ") {
+            Some(frag_idx) => {
+                let frag_str = &after_percent[frag_idx + 6..];
+                let frag_str = &frag_str[..frag_str.find(')').unwrap_or(frag_str.len())];
+                let mut parts = frag_str.split('/');
+                (
+                    parts.next().and_then(|n| n.trim().parse().ok()),
+                    parts.next().and_then(|n| n.trim().parse().ok()),
+                )
+            }
+            None => (None, None),
+        };
+
+        return Some(YtDlpEvent::Progress(ProgressEvent {
+            percent,
+            downloaded_bytes: ((percent / 100.0) * total_bytes as f64) as u64,
+            total_bytes,
+            total_is_estimate,
+            speed_bytes_per_sec,
+            eta_seconds,
+            fragment_index,
+            fragment_count,
+        }));
+    }
+
+    // Post-processors announce themselves as `[Merger]`, `[ExtractAudio]`, ...
+    if line.starts_with("[Merger]")
+        || line.starts_with("[ExtractAudio]")
+        || line.starts_with("[VideoConvertor]")
+        || line.starts_with("[FixupM3u8]")
+        || line.starts_with("[ffmpeg]")
+    {
+        let name = &line[1..line.find(']').unwrap_or(1)];
+        return Some(YtDlpEvent::PostProcessing(name.to_string()));
     }
 
     None
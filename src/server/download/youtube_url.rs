@@ -0,0 +1,242 @@
+// YouTube URL normalization
+//
+// YouTube links come in many shapes. Every form below is recognised and
+// rewritten to the canonical watch URL; tracking parameters such as `si`,
+// `feature` or `pp` are dropped, while the start time and playlist context
+// are kept.
+//
+// | Input                                              | Canonical URL                                         |
+// |----------------------------------------------------|-------------------------------------------------------|
+// | `https://www.youtube.com/watch?v=ID`               | `https://www.youtube.com/watch?v=ID`                  |
+// | `https://youtube.com/watch?v=ID&feature=share`     | `https://www.youtube.com/watch?v=ID`                  |
+// | `https://m.youtube.com/watch?v=ID&t=42s`           | `https://www.youtube.com/watch?v=ID&t=42s`            |
+// | `https://music.youtube.com/watch?v=ID&list=PL`     | `https://www.youtube.com/watch?v=ID&list=PL`          |
+// | `https://youtu.be/ID?si=abc&t=90`                  | `https://www.youtube.com/watch?v=ID&t=90`             |
+// | `https://www.youtube.com/shorts/ID`                | `https://www.youtube.com/watch?v=ID`                  |
+// | `https://www.youtube.com/embed/ID?start=30`        | `https://www.youtube.com/watch?v=ID&t=30`             |
+// | `https://www.youtube-nocookie.com/embed/ID`        | `https://www.youtube.com/watch?v=ID`                  |
+// | `https://www.youtube.com/live/ID?si=abc`           | `https://www.youtube.com/watch?v=ID`                  |
+// | `https://www.youtube.com/v/ID`                     | `https://www.youtube.com/watch?v=ID`                  |
+// | `https://www.youtube.com/watch?v=ID#t=1m5s`        | `https://www.youtube.com/watch?v=ID&t=1m5s`           |
+// | `https://youtube.com/playlist?list=PL&si=abc`      | `https://www.youtube.com/playlist?list=PL`            |
+//
+// Channel pages and other YouTube URLs without a video or playlist are not
+// YouTube video links and are left to the callers.
+
+use url::Url;
+
+/// The parts of a YouTube link worth keeping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YoutubeUrl {
+    /// 11 character video ID, `None` for playlist pages
+    pub video_id: Option<String>,
+    /// Playlist the link was opened from
+    pub list: Option<String>,
+    /// Start time as given in the link, e.g. `90`, `90s` or `1m30s`
+    pub start_time: Option<String>,
+}
+
+impl YoutubeUrl {
+    /// Parse any known form of YouTube video or playlist link
+    pub fn parse(url: &str) -> Option<Self> {
+        let parsed = Url::parse(url.trim()).ok()?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return None;
+        }
+        let host = parsed.host_str()?.to_lowercase();
+
+        let query_value = |name: &str| {
+            parsed
+                .query_pairs()
+                .find(|(key, value)| key == name && !value.is_empty())
+                .map(|(_, value)| value.trim().to_string())
+        };
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+
+        let video_id = if host == "youtu.be" {
+            segments.first().map(|id| id.to_string())
+        } else if is_youtube_host(&host) {
+            match segments.as_slice() {
+                ["watch"] => query_value("v"),
+                ["shorts" | "embed" | "live" | "v" | "e", id, ..] => Some(id.to_string()),
+                ["playlist"] => None,
+                _ => return None,
+            }
+        } else {
+            return None;
+        };
+
+        let list = query_value("list");
+        let video_id = match video_id {
+            Some(id) if is_video_id(&id) => Some(id),
+            Some(_) => return None,
+            None if list.is_some() => None,
+            None => return None,
+        };
+
+        // Embeds use `start`, shared links `t`, older links a `#t=` fragment
+        let start_time = query_value("t")
+            .or_else(|| query_value("start"))
+            .or_else(|| {
+                parsed
+                    .fragment()
+                    .and_then(|fragment| fragment.strip_prefix("t="))
+                    .filter(|time| !time.is_empty())
+                    .map(str::to_string)
+            });
+
+        Some(Self {
+            video_id,
+            list,
+            start_time,
+        })
+    }
+
+    /// The canonical `www.youtube.com` URL for the link
+    pub fn canonical(&self) -> String {
+        let mut url = match &self.video_id {
+            Some(id) => format!("https://www.youtube.com/watch?v={}", id),
+            None => "https://www.youtube.com/playlist?".to_string(),
+        };
+
+        let mut params = Vec::new();
+        if let Some(list) = &self.list {
+            params.push(format!("list={}", list));
+        }
+        // A start time means nothing on a playlist page
+        if let (Some(_), Some(start)) = (&self.video_id, &self.start_time) {
+            params.push(format!("t={}", start));
+        }
+
+        for param in params {
+            if !url.ends_with('?') {
+                url.push('&');
+            }
+            url.push_str(&param);
+        }
+        url
+    }
+}
+
+/// Check whether a host serves YouTube pages (`youtu.be` short links aside)
+pub fn is_youtube_host(host: &str) -> bool {
+    let host = host.to_lowercase();
+    host == "youtube.com"
+        || host.ends_with(".youtube.com")
+        || host == "youtube-nocookie.com"
+        || host.ends_with(".youtube-nocookie.com")
+}
+
+/// Check whether a string looks like a YouTube video ID
+pub fn is_video_id(id: &str) -> bool {
+    id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Video ID of any form of YouTube video link
+pub fn youtube_video_id(url: &str) -> Option<String> {
+    YoutubeUrl::parse(url)?.video_id
+}
+
+/// Canonical form of a YouTube link; other URLs are only trimmed
+pub fn normalize_url(url: &str) -> String {
+    match YoutubeUrl::parse(url) {
+        Some(youtube_url) => youtube_url.canonical(),
+        None => url.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_every_link_form() {
+        let cases = [
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://youtube.com/watch?v=dQw4w9WgXcQ&feature=share&pp=ygUE",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://m.youtube.com/watch?v=dQw4w9WgXcQ&t=42s",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s",
+            ),
+            (
+                "https://music.youtube.com/watch?v=dQw4w9WgXcQ&list=PLabc123",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLabc123",
+            ),
+            (
+                "https://youtu.be/dQw4w9WgXcQ?si=abc",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://youtu.be/dQw4w9WgXcQ?si=abc&t=90",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=90",
+            ),
+            (
+                "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://www.youtube.com/embed/dQw4w9WgXcQ?start=30",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=30",
+            ),
+            (
+                "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://www.youtube.com/live/dQw4w9WgXcQ?si=abc",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://www.youtube.com/v/dQw4w9WgXcQ",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ#t=1m5s",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1m5s",
+            ),
+            (
+                "https://www.youtube.com/watch?si=abc&list=PLabc123&v=dQw4w9WgXcQ&t=10&index=3",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLabc123&t=10",
+            ),
+            (
+                "https://youtube.com/playlist?list=PLabc123&si=abc",
+                "https://www.youtube.com/playlist?list=PLabc123",
+            ),
+            (
+                "  https://www.youtube.com/watch?v=dQw4w9WgXcQ  ",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            ),
+        ];
+
+        for (input, canonical) in cases {
+            assert_eq!(normalize_url(input), canonical, "normalizing {}", input);
+        }
+    }
+
+    #[test]
+    fn leaves_other_urls_alone() {
+        let cases = [
+            "https://www.youtube.com/@channel/videos",
+            "https://www.youtube.com/watch?v=tooshort",
+            "https://vimeo.com/123456",
+            "not a url",
+        ];
+
+        for input in cases {
+            assert_eq!(normalize_url(input), input);
+            assert_eq!(youtube_video_id(input), None);
+        }
+    }
+}
@@ -1,22 +1,23 @@
-use dioxus::prelude::*;
-use server_fn::error::NoCustomError;
 use std::path::PathBuf;
 use std::process::Command;
 use tracing;
 
+#[cfg(feature = "server")]
+use super::error::DownloaderError;
+#[cfg(feature = "server")]
+use super::retry::classify_failure;
+#[cfg(feature = "server")]
+use super::types::YtDlpEvent;
+
 /// Check if yt-dlp is installed and download it if not found
 #[cfg(feature = "server")]
-pub async fn ensure_yt_dlp_available() -> Result<PathBuf, ServerFnError<NoCustomError>> {
+pub async fn ensure_yt_dlp_available() -> Result<PathBuf, DownloaderError> {
     let app_data_dir = get_app_data_dir()?;
     let bin_dir = app_data_dir.join("bin");
 
     // Create the bin directory if it doesn't exist
-    std::fs::create_dir_all(&bin_dir).map_err(|e| {
-        ServerFnError::<NoCustomError>::ServerError(format!(
-            "Failed to create bin directory: {}",
-            e
-        ))
-    })?;
+    std::fs::create_dir_all(&bin_dir)
+        .map_err(|e| DownloaderError::Storage(format!("Failed to create bin directory: {}", e)))?;
 
     // Path to the yt-dlp executable in our bin directory
     let yt_dlp_path = bin_dir.join(get_yt_dlp_binary_name());
@@ -49,13 +50,14 @@ pub async fn ensure_yt_dlp_available() -> Result<PathBuf, ServerFnError<NoCustom
         #[cfg(not(target_os = "windows"))]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = std::fs::metadata(&yt_dlp_path)?.permissions();
+            let mut perms = std::fs::metadata(&yt_dlp_path)
+                .map_err(|e| {
+                    DownloaderError::YtDlpMissing(format!("Failed to read yt-dlp binary: {}", e))
+                })?
+                .permissions();
             perms.set_mode(0o755); // rwx r-x r-x
             std::fs::set_permissions(&yt_dlp_path, perms).map_err(|e| {
-                ServerFnError::<NoCustomError>::ServerError(format!(
-                    "Failed to set executable permissions: {}",
-                    e
-                ))
+                DownloaderError::Storage(format!("Failed to set executable permissions: {}", e))
             })?;
         }
     }
@@ -75,7 +77,7 @@ pub async fn ensure_yt_dlp_available() -> Result<PathBuf, ServerFnError<NoCustom
                     }
                     _ => {
                         tracing::error!("Downloaded yt-dlp failed verification");
-                        return Err(ServerFnError::<NoCustomError>::ServerError(
+                        return Err(DownloaderError::YtDlpMissing(
                             "Downloaded yt-dlp failed verification. Make sure it has executable permissions.".to_string(),
                         ));
                     }
@@ -83,7 +85,7 @@ pub async fn ensure_yt_dlp_available() -> Result<PathBuf, ServerFnError<NoCustom
             }
             Err(e) => {
                 tracing::error!("Failed to download yt-dlp: {}", e);
-                return Err(ServerFnError::<NoCustomError>::ServerError(format!(
+                return Err(DownloaderError::YtDlpMissing(format!(
                     "Failed to download yt-dlp: {}",
                     e
                 )));
@@ -99,7 +101,7 @@ pub async fn ensure_yt_dlp_available() -> Result<PathBuf, ServerFnError<NoCustom
             tracing::info!("Bundled yt-dlp ready: {}", version.trim());
             Ok(yt_dlp_path)
         }
-        _ => Err(ServerFnError::<NoCustomError>::ServerError(
+        _ => Err(DownloaderError::YtDlpMissing(
             "Failed to get a working yt-dlp binary".to_string(),
         )),
     }
@@ -107,23 +109,18 @@ pub async fn ensure_yt_dlp_available() -> Result<PathBuf, ServerFnError<NoCustom
 
 /// Get the appropriate app data directory for storing our bundled binaries
 #[cfg(feature = "server")]
-fn get_app_data_dir() -> Result<PathBuf, ServerFnError<NoCustomError>> {
+pub(crate) fn get_app_data_dir() -> Result<PathBuf, DownloaderError> {
     // Use dirs crate to get platform-specific app data directory
     let base_dir = dirs::data_local_dir()
         .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
         .ok_or_else(|| {
-            ServerFnError::<NoCustomError>::ServerError(
-                "Could not determine app data directory".to_string(),
-            )
+            DownloaderError::Storage("Could not determine app data directory".to_string())
         })?;
 
     // Create our app's directory
     let app_dir = base_dir.join("youtube_downloader");
     std::fs::create_dir_all(&app_dir).map_err(|e| {
-        ServerFnError::<NoCustomError>::ServerError(format!(
-            "Failed to create app data directory: {}",
-            e
-        ))
+        DownloaderError::Storage(format!("Failed to create app data directory: {}", e))
     })?;
 
     Ok(app_dir)
@@ -144,7 +141,7 @@ fn get_yt_dlp_binary_name() -> String {
 
 /// Extract bundled yt-dlp binary for desktop builds
 #[cfg(all(feature = "server", feature = "desktop"))]
-fn extract_bundled_yt_dlp(bin_dir: &PathBuf) -> Result<(), ServerFnError<NoCustomError>> {
+fn extract_bundled_yt_dlp(bin_dir: &PathBuf) -> Result<(), DownloaderError> {
     // Path to the bundled binary (embedded in the executable at compile time)
     // We'll use different binaries for different platforms
     let binary_data = {
@@ -181,7 +178,7 @@ fn extract_bundled_yt_dlp(bin_dir: &PathBuf) -> Result<(), ServerFnError<NoCusto
             all(target_os = "linux", target_arch = "aarch64")
         )))]
         {
-            return Err(ServerFnError::<NoCustomError>::ServerError(
+            return Err(DownloaderError::YtDlpMissing(
                 "No bundled yt-dlp for this platform".to_string(),
             ));
         }
@@ -190,9 +187,204 @@ fn extract_bundled_yt_dlp(bin_dir: &PathBuf) -> Result<(), ServerFnError<NoCusto
     // Write the binary to disk
     let target_path = bin_dir.join(get_yt_dlp_binary_name());
     std::fs::write(&target_path, binary_data).map_err(|e| {
-        ServerFnError::<NoCustomError>::ServerError(format!("Failed to write yt-dlp binary: {}", e))
+        DownloaderError::YtDlpMissing(format!("Failed to write yt-dlp binary: {}", e))
     })?;
 
     tracing::info!("Extracted bundled yt-dlp to {:?}", target_path);
     Ok(())
 }
+
+/// The yt-dlp binary to run: the bundled one, or whatever is on PATH
+///
+/// Looked up once; the check runs the binary and blocks, so it's kept off the
+/// async workers.
+#[cfg(feature = "server")]
+pub async fn yt_dlp_binary() -> PathBuf {
+    static BINARY: tokio::sync::OnceCell<PathBuf> = tokio::sync::OnceCell::const_new();
+    BINARY
+        .get_or_init(|| async {
+            let check = tokio::task::spawn_blocking(|| {
+                tokio::runtime::Handle::current().block_on(ensure_yt_dlp_available())
+            })
+            .await
+            .map_err(|e| DownloaderError::Internal(format!("yt-dlp check failed: {}", e)))
+            .and_then(|result| result);
+            match check {
+                Ok(path) => path,
+                Err(e) => {
+                    tracing::warn!("Using yt-dlp from PATH: {}", e);
+                    PathBuf::from(get_yt_dlp_binary_name())
+                }
+            }
+        })
+        .await
+        .clone()
+}
+
+/// A yt-dlp download run that reports progress line by line
+///
+/// Mirrors the `youtube_dl::YoutubeDl` builder, but runs yt-dlp with
+/// `--newline` so progress can be parsed while the download is running.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct YtDlpDownload {
+    url: String,
+    output_directory: Option<String>,
+    output_template: Option<String>,
+    format: Option<String>,
+    socket_timeout: Option<String>,
+    extract_audio: bool,
+    extra_args: Vec<String>,
+}
+
+#[cfg(feature = "server")]
+impl YtDlpDownload {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            output_directory: None,
+            output_template: None,
+            format: None,
+            socket_timeout: None,
+            extract_audio: false,
+            extra_args: Vec::new(),
+        }
+    }
+
+    pub fn output_directory(&mut self, dir: impl Into<String>) -> &mut Self {
+        self.output_directory = Some(dir.into());
+        self
+    }
+
+    pub fn output_template(&mut self, template: impl Into<String>) -> &mut Self {
+        self.output_template = Some(template.into());
+        self
+    }
+
+    pub fn format(&mut self, format: impl Into<String>) -> &mut Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    pub fn socket_timeout(&mut self, timeout: impl Into<String>) -> &mut Self {
+        self.socket_timeout = Some(timeout.into());
+        self
+    }
+
+    pub fn extract_audio(&mut self, extract_audio: bool) -> &mut Self {
+        self.extract_audio = extract_audio;
+        self
+    }
+
+    /// Append `template` to `file` at a given stage, e.g. `after_move:filepath`
+    ///
+    /// Unlike `--print`, this doesn't silence the progress output.
+    pub fn print_to_file(
+        &mut self,
+        template: impl Into<String>,
+        file: impl Into<String>,
+    ) -> &mut Self {
+        self.extra_args.push("--print-to-file".to_string());
+        self.extra_args.push(template.into());
+        self.extra_args.push(file.into());
+        self
+    }
+
+    pub fn extra_arg(&mut self, arg: impl Into<String>) -> &mut Self {
+        self.extra_args.push(arg.into());
+        self
+    }
+
+    fn build_args(&self) -> Vec<String> {
+        let mut args = vec!["--newline".to_string(), "--no-colors".to_string()];
+
+        if let Some(dir) = &self.output_directory {
+            args.push("-P".to_string());
+            args.push(dir.clone());
+        }
+        if let Some(template) = &self.output_template {
+            args.push("-o".to_string());
+            args.push(template.clone());
+        }
+        if let Some(format) = &self.format {
+            args.push("-f".to_string());
+            args.push(format.clone());
+        }
+        if let Some(timeout) = &self.socket_timeout {
+            args.push("--socket-timeout".to_string());
+            args.push(timeout.clone());
+        }
+        if self.extract_audio {
+            args.push("-x".to_string());
+        }
+
+        args.extend(self.extra_args.iter().cloned());
+        args.push("--".to_string());
+        args.push(self.url.clone());
+        args
+    }
+
+    /// Run the download, calling `on_event` for every progress line yt-dlp prints
+    ///
+    /// A failed run is classified from yt-dlp's error lines.
+    pub async fn run(&self, mut on_event: impl FnMut(YtDlpEvent)) -> Result<(), DownloaderError> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+        let mut child = tokio::process::Command::new(yt_dlp_binary().await)
+            .args(self.build_args())
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| DownloaderError::YtDlpMissing(format!("Failed to start yt-dlp: {}", e)))?;
+
+        // Collect stderr in the background so a full pipe can't stall yt-dlp
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr_task = tokio::spawn(async move {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output).await;
+            output
+        });
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(event) = super::utils::parse_progress_line(&line) {
+                on_event(event);
+            }
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| DownloaderError::Internal(format!("Failed to wait for yt-dlp: {}", e)))?;
+        let stderr_output = stderr_task.await.unwrap_or_default();
+
+        if status.success() {
+            Ok(())
+        } else {
+            // The last error line is the most useful part of yt-dlp's output
+            let message = stderr_output
+                .lines()
+                .rev()
+                .find(|line| line.contains("ERROR"))
+                .or_else(|| stderr_output.lines().last())
+                .unwrap_or("yt-dlp exited with an error")
+                .trim()
+                .to_string();
+
+            // Classify from every error line; the last one may only be a summary
+            let errors: Vec<&str> = stderr_output
+                .lines()
+                .filter(|line| line.contains("ERROR"))
+                .collect();
+            let kind = if errors.is_empty() {
+                classify_failure(&message)
+            } else {
+                classify_failure(&errors.join("\n"))
+            };
+            Err(DownloaderError::Download { kind, message })
+        }
+    }
+}
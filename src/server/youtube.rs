@@ -1,6 +1,7 @@
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
-use server_fn::error::NoCustomError;
+
+use crate::server::download::error::DownloaderError;
 
 // Video search result model for communication with client
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -19,15 +20,26 @@ pub struct VideoSearchResult {
 #[server(SearchYoutubeVideos)]
 pub async fn search_youtube_videos(
     query: String,
-) -> Result<Vec<VideoSearchResult>, ServerFnError<NoCustomError>> {
+) -> Result<Vec<VideoSearchResult>, ServerFnError<DownloaderError>> {
     tracing::info!("Searching YouTube for query: {}", query);
 
     {
         use rusty_ytdl::search::{SearchOptions, SearchType, YouTube};
-
-        let youtube = YouTube::new().map_err(|e| {
-            ServerFnError::<NoCustomError>::ServerError(format!("YouTube init error: {}", e))
-        })?;
+        use rusty_ytdl::RequestOptions;
+
+        // Go through the proxy from the network settings, like yt-dlp does
+        let proxy = match crate::server::download::network::load().await.proxy {
+            Some(proxy) => Some(rusty_ytdl::reqwest::Proxy::all(&proxy).map_err(|e| {
+                DownloaderError::InvalidUrl(format!("Invalid proxy {}: {}", proxy, e))
+            })?),
+            None => None,
+        };
+        let request_options = RequestOptions {
+            proxy,
+            ..Default::default()
+        };
+        let youtube = YouTube::new_with_options(&request_options)
+            .map_err(|e| DownloaderError::Internal(format!("YouTube init error: {}", e)))?;
 
         let search_options = SearchOptions {
             limit: 20,
@@ -39,9 +51,7 @@ pub async fn search_youtube_videos(
         let results = youtube
             .search(&query, Some(&search_options))
             .await
-            .map_err(|e| {
-                ServerFnError::<NoCustomError>::ServerError(format!("Search error: {}", e))
-            })?;
+            .map_err(|e| DownloaderError::Network(format!("Search error: {}", e)))?;
 
         let mut videos = Vec::new();
 
@@ -87,7 +97,7 @@ pub async fn download_youtube_video(
     video_id: String,
     title: String,
     is_audio: bool,
-) -> Result<String, ServerFnError<NoCustomError>> {
+) -> Result<String, ServerFnError<DownloaderError>> {
     tracing::info!(
         "Download request: video_id={}, title={}, is_audio={}",
         video_id,
@@ -117,17 +127,12 @@ pub async fn download_youtube_video(
                 // File paths and database updates are handled in the download_with_quality function
                 Ok(format!("Successfully downloaded: {}", title))
             }
-            Err(e) => Err(ServerFnError::<NoCustomError>::ServerError(format!(
-                "Download failed: {}",
-                e
-            ))),
+            Err(e) => Err(e),
         }
     }
 
     #[cfg(not(feature = "server"))]
     {
-        Err(ServerFnError::<NoCustomError>::ServerError(
-            "Server feature not enabled".to_string(),
-        ))
+        Err(DownloaderError::ServerDisabled.into())
     }
 }
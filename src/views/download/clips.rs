@@ -0,0 +1,126 @@
+use crate::server::download::types::{format_timestamp, ClipRange};
+use crate::server::download::utils::{parse_timestamp, url_start_time};
+use dioxus::prelude::*;
+
+// Turn the rows of the picker into clip ranges, with a message per bad row
+//
+// Empty rows are skipped; an empty start means the start of the video.
+fn parse_rows(rows: &[(String, String)]) -> (Vec<ClipRange>, Vec<String>) {
+    let mut clips = Vec::new();
+    let mut errors = Vec::new();
+    for (index, (start, end)) in rows.iter().enumerate() {
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() && end.is_empty() {
+            continue;
+        }
+
+        let range = index + 1;
+        let start_secs = match parse_timestamp(start) {
+            Some(start_secs) => start_secs,
+            None if start.is_empty() => 0.0,
+            None => {
+                errors.push(format!(
+                    "Range {}: can't read the start time \"{}\"",
+                    range, start
+                ));
+                continue;
+            }
+        };
+        let end_secs = if end.is_empty() {
+            None
+        } else {
+            match parse_timestamp(end) {
+                Some(end_secs) => Some(end_secs),
+                None => {
+                    errors.push(format!(
+                        "Range {}: can't read the end time \"{}\"",
+                        range, end
+                    ));
+                    continue;
+                }
+            }
+        };
+        match ClipRange::new(start_secs, end_secs) {
+            Ok(clip) => clips.push(clip),
+            Err(message) => errors.push(format!("Range {}: {}", range, message)),
+        }
+    }
+    (clips, errors)
+}
+
+// Clip picker for downloading only parts of a video
+//
+// Each row takes a start and an optional end time such as "1:30" or "1m30s"
+// and the valid rows are written into `clips`; with no rows the whole video
+// is downloaded. A start time in the link, e.g. `t=90`, fills in the first row.
+#[component]
+pub fn ClipPicker(url: String, clips: Signal<Vec<ClipRange>>, disabled: bool) -> Element {
+    let mut clips = clips;
+    let mut rows = use_signal(|| match url_start_time(&url) {
+        Some(start) => vec![(format_timestamp(start), String::new())],
+        None => Vec::new(),
+    });
+
+    // Keep the ranges in step with what's typed
+    use_effect(move || clips.set(parse_rows(&rows()).0));
+
+    let (_, errors) = parse_rows(&rows());
+
+    rsx! {
+        div { class: "mb-6",
+            div { class: "flex items-center justify-between mb-2",
+                label { class: "text-sm font-medium text-text-primary", "Clip ranges" }
+                button {
+                    class: "text-sm text-text-muted hover:text-text-primary disabled:opacity-50",
+                    disabled,
+                    onclick: move |_| rows.with_mut(|rows| rows.push((String::new(), String::new()))),
+                    "Add range"
+                }
+            }
+
+            if rows().is_empty() {
+                p { class: "text-sm text-text-muted",
+                    "The whole video is downloaded. Add a range to download only part of it."
+                }
+            }
+            for (index, (start, end)) in rows().into_iter().enumerate() {
+                div { key: "{index}", class: "flex items-center gap-2 mb-2",
+                    input {
+                        class: "w-32 bg-background-medium border border-border text-text-primary text-sm rounded-lg p-2",
+                        r#type: "text",
+                        placeholder: "Start, e.g. 1:30",
+                        value: "{start}",
+                        disabled,
+                        oninput: move |e| rows.with_mut(|rows| rows[index].0 = e.value()),
+                    }
+                    span { class: "text-sm text-text-muted", "to" }
+                    input {
+                        class: "w-32 bg-background-medium border border-border text-text-primary text-sm rounded-lg p-2",
+                        r#type: "text",
+                        placeholder: "End (optional)",
+                        value: "{end}",
+                        disabled,
+                        oninput: move |e| rows.with_mut(|rows| rows[index].1 = e.value()),
+                    }
+                    button {
+                        class: "text-sm text-text-muted hover:text-text-primary disabled:opacity-50",
+                        disabled,
+                        onclick: move |_| {
+                            rows.with_mut(|rows| {
+                                rows.remove(index);
+                            })
+                        },
+                        "Remove"
+                    }
+                }
+            }
+
+            for message in errors {
+                p { class: "text-sm text-accent-rose", "{message}" }
+            }
+            if clips().len() > 1 {
+                p { class: "text-sm text-text-muted", "The ranges are joined into one file" }
+            }
+        }
+    }
+}
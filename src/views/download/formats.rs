@@ -0,0 +1,247 @@
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::info::list_formats;
+use crate::server::download::types::{VideoFormat, VideoFormats};
+use crate::views::download::types::FormatType;
+use dioxus::prelude::*;
+
+// Audio stream merged with a video-only format when none is picked
+const BEST_AUDIO: &str = "bestaudio";
+
+// Split a format selection such as "137+140" into its video and audio parts
+fn split_selection(selection: &Option<String>) -> (Option<String>, Option<String>) {
+    match selection.as_deref().map(|id| id.split_once('+')) {
+        Some(Some((video, audio))) => (Some(video.to_string()), Some(audio.to_string())),
+        Some(None) => (selection.clone(), None),
+        None => (None, None),
+    }
+}
+
+// Format picker listing everything yt-dlp offers for a video
+//
+// Writes the picked yt-dlp format into `selection`: a single format ID for
+// audio and combined formats, or "video+audio" for video-only formats.
+// `None` means the download falls back to the chosen quality.
+#[component]
+pub fn FormatPicker(
+    url: String,
+    format_type: FormatType,
+    selection: Signal<Option<String>>,
+    disabled: bool,
+) -> Element {
+    let mut selection = selection;
+    let mut formats = use_signal(|| None::<VideoFormats>);
+    let mut loading = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let load_formats = move |_| {
+        let url = url.trim().to_string();
+        if url.is_empty() {
+            error.set(Some("Enter a video URL first".to_string()));
+            return;
+        }
+
+        loading.set(true);
+        error.set(None);
+        spawn(async move {
+            match list_formats(url).await {
+                Ok(list) => formats.set(Some(list)),
+                Err(e) => error.set(Some(format!(
+                    "Failed to load formats: {}",
+                    DownloaderError::from(e).message()
+                ))),
+            }
+            loading.set(false);
+        });
+    };
+
+    let Some(list) = formats() else {
+        return rsx! {
+            div { class: "mb-6",
+                button {
+                    class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover disabled:opacity-50",
+                    disabled: disabled || loading(),
+                    onclick: load_formats,
+                    if loading() { "Loading formats..." } else { "Choose an exact format" }
+                }
+                if let Some(message) = error() {
+                    p { class: "mt-2 text-sm text-accent-rose", "{message}" }
+                }
+            }
+        };
+    };
+
+    let (picked_video, picked_audio) = split_selection(&selection());
+    let audio_only: Vec<VideoFormat> = list
+        .formats
+        .iter()
+        .filter(|format| !format.has_video())
+        .cloned()
+        .collect();
+    // Best formats first
+    let rows: Vec<VideoFormat> = match format_type {
+        FormatType::Video => list
+            .formats
+            .iter()
+            .rev()
+            .filter(|f| f.has_video())
+            .cloned()
+            .collect(),
+        FormatType::Audio(_) => audio_only.iter().rev().cloned().collect(),
+    };
+    // Video-only formats need an audio stream merged in
+    let needs_audio = format_type == FormatType::Video
+        && list
+            .formats
+            .iter()
+            .any(|format| Some(&format.format_id) == picked_video.as_ref() && !format.has_audio());
+
+    let mut pick = move |format: VideoFormat| {
+        let choice = if format.has_video() && !format.has_audio() {
+            let audio = split_selection(&selection.peek()).1;
+            format!(
+                "{}+{}",
+                format.format_id,
+                audio.unwrap_or_else(|| BEST_AUDIO.to_string())
+            )
+        } else {
+            format.format_id
+        };
+        selection.set(Some(choice));
+    };
+
+    rsx! {
+        div { class: "mb-6",
+            div { class: "flex items-center justify-between mb-2",
+                label { class: "text-sm font-medium text-text-primary", "Formats for {list.title}" }
+                button {
+                    class: "text-sm text-text-muted hover:text-text-primary disabled:opacity-50",
+                    disabled,
+                    onclick: move |_| selection.set(None),
+                    "Use quality setting"
+                }
+            }
+            div { class: "max-h-72 overflow-y-auto rounded-lg border border-border",
+                table { class: "w-full text-sm text-left text-text-secondary",
+                    thead { class: "sticky top-0 bg-background-medium text-xs uppercase text-text-muted",
+                        tr {
+                            th { class: "px-3 py-2", "" }
+                            th { class: "px-3 py-2", "ID" }
+                            th { class: "px-3 py-2", "Resolution" }
+                            th { class: "px-3 py-2", "FPS" }
+                            th { class: "px-3 py-2", "Codec" }
+                            th { class: "px-3 py-2", "Bitrate" }
+                            th { class: "px-3 py-2", "Container" }
+                            th { class: "px-3 py-2", "Size" }
+                        }
+                    }
+                    tbody {
+                        for format in rows {
+                            FormatRow {
+                                key: "{format.format_id}",
+                                checked: Some(&format.format_id) == picked_video.as_ref(),
+                                disabled,
+                                on_pick: move |format| pick(format),
+                                format: format.clone(),
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Audio stream for a video-only format
+            if needs_audio {
+                div { class: "mt-3 flex items-center gap-3",
+                    label { class: "text-sm text-text-secondary", "Audio track" }
+                    select {
+                        class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg p-2",
+                        disabled,
+                        onchange: move |e| {
+                            if let (Some(video), _) = split_selection(&selection.peek()) {
+                                selection.set(Some(format!("{}+{}", video, e.value())));
+                            }
+                        },
+                        option {
+                            value: BEST_AUDIO,
+                            selected: picked_audio.as_deref() == Some(BEST_AUDIO),
+                            "Best available"
+                        }
+                        for format in audio_only.iter().rev() {
+                            option {
+                                value: "{format.format_id}",
+                                selected: picked_audio.as_ref() == Some(&format.format_id),
+                                {format!(
+                                    "{} · {} · {} kbps · {}",
+                                    format.format_id,
+                                    format.acodec.clone().unwrap_or_default(),
+                                    format.bitrate_kbps.map(|kbps| format!("{:.0}", kbps)).unwrap_or_else(|| "?".to_string()),
+                                    format.format_filesize()
+                                )}
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(selected) = selection() {
+                p { class: "mt-2 text-sm text-text-muted",
+                    "Downloading format "
+                    span { class: "font-mono text-accent-teal", "{selected}" }
+                    " instead of the quality setting"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn FormatRow(
+    format: VideoFormat,
+    checked: bool,
+    disabled: bool,
+    on_pick: EventHandler<VideoFormat>,
+) -> Element {
+    let codec = match (&format.vcodec, &format.acodec) {
+        (Some(video), Some(audio)) => format!("{} / {}", video, audio),
+        (Some(video), None) => format!("{} (no audio)", video),
+        (None, Some(audio)) => audio.clone(),
+        (None, None) => "-".to_string(),
+    };
+    let fps = format
+        .fps
+        .map(|fps| format!("{:.0}", fps))
+        .unwrap_or_else(|| "-".to_string());
+    let bitrate = format
+        .bitrate_kbps
+        .map(|kbps| format!("{:.0} kbps", kbps))
+        .unwrap_or_else(|| "-".to_string());
+    let picked = format.clone();
+
+    rsx! {
+        tr {
+            class: "border-t border-border cursor-pointer",
+            class: if checked { "bg-accent-teal bg-opacity-10 text-text-primary" } else { "hover:bg-background-hover" },
+            onclick: move |_| {
+                if !disabled {
+                    on_pick.call(picked.clone());
+                }
+            },
+            td { class: "px-3 py-2",
+                input { r#type: "radio", checked, disabled }
+            }
+            td { class: "px-3 py-2 font-mono", "{format.format_id}" }
+            td { class: "px-3 py-2",
+                "{format.resolution()}"
+                if format.hdr {
+                    span { class: "ml-2 px-1.5 rounded text-xs font-medium bg-accent-amber bg-opacity-20 text-accent-amber",
+                        "HDR"
+                    }
+                }
+            }
+            td { class: "px-3 py-2", "{fps}" }
+            td { class: "px-3 py-2 truncate max-w-[12rem]", "{codec}" }
+            td { class: "px-3 py-2", "{bitrate}" }
+            td { class: "px-3 py-2", "{format.ext}" }
+            td { class: "px-3 py-2", "{format.format_filesize()}" }
+        }
+    }
+}
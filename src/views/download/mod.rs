@@ -1,6 +1,9 @@
 // Export all parts of the download module
+mod clips;
+mod formats;
 mod handlers;
 mod platforms;
+mod subtitles;
 mod types;
 mod ui;
 
@@ -19,18 +19,15 @@ pub fn format_eta(seconds: u64) -> String {
     }
 }
 
-// Non-web fallback implementation using base64
+// Open a saved download with the system's default app
 #[cfg(not(feature = "web"))]
-pub fn create_blob_url(data: &[u8], mime_type: &str) -> Option<String> {
-    use base64::{engine::general_purpose::STANDARD, Engine};
-    let base64_data = STANDARD.encode(data);
-    let data_url = format!("data:{};base64,{}", mime_type, base64_data);
-    Some(data_url)
+pub fn open_saved_file(_download_id: i64, file_path: &str) {
+    crate::server::download::services::open_file(file_path);
 }
 
 // No-op for trigger_download on non-web platforms
 #[cfg(not(feature = "web"))]
 pub fn trigger_download(_url: &str, _filename: &str) {
     // This is a no-op for non-web platforms
-    // Downloads happen via data URLs in the anchor element
+    // Desktop builds save finished files with save_to_disk instead
 }
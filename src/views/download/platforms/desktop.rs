@@ -1,15 +1,17 @@
 // Desktop-specific implementations for the download view
-use crate::views::download::types::FormatType;
 use dioxus::prelude::*;
+use std::path::Path;
 
-/// Save downloaded data to disk
+/// Save a finished download to disk
 ///
 /// This implementation:
-/// 1. Tries to save to the user's Downloads folder
+/// 1. Copies the file into the user's Downloads folder
 /// 2. Falls back to a temporary directory if Downloads isn't available
 /// 3. Returns a result with the path of the saved file or an error message
+///
+/// The copy is streamed by the OS, so the file is never held in memory.
 pub fn save_to_disk(
-    data: &[u8],
+    source: &str,
     filename: &str,
     status_signal: &Signal<Option<String>>,
     error_signal: &Signal<Option<String>>,
@@ -18,55 +20,46 @@ pub fn save_to_disk(
     let mut status_signal = status_signal.clone();
     let mut error_signal = error_signal.clone();
 
-    // Try to get the user's Downloads folder
-    let home_dir = dirs::home_dir();
-    let downloads_dir = home_dir.and_then(|dir| Some(dir.join("Downloads")));
+    // Save to the Downloads folder, or a temporary directory if there isn't one
+    let (target_dir, temporary) = match dirs::home_dir().map(|dir| dir.join("Downloads")) {
+        Some(downloads_path) => (downloads_path, false),
+        None => (std::env::temp_dir(), true),
+    };
+    let file_path = target_dir.join(filename);
+    let path_str = file_path.to_string_lossy().to_string();
 
-    // If we have a Downloads folder, save there
-    if let Some(downloads_path) = downloads_dir {
-        // Create the full path with filename
-        let file_path = downloads_path.join(filename);
+    // The download may already have been moved to this exact location
+    let already_there = match (Path::new(source).canonicalize(), file_path.canonicalize()) {
+        (Ok(source), Ok(target)) => source == target,
+        _ => false,
+    };
 
-        // Try to save the file directly
-        match std::fs::write(&file_path, data) {
-            Ok(_) => {
-                let path_str = file_path.to_string_lossy().to_string();
-                status_signal.set(Some(format!("File saved successfully to {}", path_str)));
-                error_signal.set(None);
-                Ok(path_str)
-            }
-            Err(e) => {
-                let error_msg = format!("Failed to save file: {}", e);
-                error_signal.set(Some(error_msg.clone()));
-                status_signal.set(Some(
-                    "Error saving file. Check permissions and try again.".to_string(),
-                ));
-                Err(error_msg)
-            }
-        }
+    let result = if already_there {
+        Ok(())
     } else {
-        // Fall back to a temporary directory if we can't find Downloads
-        let temp_dir = std::env::temp_dir();
-        let file_path = temp_dir.join(filename);
+        std::fs::copy(source, &file_path).map(|_| ())
+    };
 
-        match std::fs::write(&file_path, data) {
-            Ok(_) => {
-                let path_str = file_path.to_string_lossy().to_string();
+    match result {
+        Ok(_) => {
+            if temporary {
                 status_signal.set(Some(format!(
                     "File saved to temporary location: {}",
                     path_str
                 )));
-                error_signal.set(None);
-                Ok(path_str)
-            }
-            Err(e) => {
-                let error_msg = format!("Failed to save file: {}", e);
-                error_signal.set(Some(error_msg.clone()));
-                status_signal.set(Some(
-                    "Error saving file. Check permissions and try again.".to_string(),
-                ));
-                Err(error_msg)
+            } else {
+                status_signal.set(Some(format!("File saved successfully to {}", path_str)));
             }
+            error_signal.set(None);
+            Ok(path_str)
+        }
+        Err(e) => {
+            let error_msg = format!("Failed to save file: {}", e);
+            error_signal.set(Some(error_msg.clone()));
+            status_signal.set(Some(
+                "Error saving file. Check permissions and try again.".to_string(),
+            ));
+            Err(error_msg)
         }
     }
 }
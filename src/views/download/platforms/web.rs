@@ -1,25 +1,18 @@
 // Web-specific implementations
-use js_sys::{Array, Uint8Array};
-use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+use crate::server::download::utils::file_url;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlAnchorElement;
 
-/// Create a blob URL from raw data for web platform
-pub fn create_blob_url(data: &[u8], mime_type: &str) -> Option<String> {
-    let uint8_array = Uint8Array::new_with_length(data.len() as u32);
-    uint8_array.copy_from(data);
-
-    let array = Array::new();
-    array.push(&uint8_array.buffer().into());
-
-    let mut blob_options = BlobPropertyBag::new();
-    blob_options.set_type(mime_type);
-
-    Blob::new_with_u8_array_sequence_and_options(&array, &blob_options)
-        .ok()
-        .and_then(|blob| Url::create_object_url_with_blob(&blob).ok())
+/// Open a saved download in a new tab, streamed from the file endpoint
+pub fn open_saved_file(download_id: i64, _file_path: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.open_with_url(&file_url(download_id));
+    }
 }
 
 /// Trigger a download for web platform
+///
+/// `url` is the server's file endpoint, which streams the file to the browser.
 pub fn trigger_download(url: &str, filename: &str) {
     if let Some(window) = web_sys::window() {
         if let Some(document) = window.document() {
@@ -0,0 +1,190 @@
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::info::list_subtitles;
+use crate::server::download::types::{SubtitleFormat, SubtitleOptions, SubtitleTrack};
+use crate::views::download::types::FormatType;
+use dioxus::prelude::*;
+
+// Subtitle picker listing the languages a video has subtitles in
+//
+// Writes the picked languages into `options`; with no languages picked no
+// subtitles are fetched. Picking a language that only has auto-generated
+// captions turns on the caption fallback.
+#[component]
+pub fn SubtitlePicker(
+    url: String,
+    format_type: FormatType,
+    options: Signal<SubtitleOptions>,
+    disabled: bool,
+) -> Element {
+    let mut options = options;
+    let mut tracks = use_signal(|| None::<Vec<SubtitleTrack>>);
+    let mut loading = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let load_tracks = move |_| {
+        let url = url.trim().to_string();
+        if url.is_empty() {
+            error.set(Some("Enter a video URL first".to_string()));
+            return;
+        }
+
+        loading.set(true);
+        error.set(None);
+        spawn(async move {
+            match list_subtitles(url).await {
+                Ok(list) if list.is_empty() => {
+                    error.set(Some("This video has no subtitles".to_string()))
+                }
+                Ok(list) => tracks.set(Some(list)),
+                Err(e) => error.set(Some(format!(
+                    "Failed to load subtitles: {}",
+                    DownloaderError::from(e).message()
+                ))),
+            }
+            loading.set(false);
+        });
+    };
+
+    let Some(list) = tracks() else {
+        return rsx! {
+            div { class: "mb-6",
+                button {
+                    class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover disabled:opacity-50",
+                    disabled: disabled || loading(),
+                    onclick: load_tracks,
+                    if loading() { "Loading subtitles..." } else { "Add subtitles" }
+                }
+                if let Some(message) = error() {
+                    p { class: "mt-2 text-sm text-accent-rose", "{message}" }
+                }
+            }
+        };
+    };
+
+    let picked = options().languages;
+    let (automatic, manual): (Vec<SubtitleTrack>, Vec<SubtitleTrack>) =
+        list.iter().cloned().partition(|track| track.auto_generated);
+    let is_video = format_type == FormatType::Video;
+
+    let mut toggle = move |language: String| {
+        let tracks = tracks.peek().clone().unwrap_or_default();
+        let auto_only = |language: &String| {
+            tracks
+                .iter()
+                .any(|track| track.auto_generated && track.language == *language)
+        };
+        options.with_mut(|options| {
+            match options
+                .languages
+                .iter()
+                .position(|picked| *picked == language)
+            {
+                Some(index) => {
+                    options.languages.remove(index);
+                }
+                None => options.languages.push(language),
+            }
+            options.auto_generated = options.languages.iter().any(auto_only);
+        });
+    };
+
+    rsx! {
+        div { class: "mb-6",
+            div { class: "flex items-center justify-between mb-2",
+                label { class: "text-sm font-medium text-text-primary", "Subtitles" }
+                button {
+                    class: "text-sm text-text-muted hover:text-text-primary disabled:opacity-50",
+                    disabled,
+                    onclick: move |_| {
+                        options
+                            .with_mut(|options| {
+                                options.languages.clear();
+                                options.auto_generated = false;
+                            })
+                    },
+                    "No subtitles"
+                }
+            }
+
+            if !manual.is_empty() {
+                div { class: "flex flex-wrap gap-2 mb-3",
+                    for track in manual {
+                        LanguageChip {
+                            key: "{track.language}",
+                            language: track.language.clone(),
+                            checked: picked.contains(&track.language),
+                            disabled,
+                            on_toggle: move |language| toggle(language),
+                        }
+                    }
+                }
+            }
+            if !automatic.is_empty() {
+                p { class: "text-xs text-text-muted mb-2", "Auto-generated captions" }
+                div { class: "flex flex-wrap gap-2 mb-3 max-h-32 overflow-y-auto",
+                    for track in automatic {
+                        LanguageChip {
+                            key: "{track.language}",
+                            language: track.language.clone(),
+                            checked: picked.contains(&track.language),
+                            disabled,
+                            on_toggle: move |language| toggle(language),
+                        }
+                    }
+                }
+            }
+
+            div { class: "flex flex-wrap items-center gap-4",
+                select {
+                    class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg p-2",
+                    disabled,
+                    onchange: move |e| {
+                        if let Some(format) = SubtitleFormat::from_name(&e.value()) {
+                            options.with_mut(|options| options.format = format);
+                        }
+                    },
+                    for format in SubtitleFormat::ALL {
+                        option {
+                            value: format.as_str(),
+                            selected: options().format == format,
+                            "{format.as_str().to_uppercase()}"
+                        }
+                    }
+                }
+                if is_video {
+                    label { class: "flex items-center gap-2 text-sm text-text-secondary",
+                        input {
+                            r#type: "checkbox",
+                            checked: options().embed,
+                            disabled,
+                            onchange: move |e| options.with_mut(|options| options.embed = e.checked()),
+                        }
+                        "Embed in the video"
+                    }
+                } else {
+                    span { class: "text-sm text-text-muted", "Saved next to the audio file" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LanguageChip(
+    language: String,
+    checked: bool,
+    disabled: bool,
+    on_toggle: EventHandler<String>,
+) -> Element {
+    let toggled = language.clone();
+
+    rsx! {
+        button {
+            class: "px-3 py-1 rounded-full text-xs border disabled:opacity-50",
+            class: if checked { "bg-accent-teal text-white border-accent-teal" } else { "border-border text-text-secondary hover:bg-background-hover" },
+            disabled,
+            onclick: move |_| on_toggle.call(toggled.clone()),
+            "{language}"
+        }
+    }
+}
@@ -1,10 +1,32 @@
+use crate::server::download::error::DownloaderError;
+use crate::server::download::types::{AudioFormat, DownloadedFile};
 use dioxus::prelude::*;
 
 // Enum for format type selection
 #[derive(Clone, PartialEq)]
 pub enum FormatType {
     Video,
-    Audio,
+    Audio(AudioFormat),
+}
+
+// Signals the download view shows a running download in
+#[derive(Clone, Copy)]
+pub struct DownloadState {
+    pub download_in_progress: Signal<bool>,
+    pub progress_percent: Signal<i32>,
+    pub status: Signal<Option<String>>,
+    pub progress_eta: Signal<String>,
+    pub loading: Signal<bool>,
+    pub error: Signal<Option<String>>,
+    // Typed error of the last failed download, for targeted guidance
+    pub failure: Signal<Option<DownloaderError>>,
+    pub downloaded_file: Signal<Option<DownloadedFile>>,
+    pub blob_url: Signal<Option<String>>,
+    pub download_ready: Signal<bool>,
+    // Queue job behind the running download, so it can be cancelled
+    pub active_job: Signal<Option<i64>>,
+    // Whether the running job is recording a live stream
+    pub recording: Signal<bool>,
 }
 
 // Enum for quality selection
@@ -13,6 +35,8 @@ pub enum Quality {
     Highest,
     Medium,
     Lowest,
+    // Best video no taller than the given height
+    UpTo(u32),
 }
 
 // Helper functions for converting types to/from strings
@@ -20,21 +44,21 @@ impl FormatType {
     pub fn to_string(&self) -> String {
         match self {
             FormatType::Video => "video".to_string(),
-            FormatType::Audio => "audio".to_string(),
+            FormatType::Audio(_) => "audio".to_string(),
         }
     }
 
     pub fn get_extension(&self) -> &'static str {
         match self {
             FormatType::Video => "mp4",
-            FormatType::Audio => "mp3",
+            FormatType::Audio(format) => format.extension(),
         }
     }
 
     pub fn get_mime_type(&self) -> &'static str {
         match self {
             FormatType::Video => "video/mp4",
-            FormatType::Audio => "audio/mpeg",
+            FormatType::Audio(format) => format.mime_type(),
         }
     }
 
@@ -53,6 +77,7 @@ impl Quality {
             Quality::Highest => "highest".to_string(),
             Quality::Medium => "medium".to_string(),
             Quality::Lowest => "lowest".to_string(),
+            Quality::UpTo(height) => format!("{}p", height),
         }
     }
 }
@@ -1,6 +1,16 @@
-use crate::views::download::handlers::{execute_download, update_filename};
-use crate::views::download::platforms::trigger_download;
-use crate::views::download::types::{FormatType, Quality};
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::duplicates::find_duplicates;
+use crate::server::download::handlers::queue::{cancel_download, stop_recording};
+use crate::server::download::types::{
+    AudioFormat, AudioQuality, ClipRange, Container, DownloadOptions, DownloadedFile,
+    DuplicateDownload, LiveOptions, SubtitleOptions, VideoCodec, VideoQuality,
+};
+use crate::views::download::clips::ClipPicker;
+use crate::views::download::formats::FormatPicker;
+use crate::views::download::handlers::{execute_download, update_filename, with_extension};
+use crate::views::download::platforms::{open_saved_file, trigger_download};
+use crate::views::download::subtitles::SubtitlePicker;
+use crate::views::download::types::{DownloadState, FormatType, Quality};
 use dioxus::prelude::*;
 use dioxus_free_icons::{icons::fa_solid_icons::FaDownload, Icon};
 
@@ -16,20 +26,39 @@ pub fn Download(url: String, format: String) -> Element {
     let mut format_type = use_signal(|| {
         // Set format type based on the format parameter
         match format.as_str() {
-            "audio" => FormatType::Audio,
+            "audio" => FormatType::Audio(AudioFormat::default()),
             _ => FormatType::Video, // Default to video
         }
     });
     let mut quality = use_signal(|| Quality::Highest);
+    // Codec and container preferences for video downloads
+    let mut video_codec = use_signal(|| None::<VideoCodec>);
+    let mut container = use_signal(|| None::<Container>);
+    // Target bitrate for lossy audio formats, `None` for the best VBR
+    let mut audio_bitrate = use_signal(|| None::<u32>);
+    // Exact yt-dlp format picked from the format list, overriding the quality
+    let mut format_id = use_signal(|| None::<String>);
+    // Split audio downloads into a file per chapter
+    let mut split_chapters = use_signal(|| false);
+    // Subtitle languages picked for the video
+    let mut subtitle_options = use_signal(SubtitleOptions::default);
+    // Parts of the video to download, empty for the whole video
+    let clips = use_signal(Vec::<ClipRange>::new);
+    // How to record the video if it's a live stream
+    let mut live_options = use_signal(LiveOptions::default);
+    // Speed cap for this download in KiB/s, `None` for the one in the settings
+    let mut rate_limit = use_signal(|| None::<u64>);
 
     // UI state
     let mut status = use_signal(|| None::<String>);
     let mut error = use_signal(|| None::<String>);
+    // Typed error of the last failed download, for targeted guidance
+    let mut failure = use_signal(|| None::<DownloaderError>);
     let mut download_ready = use_signal(|| false);
     let mut loading = use_signal(|| false);
 
     // Download state
-    let download_data = use_signal(|| None::<Vec<u8>>);
+    let downloaded_file = use_signal(|| None::<DownloadedFile>);
     let mut blob_url = use_signal(|| None::<String>);
 
     // Progress tracking
@@ -40,6 +69,29 @@ pub fn Download(url: String, format: String) -> Element {
     // Flag to track if download is in progress and we should poll for updates
     let mut download_in_progress = use_signal(|| false);
 
+    // Queue job behind the running download, so it can be cancelled
+    let active_job = use_signal(|| None::<i64>);
+    // Whether the running job is recording a live stream
+    let recording = use_signal(|| false);
+    // Earlier downloads of the video in the same format, waiting for a decision
+    let mut duplicates = use_signal(Vec::<DuplicateDownload>::new);
+
+    // What `execute_download` reports the download's progress and outcome in
+    let download_state = DownloadState {
+        download_in_progress,
+        progress_percent,
+        status,
+        progress_eta,
+        loading,
+        error,
+        failure,
+        downloaded_file,
+        blob_url,
+        download_ready,
+        active_job,
+        recording,
+    };
+
     // Define button text based on platform
     let save_button_text = if cfg!(feature = "desktop") {
         "Choose Where to Save"
@@ -50,6 +102,8 @@ pub fn Download(url: String, format: String) -> Element {
     // Handle format type change
     let mut handle_format_change = move |new_format: FormatType| {
         format_type.set(new_format.clone());
+        // Picked formats only make sense for the format type they were picked for
+        format_id.set(None);
 
         // Always update the filename extension when format changes
         if !filename().is_empty() {
@@ -58,25 +112,33 @@ pub fn Download(url: String, format: String) -> Element {
         }
     };
 
+    // The `quality` value sent to the server, see `VideoQuality` and `AudioQuality`
+    let quality_setting = move || match format_type() {
+        FormatType::Video => VideoQuality {
+            codec: video_codec(),
+            container: container(),
+            ..VideoQuality::parse(&quality().to_string())
+        }
+        .to_string(),
+        FormatType::Audio(format) => AudioQuality {
+            format,
+            bitrate_kbps: audio_bitrate(),
+        }
+        .to_string(),
+    };
+
     // Handle URL validation
     let is_url_valid = !url().is_empty();
 
-    // Handle the download button click
-    let handle_download = move |_| {
-        // Validate inputs
-        if url().trim().is_empty() {
-            error.set(Some("Please enter a valid YouTube URL".into()));
-            return;
-        }
-
-        if filename().trim().is_empty() {
-            error.set(Some("Please enter a filename".into()));
-            return;
-        }
+    // Start the download, replacing an earlier copy when `replaces` is set;
+    // `allow_duplicate` downloads it again next to the copies in the library
+    let mut start_download = move |replaces: Option<i64>, allow_duplicate: bool| {
+        duplicates.set(Vec::new());
 
         // Reset state for new download
         loading.set(true);
         error.set(None);
+        failure.set(None);
         status.set(Some("Initializing download...".into()));
         download_ready.set(false);
         blob_url.set(None);
@@ -88,20 +150,80 @@ pub fn Download(url: String, format: String) -> Element {
         download_in_progress.set(true);
 
         // Execute the actual download with real progress updates
-        execute_download(
-            url().clone(),
-            format_type(),
-            quality(),
-            &download_in_progress,
-            &progress_percent,
-            &status,
-            &progress_eta,
-            &loading,
-            &error,
-            &download_data,
-            &blob_url,
-            &download_ready,
-        );
+        let options = DownloadOptions {
+            format_id: format_id(),
+            subtitles: Some(subtitle_options()).filter(|options| !options.languages.is_empty()),
+            split_chapters: split_chapters(),
+            clips: clips(),
+            live: live_options(),
+            rate_limit_kib: rate_limit(),
+            replaces,
+            allow_duplicate,
+            ..DownloadOptions::new(format_type().to_string(), quality_setting())
+        };
+        execute_download(url(), options, download_state);
+    };
+
+    // Handle the download button click
+    let handle_download = move |_| {
+        // Validate inputs
+        if url().trim().is_empty() {
+            error.set(Some("Please enter a valid video URL".into()));
+            return;
+        }
+
+        if filename().trim().is_empty() {
+            error.set(Some("Please enter a filename".into()));
+            return;
+        }
+
+        // Clips are new files by nature; whole videos are checked against the library
+        if !clips().is_empty() {
+            start_download(None, false);
+            return;
+        }
+        let (url, format, quality) = (url(), format_type().to_string(), quality_setting());
+        let picked_format = format_id();
+        spawn(async move {
+            match find_duplicates(url, format, quality, picked_format).await {
+                Ok(found) if !found.is_empty() => duplicates.set(found),
+                Ok(_) => start_download(None, false),
+                Err(e) => {
+                    tracing::warn!("Failed to check for earlier downloads: {}", e);
+                    start_download(None, false);
+                }
+            }
+        });
+    };
+
+    // Stop the running download; the download task reports the outcome
+    let handle_cancel = move |_| {
+        if let Some(job_id) = active_job() {
+            status.set(Some("Cancelling download...".into()));
+            spawn(async move {
+                if let Err(e) = cancel_download(job_id).await {
+                    error.set(Some(format!(
+                        "Failed to cancel download: {}",
+                        DownloaderError::from(e).message()
+                    )));
+                }
+            });
+        }
+    };
+
+    // End the recording early; what was recorded so far is kept
+    let handle_stop_recording = move |_| {
+        if let Some(job_id) = active_job() {
+            status.set(Some("Stopping recording...".into()));
+            spawn(async move {
+                if let Err(e) = stop_recording(job_id).await {
+                    error.set(Some(format!(
+                        "Failed to stop recording: {}",
+                        DownloaderError::from(e).message()
+                    )));
+                }
+            });
+        }
     };
 
     let get_button_class = move || {
@@ -163,13 +285,24 @@ pub fn Download(url: String, format: String) -> Element {
     };
 
     // Define download content
+    let video_container_label = container()
+        .unwrap_or(Container::Mp4)
+        .as_str()
+        .to_uppercase();
     let download_content = if download_ready() {
-        // Get the extension and update filename if needed
-        let extension = format_type().get_extension();
-        let download_filename = if filename().ends_with(extension) {
+        // Name the file after the one the server saved, whose extension is
+        // the final word on the format
+        let extension = downloaded_file()
+            .and_then(|file| {
+                std::path::Path::new(&file.file_name)
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| format_type().get_extension().to_string());
+        let download_filename = if filename().ends_with(&extension) {
             filename().clone()
         } else {
-            update_filename(&filename(), &format_type())
+            with_extension(&filename(), &extension)
         };
 
         // Define platform-specific download handlers
@@ -182,15 +315,19 @@ pub fn Download(url: String, format: String) -> Element {
 
         #[cfg(feature = "desktop")]
         let download_handler = {
-            let data_clone = download_data.clone();
+            let file_clone = downloaded_file.clone();
             let download_filename_clone = download_filename.clone();
             let mut status_clone = status.clone();
             let mut error_clone = error.clone();
 
             move |_| {
-                if let Some(data) = data_clone() {
-                    let _ =
-                        save_to_disk(&data, &download_filename_clone, &status_clone, &error_clone);
+                if let Some(file) = file_clone() {
+                    let _ = save_to_disk(
+                        &file.file_path,
+                        &download_filename_clone,
+                        &status_clone,
+                        &error_clone,
+                    );
                 }
             }
         };
@@ -214,13 +351,13 @@ pub fn Download(url: String, format: String) -> Element {
                     FormatType::Video => rsx! {
                         p { class: "text-text-secondary mb-4",
                             "File format: "
-                            span { class: "font-bold text-accent-teal", "Video (MP4)" }
+                            span { class: "font-bold text-accent-teal", "Video ({video_container_label})" }
                         }
                     },
-                    FormatType::Audio => rsx! {
+                    FormatType::Audio(audio_format) => rsx! {
                         p { class: "text-text-secondary mb-4",
                             "File format: "
-                            span { class: "font-bold text-accent-amber", "Audio (MP3)" }
+                            span { class: "font-bold text-accent-amber", "Audio ({audio_format.label()})" }
                         }
                     },
                 }
@@ -239,7 +376,18 @@ pub fn Download(url: String, format: String) -> Element {
     };
 
     // Progress bar component
-    let progress_component = if loading() && progress_percent() > 0 {
+    let progress_component = if loading() && recording() {
+        // A recording has no percentage, so the bar just pulses
+        rsx! {
+            div { class: "mt-4",
+                div { class: "mb-2 flex justify-between",
+                    span { class: "text-text-secondary", "{status().unwrap_or_default()}" }
+                    span { class: "text-accent-rose font-medium", "● Live" }
+                }
+                div { class: "w-full bg-accent-rose bg-opacity-60 rounded-full h-2.5 animate-pulse" }
+            }
+        }
+    } else if loading() && progress_percent() > 0 {
         let eta_section = if !progress_eta().is_empty() {
             rsx! {
                 div { class: "mt-1 text-sm text-text-muted flex justify-between",
@@ -283,11 +431,90 @@ pub fn Download(url: String, format: String) -> Element {
         rsx! {}
     };
 
+    // Cancel button, shown while a download is queued or running
+    let cancel_button = if loading() && active_job().is_some() {
+        rsx! {
+            div { class: "mt-3 flex justify-end gap-2",
+                if recording() {
+                    button {
+                        class: "px-4 py-2 text-sm rounded-lg border border-accent-rose text-accent-rose hover:bg-accent-rose hover:bg-opacity-10 transition-colors",
+                        onclick: handle_stop_recording,
+                        "Stop recording"
+                    }
+                }
+                button {
+                    class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover transition-colors",
+                    onclick: handle_cancel,
+                    "Cancel download"
+                }
+            }
+        }
+    } else {
+        rsx! {}
+    };
+
+    // Warning shown when the video was already downloaded in this format
+    let duplicate_warning = if let Some(existing) = duplicates().first().cloned() {
+        let copies = duplicates().len();
+        let existing_id = existing.download_id;
+        let existing_path = existing.file_path.clone();
+        rsx! {
+            div { class: "mt-4 bg-accent-amber bg-opacity-10 border border-accent-amber p-4 rounded",
+                p { class: "text-accent-amber font-medium", "Already in your library" }
+                p { class: "mt-1 text-sm text-text-secondary",
+                    "\"{existing.title}\" was downloaded in this format and quality on {existing.downloaded_at} as {existing.file_name}."
+                }
+                if copies > 1 {
+                    p { class: "mt-1 text-sm text-text-secondary",
+                        "There are {copies} copies; replacing swaps out the newest."
+                    }
+                }
+                div { class: "mt-3 flex flex-wrap gap-2",
+                    button {
+                        class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover transition-colors",
+                        onclick: move |_| open_saved_file(existing_id, &existing_path),
+                        "Open existing"
+                    }
+                    button {
+                        class: "px-4 py-2 text-sm rounded-lg border border-accent-amber text-accent-amber hover:bg-accent-amber hover:bg-opacity-10 transition-colors",
+                        onclick: move |_| start_download(None, true),
+                        "Download again"
+                    }
+                    button {
+                        class: "px-4 py-2 text-sm rounded-lg border border-accent-amber text-accent-amber hover:bg-accent-amber hover:bg-opacity-10 transition-colors",
+                        onclick: move |_| start_download(Some(existing_id), false),
+                        "Replace"
+                    }
+                    button {
+                        class: "px-4 py-2 text-sm rounded-lg text-text-muted hover:text-text-primary transition-colors",
+                        onclick: move |_| duplicates.set(Vec::new()),
+                        "Cancel"
+                    }
+                }
+            }
+        }
+    } else {
+        rsx! {}
+    };
+
     // Error message component
     let error_message = if let Some(err) = error() {
+        let guidance = failure().map(|failure| failure.guidance());
+        // Only offer a retry when running the same download again can help
+        let can_retry = failure().is_some_and(|failure| failure.is_retryable()) && !loading();
         rsx! {
             div { class: "mt-4 bg-accent-rose bg-opacity-10 text-accent-rose p-3 rounded",
                 p { "{err}" }
+                if let Some(guidance) = guidance {
+                    p { class: "mt-1 text-sm text-text-secondary", "{guidance}" }
+                }
+                if can_retry {
+                    button {
+                        class: "mt-3 px-4 py-2 text-sm rounded-lg border border-accent-rose text-accent-rose hover:bg-accent-rose hover:bg-opacity-10 transition-colors",
+                        onclick: handle_download,
+                        "Retry download"
+                    }
+                }
             }
         }
     } else {
@@ -325,10 +552,10 @@ pub fn Download(url: String, format: String) -> Element {
                             // Audio option
                             button {
                                 key: "audio",
-                                class: if format_type() == FormatType::Audio { "bg-accent-amber bg-opacity-20 text-accent-amber border border-accent-amber text-sm rounded-lg px-4 py-2.5 focus:outline-none" } else { "bg-background-medium hover:bg-background-hover text-text-primary border border-border text-sm rounded-lg px-4 py-2.5 focus:outline-none" },
-                                onclick: move |_| handle_format_change(FormatType::Audio),
+                                class: if matches!(format_type(), FormatType::Audio(_)) { "bg-accent-amber bg-opacity-20 text-accent-amber border border-accent-amber text-sm rounded-lg px-4 py-2.5 focus:outline-none" } else { "bg-background-medium hover:bg-background-hover text-text-primary border border-border text-sm rounded-lg px-4 py-2.5 focus:outline-none" },
+                                onclick: move |_| handle_format_change(FormatType::Audio(AudioFormat::default())),
                                 disabled: loading(),
-                                "🎵 Audio (MP3)"
+                                "🎵 Audio"
                             }
                             // Video option
                             button {
@@ -336,7 +563,7 @@ pub fn Download(url: String, format: String) -> Element {
                                 class: if format_type() == FormatType::Video { "bg-accent-teal bg-opacity-20 text-accent-teal border border-accent-teal text-sm rounded-lg px-4 py-2.5 focus:outline-none" } else { "bg-background-medium hover:bg-background-hover text-text-primary border border-border text-sm rounded-lg px-4 py-2.5 focus:outline-none" },
                                 onclick: move |_| handle_format_change(FormatType::Video),
                                 disabled: loading(),
-                                "🎬 Video (MP4)"
+                                "🎬 Video"
                             }
                         }
                     }
@@ -344,30 +571,108 @@ pub fn Download(url: String, format: String) -> Element {
                     // Quality selection
                     div { class: "mb-6",
                         label { class: "block mb-2 text-sm font-medium text-text-primary",
-                            if format_type() == FormatType::Audio {
-                                "Audio Quality"
+                            if matches!(format_type(), FormatType::Audio(_)) {
+                                "Audio Format"
                             } else {
                                 "Video Quality"
                             }
                         }
-                        div { class: "grid grid-cols-3 gap-2",
-                            button {
-                                class: if quality() == Quality::Highest { "bg-accent-teal bg-opacity-20 text-accent-teal border border-accent-teal text-sm rounded-lg px-4 py-2.5 focus:outline-none" } else { "bg-background-medium hover:bg-background-hover text-text-primary border border-border text-sm rounded-lg px-4 py-2.5 focus:outline-none" },
-                                onclick: move |_| quality.set(Quality::Highest),
-                                disabled: loading(),
-                                "High"
+                        if format_type() == FormatType::Video {
+                            div { class: "grid grid-cols-3 gap-2",
+                                // Resolution cap
+                                select {
+                                    class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg px-4 py-2.5 focus:outline-none",
+                                    disabled: loading(),
+                                    onchange: move |e| {
+                                        quality
+                                            .set(
+                                                match e.value().as_str() {
+                                                    "lowest" => Quality::Lowest,
+                                                    "highest" => Quality::Highest,
+                                                    height => height.parse().map(Quality::UpTo).unwrap_or(Quality::Highest),
+                                                },
+                                            )
+                                    },
+                                    option { value: "highest", selected: quality() == Quality::Highest, "Best available" }
+                                    for height in VideoQuality::HEIGHT_PRESETS.into_iter().rev() {
+                                        option {
+                                            value: "{height}",
+                                            selected: quality() == Quality::UpTo(height) || (height == 720 && quality() == Quality::Medium),
+                                            "Up to {height}p"
+                                        }
+                                    }
+                                    option { value: "lowest", selected: quality() == Quality::Lowest, "Lowest" }
+                                }
+                                // Codec preference
+                                select {
+                                    class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg px-4 py-2.5 focus:outline-none",
+                                    disabled: loading(),
+                                    onchange: move |e| video_codec.set(VideoCodec::from_name(&e.value())),
+                                    option { value: "", selected: video_codec().is_none(), "Any codec" }
+                                    for codec in VideoCodec::ALL {
+                                        option {
+                                            value: codec.as_str(),
+                                            selected: video_codec() == Some(codec),
+                                            "Prefer {codec.label()}"
+                                        }
+                                    }
+                                }
+                                // Output container
+                                select {
+                                    class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg px-4 py-2.5 focus:outline-none",
+                                    disabled: loading(),
+                                    onchange: move |e| container.set(Container::from_name(&e.value())),
+                                    for option_container in Container::ALL {
+                                        option {
+                                            value: option_container.as_str(),
+                                            selected: container().unwrap_or(Container::Mp4) == option_container,
+                                            {option_container.as_str().to_uppercase()}
+                                        }
+                                    }
+                                }
                             }
-                            button {
-                                class: if quality() == Quality::Medium { "bg-accent-teal bg-opacity-20 text-accent-teal border border-accent-teal text-sm rounded-lg px-4 py-2.5 focus:outline-none" } else { "bg-background-medium hover:bg-background-hover text-text-primary border border-border text-sm rounded-lg px-4 py-2.5 focus:outline-none" },
-                                onclick: move |_| quality.set(Quality::Medium),
-                                disabled: loading(),
-                                "Medium"
+                        } else if let FormatType::Audio(audio_format) = format_type() {
+                            div { class: "grid grid-cols-2 gap-2",
+                                // Output format
+                                select {
+                                    class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg px-4 py-2.5 focus:outline-none",
+                                    disabled: loading(),
+                                    onchange: move |e| {
+                                        if let Some(format) = AudioFormat::from_name(&e.value()) {
+                                            handle_format_change(FormatType::Audio(format));
+                                        }
+                                    },
+                                    for option_format in AudioFormat::ALL {
+                                        option {
+                                            value: option_format.as_str(),
+                                            selected: audio_format == option_format,
+                                            if option_format == AudioFormat::Original {
+                                                "Original stream (no re-encoding)"
+                                            } else {
+                                                "{option_format.label()}"
+                                            }
+                                        }
+                                    }
+                                }
+                                // Target bitrate, lossy formats only
+                                select {
+                                    class: "bg-background-medium border border-border text-text-primary text-sm rounded-lg px-4 py-2.5 focus:outline-none disabled:opacity-50",
+                                    disabled: loading() || !audio_format.is_lossy(),
+                                    onchange: move |e| audio_bitrate.set(e.value().parse().ok()),
+                                    option { value: "", selected: audio_bitrate().is_none(), "Best quality (VBR)" }
+                                    for kbps in AudioFormat::BITRATE_PRESETS.into_iter().rev() {
+                                        option { value: "{kbps}", selected: audio_bitrate() == Some(kbps), "{kbps} kbps" }
+                                    }
+                                }
                             }
-                            button {
-                                class: if quality() == Quality::Lowest { "bg-accent-teal bg-opacity-20 text-accent-teal border border-accent-teal text-sm rounded-lg px-4 py-2.5 focus:outline-none" } else { "bg-background-medium hover:bg-background-hover text-text-primary border border-border text-sm rounded-lg px-4 py-2.5 focus:outline-none" },
-                                onclick: move |_| quality.set(Quality::Lowest),
-                                disabled: loading(),
-                                "Low"
+                            label { class: "flex items-center gap-2 mt-3 text-sm text-text-secondary",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: split_chapters(),
+                                    disabled: loading(),
+                                    onchange: move |e| split_chapters.set(e.checked()),
+                                }
+                                "Split into one file per chapter"
                             }
                         }
                     }
@@ -383,7 +688,12 @@ pub fn Download(url: String, format: String) -> Element {
                                 r#type: "text",
                                 placeholder: "Enter video URL (YouTube, Vimeo, etc.)",
                                 value: "{url}",
-                                oninput: move |e| url.set(e.value().clone()),
+                                oninput: move |e| {
+                                    url.set(e.value().clone());
+                                    format_id.set(None);
+                                    subtitle_options.with_mut(|options| options.languages.clear());
+                                    duplicates.set(Vec::new());
+                                },
                                 disabled: loading(),
                             }
                             button {
@@ -391,6 +701,9 @@ pub fn Download(url: String, format: String) -> Element {
                                 r#type: "button",
                                 onclick: move |_| {
                                     url.set("https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string());
+                                    format_id.set(None);
+                                    subtitle_options.with_mut(|options| options.languages.clear());
+                                    duplicates.set(Vec::new());
                                 },
                                 disabled: loading(),
                                 "Paste"
@@ -398,6 +711,78 @@ pub fn Download(url: String, format: String) -> Element {
                         }
                     }
 
+                    // Exact format picker, overriding the quality
+                    FormatPicker {
+                        key: "{url}",
+                        url: url(),
+                        format_type: format_type(),
+                        selection: format_id,
+                        disabled: loading(),
+                    }
+
+                    // Subtitle languages to fetch with the download
+                    SubtitlePicker {
+                        key: "{url}",
+                        url: url(),
+                        format_type: format_type(),
+                        options: subtitle_options,
+                        disabled: loading(),
+                    }
+
+                    // Start and end times to download only part of the video
+                    ClipPicker { key: "{url}", url: url(), clips, disabled: loading() }
+
+                    // Recording options, used when the link is a live stream
+                    div { class: "mb-6",
+                        label { class: "block mb-2 text-sm font-medium text-text-primary",
+                            "Live streams"
+                        }
+                        label { class: "flex items-center gap-2 text-sm text-text-secondary",
+                            input {
+                                r#type: "checkbox",
+                                checked: live_options().from_start,
+                                disabled: loading(),
+                                onchange: move |e| live_options.with_mut(|options| options.from_start = e.checked()),
+                            }
+                            "Record from the start of the stream"
+                        }
+                        div { class: "flex items-center gap-2 mt-2 text-sm text-text-secondary",
+                            "Stop after"
+                            input {
+                                class: "w-24 bg-background-medium border border-border text-text-primary text-sm rounded-lg p-2",
+                                r#type: "number",
+                                min: "1",
+                                placeholder: "No limit",
+                                value: live_options().max_duration_secs.map(|secs| (secs / 60).to_string()).unwrap_or_default(),
+                                disabled: loading(),
+                                oninput: move |e| {
+                                    let minutes = e.value().trim().parse::<u64>().ok().filter(|minutes| *minutes > 0);
+                                    live_options.with_mut(|options| options.max_duration_secs = minutes.map(|minutes| minutes * 60));
+                                },
+                            }
+                            "minutes"
+                        }
+                    }
+
+                    // Speed cap for this download
+                    div { class: "mb-6",
+                        label { class: "block mb-2 text-sm font-medium text-text-primary",
+                            "Speed limit (optional)"
+                        }
+                        div { class: "flex items-center gap-2 text-sm text-text-secondary",
+                            input {
+                                class: "w-32 bg-background-medium border border-border text-text-primary text-sm rounded-lg p-2",
+                                r#type: "number",
+                                min: "1",
+                                placeholder: "From settings",
+                                value: rate_limit().map(|rate| rate.to_string()).unwrap_or_default(),
+                                disabled: loading(),
+                                oninput: move |e| rate_limit.set(e.value().trim().parse().ok().filter(|rate| *rate > 0)),
+                            }
+                            "KB/s"
+                        }
+                    }
+
                     // Filename input
                     div { class: "mb-6",
                         label { class: "block mb-2 text-sm font-medium text-text-primary",
@@ -415,6 +800,10 @@ pub fn Download(url: String, format: String) -> Element {
 
                     // Progress bar
                     {progress_component}
+                    {cancel_button}
+
+                    // Earlier downloads of the same video
+                    {duplicate_warning}
 
                     // Error messages
                     {error_message}
@@ -429,7 +818,7 @@ pub fn Download(url: String, format: String) -> Element {
                     if !download_ready() {
                         button {
                             class: get_button_class(),
-                            disabled: loading() || url().is_empty() || filename().is_empty(),
+                            disabled: loading() || url().is_empty() || filename().is_empty() || !duplicates().is_empty(),
                             onclick: handle_download,
                             {render_button_content()}
                         }
@@ -1,17 +1,25 @@
 use crate::common::Toaster;
 use crate::components::download_progress::{DownloadInfo, DownloadStatus};
+use crate::server::download::handlers::{for_each_job_event, queue_events, retag_download};
+use crate::server::download::types::{
+    format_timestamp, Chapter, ClipRange, JobProgress, JobState, SubtitleFile,
+};
+use crate::server::download::utils::subtitle_url;
 use dioxus::prelude::Signal;
 use dioxus::prelude::*;
 use dioxus_free_icons::icons::fa_solid_icons::{FaPause, FaPlay};
 use dioxus_free_icons::{
     icons::{
         bs_icons::{BsExclamationTriangleFill, BsSearch},
-        fa_solid_icons::{FaCalendar, FaDatabase, FaDownload, FaMusic, FaVideo},
+        fa_solid_icons::{
+            FaCalendar, FaClock, FaClosedCaptioning, FaDatabase, FaDownload, FaListOl, FaMusic,
+            FaVideo,
+        },
         hi_outline_icons::{HiFilm, HiMusicNote, HiViewGrid},
     },
     Icon,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // Platform-agnostic download item model for UI
 #[derive(Clone, Debug, PartialEq)]
@@ -27,6 +35,12 @@ pub struct DownloadItem {
     pub date_downloaded: String,
     pub thumbnail_url: Option<String>,
     pub file_exists: bool,
+    pub playlist_title: Option<String>,
+    pub playlist_index: Option<i64>,
+    pub chapters: Vec<Chapter>,
+    pub subtitles: Vec<SubtitleFile>,
+    /// Parts of the video the file holds, empty for the whole video
+    pub clips: Vec<ClipRange>,
 }
 
 impl DownloadItem {
@@ -313,6 +327,9 @@ fn DownloadsContent() -> Element {
     {
         if !has_downloads {
             return rsx! {
+                // Jobs still downloading
+                ActiveJobs { downloads: downloads.clone() }
+
                 // Show an informative message for non-web platforms
                 div { class: "text-center py-16 bg-background-card rounded-xl border border-border shadow-md",
                     div { class: "flex justify-center mb-6",
@@ -332,6 +349,9 @@ fn DownloadsContent() -> Element {
         }
 
         return rsx! {
+            // Jobs still downloading
+            ActiveJobs { downloads: downloads.clone() }
+
             // Show downloads with tabs
             DownloadsGrid {
                 downloads: downloads.clone(),
@@ -342,6 +362,71 @@ fn DownloadsContent() -> Element {
     }
 }
 
+// Live progress of queued downloads, pushed by the server
+#[component]
+fn ActiveJobs(downloads: Signal<Vec<DownloadItem>>) -> Element {
+    let mut jobs = use_signal(|| BTreeMap::<i64, JobProgress>::new());
+
+    use_future(move || async move {
+        match queue_events().await {
+            Ok(stream) => {
+                for_each_job_event(stream, |event| {
+                    if !event.state.is_finished() {
+                        jobs.write().insert(event.job_id, event);
+                        return;
+                    }
+
+                    jobs.write().remove(&event.job_id);
+
+                    // Show the finished file in the library right away
+                    if event.state == JobState::Completed {
+                        let mut downloads = downloads.clone();
+                        spawn(async move {
+                            downloads.set(data_access::fetch_downloads().await);
+                        });
+                    }
+                })
+                .await;
+            }
+            Err(e) => tracing::warn!("Failed to follow queue progress: {}", e),
+        }
+    });
+
+    if jobs().is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "mb-6 space-y-3",
+            h2 { class: "text-lg font-semibold text-text-primary", "Downloading now" }
+            for (job_id , job) in jobs() {
+                div {
+                    key: "{job_id}",
+                    class: "bg-background-card rounded-lg border border-border p-4",
+                    div { class: "flex justify-between mb-2 text-sm",
+                        span { class: "text-text-primary truncate", "{job.progress.status}" }
+                        span { class: "text-text-secondary shrink-0 ml-4",
+                            // Recordings show how long they've been going instead
+                            {
+                                match job.progress.recording_secs {
+                                    Some(secs) => format!("● {}", format_timestamp(secs as f64)),
+                                    None => format!("{:.0}%", job.progress.percent),
+                                }
+                            }
+                        }
+                    }
+                    div { class: "w-full bg-background-dark rounded-full h-2",
+                        div {
+                            class: "bg-accent-teal h-2 rounded-full transition-all duration-300",
+                            style: format!("width: {:.0}%", job.progress.percent.clamp(0.0, 100.0)),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 // Downloads grid component - separated for reuse
 #[component]
 fn DownloadsGrid(
@@ -372,6 +457,9 @@ fn DownloadsGrid(
                 .filter(|d| {
                     d.title.to_lowercase().contains(&query)
                         || d.filename.to_lowercase().contains(&query)
+                        || d.playlist_title
+                            .as_ref()
+                            .is_some_and(|title| title.to_lowercase().contains(&query))
                 })
                 .collect::<Vec<DownloadItem>>()
         }
@@ -545,6 +633,9 @@ fn DownloadCard(download: DownloadItem) -> Element {
     let is_video = &download.format_type == "video";
     let is_audio = &download.format_type == "audio";
     let mut play_video = use_signal(|| false);
+    // Where the player starts, moved by picking a chapter
+    let mut start_at = use_signal(|| 0.0_f64);
+    let mut retagging = use_signal(|| false);
     tracing::info!("play_video: {}", download.file_path);
     let file_path = use_hook(|| download.file_path.clone());
 
@@ -556,7 +647,7 @@ fn DownloadCard(download: DownloadItem) -> Element {
                     if play_video() {
                         video {
                             class: "w-full h-full object-cover",
-                            src: "{file_path}",
+                            src: "{file_path}#t={start_at}",
                             alt: "Thumbnail",
                             controls: true,
                             autoplay: true,
@@ -629,7 +720,7 @@ fn DownloadCard(download: DownloadItem) -> Element {
                         }
                     }
                     if is_audio {
-                        "MP3"
+                        "Audio"
                     } else {
                         "Video"
                     }
@@ -655,6 +746,96 @@ fn DownloadCard(download: DownloadItem) -> Element {
                     "{download.title}"
                 }
 
+                // Playlist the video was downloaded from
+                if let Some(ref playlist_title) = download.playlist_title {
+                    div { class: "flex items-center text-xs text-accent-teal mb-2",
+                        Icon {
+                            icon: FaListOl,
+                            width: 10,
+                            height: 10,
+                            class: "mr-1.5",
+                        }
+                        span { class: "truncate",
+                            if let Some(index) = download.playlist_index {
+                                "{playlist_title} · #{index}"
+                            } else {
+                                "{playlist_title}"
+                            }
+                        }
+                    }
+                }
+
+                // Parts of the video a clip holds
+                if !download.clips.is_empty() {
+                    div { class: "flex items-center text-xs text-text-muted mb-2",
+                        Icon {
+                            icon: FaClock,
+                            width: 10,
+                            height: 10,
+                            class: "mr-1.5",
+                        }
+                        span { class: "truncate",
+                            {
+                                download
+                                    .clips
+                                    .iter()
+                                    .map(ClipRange::describe)
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            }
+                        }
+                    }
+                }
+
+                // Subtitles saved with the download
+                if !download.subtitles.is_empty() {
+                    div { class: "flex flex-wrap items-center gap-1.5 mb-2",
+                        Icon {
+                            icon: FaClosedCaptioning,
+                            width: 12,
+                            height: 12,
+                            class: "mr-0.5 text-text-muted",
+                        }
+                        for subtitle in download.subtitles.iter().cloned() {
+                            SubtitleBadge { key: "{subtitle.id}", subtitle }
+                        }
+                    }
+                }
+
+                // Chapters; picking one starts the player there
+                if !download.chapters.is_empty() {
+                    details { class: "mb-3 text-sm",
+                        summary { class: "cursor-pointer text-text-muted hover:text-text-primary",
+                            "{download.chapters.len()} chapters"
+                        }
+                        ul { class: "mt-2 max-h-48 overflow-y-auto space-y-1",
+                            for chapter in download.chapters.iter().cloned() {
+                                li {
+                                    key: "{chapter.start_secs}",
+                                    class: "flex items-center gap-2",
+                                    button {
+                                        class: "flex flex-1 items-center gap-2 min-w-0 text-left hover:text-accent-teal",
+                                        onclick: move |_| {
+                                            start_at.set(chapter.start_secs);
+                                            play_video.set(true);
+                                        },
+                                        span { class: "font-mono text-xs text-text-muted", "{chapter.format_start()}" }
+                                        span { class: "truncate text-text-secondary", "{chapter.title}" }
+                                    }
+                                    if let Some(path) = chapter.file_path.clone() {
+                                        button {
+                                            class: "text-xs text-text-muted hover:text-text-primary",
+                                            title: "Open the chapter's file",
+                                            onclick: move |_| data_access::open_file(&path),
+                                            "Open"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
                 // Info row
                 div { class: "flex justify-between text-sm text-text-muted mb-4",
                     div { class: "flex items-center",
@@ -699,6 +880,25 @@ fn DownloadCard(download: DownloadItem) -> Element {
                             },
                             "Open Folder"
                         }
+
+                        // Write the stored metadata and cover art into the file again
+                        if let Some(id) = download.id {
+                            button {
+                                class: "bg-background-medium hover:bg-background-hover text-text-primary py-2 px-3 rounded-lg text-sm transition-colors duration-200 flex items-center justify-center shadow-sm disabled:opacity-50",
+                                title: "Write title, channel and cover art into the file",
+                                disabled: retagging(),
+                                onclick: move |_| {
+                                    retagging.set(true);
+                                    spawn(async move {
+                                        if let Err(e) = retag_download(id).await {
+                                            tracing::error!("Failed to retag download {}: {}", id, e);
+                                        }
+                                        retagging.set(false);
+                                    });
+                                },
+                                if retagging() { "Tagging..." } else { "Retag" }
+                            }
+                        }
                     } else {
                         div { class: "flex-1 bg-accent-rose bg-opacity-20 text-accent-rose py-2 px-3 rounded-lg text-sm text-center flex items-center justify-center",
                             Icon {
@@ -715,3 +915,31 @@ fn DownloadCard(download: DownloadItem) -> Element {
         }
     }
 }
+
+// Subtitle language badge; sidecar files can be downloaded from it
+#[component]
+fn SubtitleBadge(subtitle: SubtitleFile) -> Element {
+    let label = if subtitle.auto_generated {
+        format!("{} (auto)", subtitle.language)
+    } else {
+        subtitle.language.clone()
+    };
+
+    rsx! {
+        if subtitle.embedded {
+            span {
+                class: "px-2 py-0.5 rounded-full text-xs bg-background-hover text-text-secondary",
+                title: "Embedded in the video",
+                "{label}"
+            }
+        } else {
+            a {
+                class: "px-2 py-0.5 rounded-full text-xs bg-accent-teal bg-opacity-20 text-accent-teal hover:bg-opacity-30",
+                href: subtitle_url(subtitle.id),
+                download: "",
+                title: "Download {subtitle.format} subtitles",
+                "{label} · {subtitle.format}"
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::server::download::error::DownloaderError;
 use crate::server::download::handlers::info::get_video_info;
 use dioxus::prelude::*;
 
@@ -13,7 +14,7 @@ pub fn GetInfo() -> Element {
             // Call the server function
             match get_video_info("wallnut".to_string()).await {
                 Ok(response) => result.set(format!("Success: {}", response)),
-                Err(e) => result.set(format!("Error: {:?}", e)),
+                Err(e) => result.set(format!("Error: {}", DownloaderError::from(e).message())),
             }
         }
     };
@@ -73,7 +73,7 @@ pub fn Home() -> Element {
                                 "Multiple Formats"
                             }
                             p { class: "text-text-secondary",
-                                "Download videos in MP4 format or extract audio in MP3 format with various quality options."
+                                "Download videos in MP4 format or extract audio as MP3, Opus, M4A, FLAC or WAV with various quality options."
                             }
                         }
 
@@ -137,7 +137,7 @@ pub fn Home() -> Element {
                                 "Choose Format & Quality"
                             }
                             p { class: "text-text-secondary",
-                                "Select whether you want to download the video (MP4) or just the audio (MP3) and choose your preferred quality."
+                                "Select whether you want to download the video (MP4) or just the audio (MP3, Opus, M4A, FLAC or WAV) and choose your preferred quality."
                             }
                         }
                     }
@@ -9,3 +9,15 @@ pub use home::Home;
 
 pub mod search;
 pub use search::Search;
+
+pub mod queue;
+pub use queue::Queue;
+
+pub mod playlist;
+pub use playlist::Playlist;
+
+pub mod subscriptions;
+pub use subscriptions::Subscriptions;
+
+pub mod settings;
+pub use settings::Settings;
@@ -0,0 +1,252 @@
+use crate::common::Toaster;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::playlist::{enqueue_playlist, resolve_playlist};
+use crate::server::download::types::{AudioFormat, PlaylistEntry, PlaylistInfo};
+use crate::views::download::{FormatType, Quality};
+use dioxus::prelude::*;
+use dioxus_free_icons::{
+    icons::fa_solid_icons::{FaDownload, FaListUl, FaMusic, FaVideo},
+    Icon,
+};
+use std::collections::HashSet;
+
+#[component]
+pub fn Playlist() -> Element {
+    let mut url_input = use_signal(|| String::new());
+    let mut playlist = use_signal(|| None::<PlaylistInfo>);
+    let mut selected = use_signal(|| HashSet::<u32>::new());
+    let mut selected_format = use_signal(|| FormatType::Video);
+    let mut selected_quality = use_signal(|| Quality::Highest);
+    let mut resolving = use_signal(|| false);
+    let mut submitting = use_signal(|| false);
+    let mut toaster = use_signal(|| None::<Toaster>);
+
+    let load_playlist = move |_| {
+        let url = url_input().trim().to_string();
+        if url.is_empty() {
+            toaster.set(Some(Toaster::Warning(
+                "Enter a YouTube playlist URL".to_string(),
+            )));
+            return;
+        }
+
+        resolving.set(true);
+        toaster.set(None);
+        spawn(async move {
+            match resolve_playlist(url).await {
+                Ok(info) => {
+                    // Everything is selected until the user says otherwise
+                    selected.set(info.entries.iter().map(|entry| entry.index).collect());
+                    playlist.set(Some(info));
+                }
+                Err(e) => {
+                    playlist.set(None);
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to load playlist: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            resolving.set(false);
+        });
+    };
+
+    let queue_selected = move |_| {
+        let Some(info) = playlist() else {
+            return;
+        };
+        let mut indices: Vec<u32> = selected().into_iter().collect();
+        indices.sort_unstable();
+
+        submitting.set(true);
+        spawn(async move {
+            match enqueue_playlist(
+                info,
+                indices,
+                selected_format().to_string(),
+                selected_quality().to_string(),
+            )
+            .await
+            {
+                Ok(job_ids) => {
+                    toaster.set(Some(Toaster::Success(format!(
+                        "Queued {} downloads. Follow them on the Queue page.",
+                        job_ids.len()
+                    ))));
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to queue: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            submitting.set(false);
+        });
+    };
+
+    let toast = toaster().map(|toast| match toast {
+        Toaster::Success(msg) => ("bg-green-900/40 text-green-300", msg),
+        Toaster::Error(msg) => ("bg-red-900/40 text-red-300", msg),
+        Toaster::Warning(msg) => ("bg-yellow-900/40 text-yellow-300", msg),
+        Toaster::Info(msg) => ("bg-background-hover text-text-secondary", msg),
+    });
+
+    let selected_count = selected().len();
+
+    rsx! {
+        div { class: "container mx-auto py-6 px-4",
+            h1 { class: "text-3xl font-bold mb-4 text-text-primary", "Playlist" }
+            p { class: "mb-6 text-text-secondary",
+                "Load a playlist, pick the videos you want and queue them in one go."
+            }
+
+            // Toast notification
+            if let Some((toast_class, message)) = toast {
+                div {
+                    class: "mb-4 p-3 rounded-lg text-sm cursor-pointer {toast_class}",
+                    onclick: move |_| toaster.set(None),
+                    "{message}"
+                }
+            }
+
+            // Playlist URL form
+            div { class: "bg-background-card rounded-xl border border-border shadow-md p-5 mb-6",
+                div { class: "flex gap-4",
+                    input {
+                        class: "bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                        r#type: "text",
+                        placeholder: "https://www.youtube.com/playlist?list=...",
+                        value: "{url_input}",
+                        oninput: move |e| url_input.set(e.value().clone()),
+                    }
+                    button {
+                        class: "flex shrink-0 items-center px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                        disabled: resolving(),
+                        onclick: load_playlist,
+                        Icon { icon: FaListUl, width: 14, height: 14, class: "mr-2" }
+                        if resolving() { "Loading..." } else { "Load playlist" }
+                    }
+                }
+            }
+
+            if let Some(info) = playlist() {
+                // Playlist header and download options
+                div { class: "flex flex-wrap items-center justify-between gap-4 mb-4",
+                    div { class: "min-w-0",
+                        h2 { class: "text-xl font-semibold text-text-primary truncate", "{info.title}" }
+                        p { class: "text-sm text-text-muted",
+                            if let Some(uploader) = info.uploader.clone() {
+                                "{uploader} · "
+                            }
+                            "{info.entries.len()} videos · {selected_count} selected"
+                        }
+                    }
+                    div { class: "flex flex-wrap items-center gap-4",
+                        button {
+                            class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                            onclick: move |_| {
+                                let all = playlist
+                                    .peek()
+                                    .as_ref()
+                                    .map(|info| info.entries.iter().map(|entry| entry.index).collect())
+                                    .unwrap_or_default();
+                                selected.set(all);
+                            },
+                            "Select all"
+                        }
+                        button {
+                            class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                            onclick: move |_| selected.set(HashSet::new()),
+                            "Select none"
+                        }
+
+                        // Format selection
+                        div { class: "flex rounded-lg overflow-hidden border border-border",
+                            button {
+                                class: "flex items-center px-4 py-2 text-sm",
+                                class: if selected_format() == FormatType::Video { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                                onclick: move |_| selected_format.set(FormatType::Video),
+                                Icon { icon: FaVideo, width: 14, height: 14, class: "mr-2" }
+                                "Video"
+                            }
+                            button {
+                                class: "flex items-center px-4 py-2 text-sm",
+                                class: if matches!(selected_format(), FormatType::Audio(_)) { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                                onclick: move |_| selected_format.set(FormatType::Audio(AudioFormat::default())),
+                                Icon { icon: FaMusic, width: 14, height: 14, class: "mr-2" }
+                                "Audio"
+                            }
+                        }
+
+                        // Quality selection
+                        select {
+                            class: "bg-background border border-border text-text-primary text-sm rounded-lg p-2",
+                            onchange: move |e| {
+                                selected_quality
+                                    .set(
+                                        match e.value().as_str() {
+                                            "medium" => Quality::Medium,
+                                            "lowest" => Quality::Lowest,
+                                            _ => Quality::Highest,
+                                        },
+                                    )
+                            },
+                            option { value: "highest", selected: selected_quality() == Quality::Highest, "Highest quality" }
+                            option { value: "medium", selected: selected_quality() == Quality::Medium, "Medium quality" }
+                            option { value: "lowest", selected: selected_quality() == Quality::Lowest, "Lowest quality" }
+                        }
+
+                        button {
+                            class: "flex items-center px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                            disabled: submitting() || selected_count == 0,
+                            onclick: queue_selected,
+                            Icon { icon: FaDownload, width: 14, height: 14, class: "mr-2" }
+                            if submitting() { "Adding..." } else { "Queue {selected_count} selected" }
+                        }
+                    }
+                }
+
+                // Entry list
+                div { class: "space-y-2",
+                    for entry in info.entries.clone() {
+                        PlaylistRow {
+                            key: "{entry.index}",
+                            checked: selected().contains(&entry.index),
+                            on_toggle: move |index: u32| {
+                                let mut selection = selected.write();
+                                if !selection.remove(&index) {
+                                    selection.insert(index);
+                                }
+                            },
+                            entry: entry.clone(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PlaylistRow(entry: PlaylistEntry, checked: bool, on_toggle: EventHandler<u32>) -> Element {
+    let title = entry
+        .title
+        .clone()
+        .unwrap_or_else(|| entry.video_id.clone());
+    let index = entry.index;
+
+    rsx! {
+        label { class: "flex items-center gap-4 bg-background-card rounded-lg border border-border p-3 cursor-pointer hover:border-border-light",
+            input {
+                r#type: "checkbox",
+                class: "w-4 h-4 accent-teal-500",
+                checked,
+                onchange: move |_| on_toggle.call(index),
+            }
+            span { class: "w-8 text-right text-sm text-text-muted", "{index}" }
+            span { class: "flex-1 min-w-0 truncate text-text-primary", "{title}" }
+            span { class: "text-sm text-text-muted", "{entry.format_duration()}" }
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use crate::common::Toaster;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::queue::{
+    cancel_download, clear_finished_jobs, enqueue_downloads, get_queue_concurrency, list_queue,
+    pause_download, resume_download, set_queue_concurrency,
+};
+use crate::server::download::handlers::{for_each_job_event, queue_events};
+use crate::server::download::types::{AudioFormat, JobState, QueueEntry};
+use crate::views::download::{FormatType, Quality};
+use dioxus::prelude::*;
+use dioxus_free_icons::{
+    icons::fa_solid_icons::{FaDownload, FaMusic, FaVideo},
+    Icon,
+};
+use std::collections::HashMap;
+
+#[component]
+pub fn Queue() -> Element {
+    let mut urls_input = use_signal(|| String::new());
+    let mut selected_format = use_signal(|| FormatType::Video);
+    let mut selected_quality = use_signal(|| Quality::Highest);
+    let mut entries = use_signal(|| Vec::<QueueEntry>::new());
+    let mut percents = use_signal(|| HashMap::<i64, f64>::new());
+    let mut concurrency = use_signal(|| 0usize);
+    let mut submitting = use_signal(|| false);
+    let mut toaster = use_signal(|| None::<Toaster>);
+
+    // Load the worker count once
+    use_future(move || async move {
+        if let Ok(limit) = get_queue_concurrency().await {
+            concurrency.set(limit);
+        }
+    });
+
+    // Load the queue, then follow the progress events the server pushes
+    use_future(move || async move {
+        match list_queue().await {
+            Ok(jobs) => entries.set(jobs),
+            Err(e) => tracing::error!("Failed to load queue: {}", e),
+        }
+
+        match queue_events().await {
+            Ok(stream) => {
+                for_each_job_event(stream, |event| {
+                    // Paused jobs keep showing how far they got
+                    if matches!(event.state, JobState::Running | JobState::Paused) {
+                        percents
+                            .write()
+                            .insert(event.job_id, event.progress.percent);
+                    } else {
+                        percents.write().remove(&event.job_id);
+                    }
+
+                    // Reload the list when a job appears or changes state
+                    let known_state = entries
+                        .peek()
+                        .iter()
+                        .find(|entry| entry.id == event.job_id)
+                        .map(|entry| entry.state);
+                    if known_state != Some(event.state) {
+                        spawn(async move {
+                            if let Ok(jobs) = list_queue().await {
+                                entries.set(jobs);
+                            }
+                        });
+                    }
+                })
+                .await;
+            }
+            Err(e) => tracing::error!("Failed to follow queue progress: {}", e),
+        }
+    });
+
+    let add_to_queue = move |_| {
+        let urls: Vec<String> = urls_input()
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        if urls.is_empty() {
+            toaster.set(Some(Toaster::Warning(
+                "Enter at least one video URL".to_string(),
+            )));
+            return;
+        }
+
+        submitting.set(true);
+        spawn(async move {
+            let requested = urls.len();
+            match enqueue_downloads(
+                urls,
+                selected_format().to_string(),
+                selected_quality().to_string(),
+            )
+            .await
+            {
+                Ok(job_ids) => {
+                    let skipped = requested - job_ids.len();
+                    if skipped > 0 {
+                        toaster.set(Some(Toaster::Warning(format!(
+                            "Queued {} downloads, skipped {} invalid or blocked URLs",
+                            job_ids.len(),
+                            skipped
+                        ))));
+                    } else {
+                        toaster.set(Some(Toaster::Success(format!(
+                            "Queued {} downloads",
+                            job_ids.len()
+                        ))));
+                    }
+                    urls_input.set(String::new());
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to queue: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+
+            if let Ok(jobs) = list_queue().await {
+                entries.set(jobs);
+            }
+            submitting.set(false);
+        });
+    };
+
+    let clear_finished = move |_| {
+        spawn(async move {
+            match clear_finished_jobs().await {
+                Ok(removed) => {
+                    toaster.set(Some(Toaster::Info(format!(
+                        "Removed {} finished jobs",
+                        removed
+                    ))));
+                    if let Ok(jobs) = list_queue().await {
+                        entries.set(jobs);
+                    }
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to clear queue: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+        });
+    };
+
+    let active_count = entries()
+        .iter()
+        .filter(|entry| !entry.state.is_finished())
+        .count();
+
+    let toast = toaster().map(|toast| match toast {
+        Toaster::Success(msg) => ("bg-green-900/40 text-green-300", msg),
+        Toaster::Error(msg) => ("bg-red-900/40 text-red-300", msg),
+        Toaster::Warning(msg) => ("bg-yellow-900/40 text-yellow-300", msg),
+        Toaster::Info(msg) => ("bg-background-hover text-text-secondary", msg),
+    });
+
+    rsx! {
+        div { class: "container mx-auto py-6 px-4",
+            h1 { class: "text-3xl font-bold mb-4 text-text-primary", "Download Queue" }
+            p { class: "mb-6 text-text-secondary",
+                "Queue several downloads at once. Jobs keep running on the server and survive restarts."
+            }
+
+            // Toast notification
+            if let Some((toast_class, message)) = toast {
+                div {
+                    class: "mb-4 p-3 rounded-lg text-sm cursor-pointer {toast_class}",
+                    onclick: move |_| toaster.set(None),
+                    "{message}"
+                }
+            }
+
+            // New jobs form
+            div { class: "bg-background-card rounded-xl border border-border shadow-md p-5 mb-6",
+                textarea {
+                    class: "bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5 mb-4",
+                    rows: "4",
+                    placeholder: "Paste video URLs, one per line",
+                    value: "{urls_input}",
+                    oninput: move |e| urls_input.set(e.value().clone()),
+                }
+
+                div { class: "flex flex-wrap items-center gap-4",
+                    // Format selection
+                    div { class: "flex rounded-lg overflow-hidden border border-border",
+                        button {
+                            class: "flex items-center px-4 py-2 text-sm",
+                            class: if selected_format() == FormatType::Video { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                            onclick: move |_| selected_format.set(FormatType::Video),
+                            Icon { icon: FaVideo, width: 14, height: 14, class: "mr-2" }
+                            "Video"
+                        }
+                        button {
+                            class: "flex items-center px-4 py-2 text-sm",
+                            class: if matches!(selected_format(), FormatType::Audio(_)) { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                            onclick: move |_| selected_format.set(FormatType::Audio(AudioFormat::default())),
+                            Icon { icon: FaMusic, width: 14, height: 14, class: "mr-2" }
+                            "Audio"
+                        }
+                    }
+
+                    // Quality selection
+                    select {
+                        class: "bg-background border border-border text-text-primary text-sm rounded-lg p-2",
+                        onchange: move |e| {
+                            selected_quality
+                                .set(
+                                    match e.value().as_str() {
+                                        "medium" => Quality::Medium,
+                                        "lowest" => Quality::Lowest,
+                                        _ => Quality::Highest,
+                                    },
+                                )
+                        },
+                        option { value: "highest", selected: selected_quality() == Quality::Highest, "Highest quality" }
+                        option { value: "medium", selected: selected_quality() == Quality::Medium, "Medium quality" }
+                        option { value: "lowest", selected: selected_quality() == Quality::Lowest, "Lowest quality" }
+                    }
+
+                    button {
+                        class: "flex items-center ml-auto px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                        disabled: submitting(),
+                        onclick: add_to_queue,
+                        Icon { icon: FaDownload, width: 14, height: 14, class: "mr-2" }
+                        if submitting() { "Adding..." } else { "Add to queue" }
+                    }
+                }
+            }
+
+            // Worker pool settings
+            div { class: "flex flex-wrap items-center justify-between gap-4 mb-4",
+                div { class: "flex items-center gap-2 text-sm text-text-secondary",
+                    span { "Parallel downloads:" }
+                    for limit in 1..=4usize {
+                        button {
+                            key: "{limit}",
+                            class: "w-8 h-8 rounded-lg border border-border",
+                            class: if concurrency() == limit { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                            onclick: move |_| {
+                                spawn(async move {
+                                    match set_queue_concurrency(limit).await {
+                                        Ok(applied) => concurrency.set(applied),
+                                        Err(e) => {
+                                            toaster
+                                                .set(
+                                                    Some(
+                                                        Toaster::Error(
+                                                            format!(
+                                                                "Failed to update workers: {}",
+                                                                DownloaderError::from(e).message(),
+                                                            ),
+                                                        ),
+                                                    ),
+                                                )
+                                        }
+                                    }
+                                });
+                            },
+                            "{limit}"
+                        }
+                    }
+                }
+                div { class: "flex items-center gap-4",
+                    span { class: "text-sm text-text-muted", "{active_count} active" }
+                    button {
+                        class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                        onclick: clear_finished,
+                        "Clear finished"
+                    }
+                }
+            }
+
+            // Job list
+            if entries().is_empty() {
+                div { class: "text-center py-12 bg-background-card rounded-xl border border-border text-text-secondary",
+                    "The queue is empty"
+                }
+            } else {
+                div { class: "space-y-3",
+                    for entry in entries() {
+                        QueueRow {
+                            key: "{entry.id}",
+                            percent: percents().get(&entry.id).copied(),
+                            entry: entry.clone(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn QueueRow(entry: QueueEntry, percent: Option<f64>) -> Element {
+    let badge_class = match entry.state {
+        JobState::Queued => "bg-background-hover text-text-secondary",
+        JobState::Running => "bg-accent-teal/20 text-accent-teal",
+        JobState::Waiting => "bg-accent-teal/10 text-text-secondary",
+        JobState::Paused => "bg-accent-amber/20 text-accent-amber",
+        JobState::Completed => "bg-green-900/40 text-green-300",
+        JobState::Failed => "bg-red-900/40 text-red-300",
+        JobState::Cancelled => "bg-background-hover text-text-muted",
+    };
+    let title = entry.title.clone().unwrap_or_else(|| entry.url.clone());
+    let job_id = entry.id;
+
+    let can_pause = matches!(
+        entry.state,
+        JobState::Queued | JobState::Running | JobState::Waiting
+    );
+    let can_resume = matches!(entry.state, JobState::Paused | JobState::Failed);
+    let resume_label = if entry.state == JobState::Failed {
+        "Retry"
+    } else {
+        "Resume"
+    };
+
+    // The queue events report the new state once the server has applied it
+    let pause = move |_| {
+        spawn(async move {
+            if let Err(e) = pause_download(job_id).await {
+                tracing::error!("Failed to pause job {}: {}", job_id, e);
+            }
+        });
+    };
+    let resume = move |_| {
+        spawn(async move {
+            if let Err(e) = resume_download(job_id).await {
+                tracing::error!("Failed to resume job {}: {}", job_id, e);
+            }
+        });
+    };
+    let cancel = move |_| {
+        spawn(async move {
+            if let Err(e) = cancel_download(job_id).await {
+                tracing::error!("Failed to cancel job {}: {}", job_id, e);
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "bg-background-card rounded-lg border border-border p-4",
+            div { class: "flex items-center justify-between gap-4",
+                div { class: "min-w-0",
+                    p { class: "font-medium text-text-primary truncate", "{title}" }
+                    p { class: "text-xs text-text-muted mt-1",
+                        if let Some(playlist) = entry.playlist.clone() {
+                            "{playlist.playlist_title} #{playlist.index} · "
+                        }
+                        "{entry.format_type} · {entry.quality} · added {entry.created_at}"
+                        if let Some(starts_at) = entry.starts_at.clone() {
+                            " · stream starts {starts_at}"
+                        }
+                    }
+                }
+                div { class: "flex shrink-0 items-center gap-2",
+                    if can_pause {
+                        button {
+                            class: "px-3 py-1 text-xs rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                            onclick: pause,
+                            "Pause"
+                        }
+                    }
+                    if can_resume {
+                        button {
+                            class: "px-3 py-1 text-xs rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                            onclick: resume,
+                            "{resume_label}"
+                        }
+                    }
+                    if !entry.state.is_finished() {
+                        button {
+                            class: "px-3 py-1 text-xs rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                            onclick: cancel,
+                            "Cancel"
+                        }
+                    }
+                    span { class: "px-3 py-1 rounded-full text-xs font-medium {badge_class}",
+                        "{entry.state.label()}"
+                    }
+                }
+            }
+            if let Some(percent) = percent {
+                div { class: "w-full bg-background-dark rounded-full h-1.5 mt-3",
+                    div {
+                        class: "bg-accent-teal h-1.5 rounded-full transition-all duration-300",
+                        style: format!("width: {:.0}%", percent.clamp(0.0, 100.0)),
+                    }
+                }
+            }
+            if let Some(failure) = entry.failure {
+                p { class: "text-xs mt-2",
+                    span { class: "font-medium text-red-300", "{failure.label()}" }
+                    span { class: "text-text-secondary", " — {failure.hint()}" }
+                }
+            }
+            if let Some(error) = entry.error.clone() {
+                p { class: "text-xs text-red-300 mt-2", "{error}" }
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use crate::common::Toaster;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::duplicates::library_video_ids;
+use crate::server::download::types::AudioFormat;
 use crate::server::youtube::{download_youtube_video, search_youtube_videos, VideoSearchResult};
 use crate::Route;
 // Import from the public re-exports instead of private modules
@@ -12,6 +15,7 @@ use dioxus_free_icons::{
     Icon,
 };
 use futures_timer::Delay;
+use std::collections::HashSet;
 use std::time::Duration;
 
 #[component]
@@ -21,6 +25,8 @@ pub fn Search() -> Element {
     let mut search_results = use_signal(|| Vec::<VideoSearchResult>::new());
     let mut toaster = use_signal(|| None::<Toaster>);
     let mut selected_format = use_signal(|| FormatType::Video);
+    // IDs of the results that are already in the downloads library
+    let mut in_library = use_signal(HashSet::<String>::new);
     let navigator = use_navigator();
 
     // States for download tracking
@@ -46,11 +52,24 @@ pub fn Search() -> Element {
                 searching.set(false);
                 let results_clone = results.clone();
                 tracing::info!("results_clone: {:?}", results_clone);
+
+                // Mark what was downloaded before
+                let video_ids = results_clone.iter().map(|video| video.id.clone()).collect();
+                spawn(async move {
+                    match library_video_ids(video_ids).await {
+                        Ok(ids) => in_library.set(ids.into_iter().collect()),
+                        Err(e) => tracing::warn!("Failed to check the library: {}", e),
+                    }
+                });
                 search_results.set(results_clone);
             }
             Some(Err(e)) => {
                 searching.set(false);
-                toaster.set(Some(Toaster::Error(format!("Search error: {}", e))));
+                let error = DownloaderError::from(e.clone());
+                toaster.set(Some(Toaster::Error(format!(
+                    "Search error: {}",
+                    error.message()
+                ))));
             }
             None => {}
         }
@@ -62,7 +81,7 @@ pub fn Search() -> Element {
         let video_url = format!("https://www.youtube.com/watch?v={}", video.id);
 
         // Get the format type
-        let format_string = if selected_format() == FormatType::Audio {
+        let format_string = if matches!(selected_format(), FormatType::Audio(_)) {
             "audio"
         } else {
             "video"
@@ -118,8 +137,8 @@ pub fn Search() -> Element {
                         }
                         button {
                             class: "px-6 py-2 rounded-lg flex items-center",
-                            class: if selected_format() == FormatType::Audio { "bg-accent-amber text-text-primary" } else { "bg-background-medium text-text-secondary hover:bg-background-hover" },
-                            onclick: move |_| selected_format.set(FormatType::Audio),
+                            class: if matches!(selected_format(), FormatType::Audio(_)) { "bg-accent-amber text-text-primary" } else { "bg-background-medium text-text-secondary hover:bg-background-hover" },
+                            onclick: move |_| selected_format.set(FormatType::Audio(AudioFormat::default())),
                             Icon {
                                 icon: FaMusic,
                                 width: 16,
@@ -270,6 +289,12 @@ pub fn Search() -> Element {
                                                 div { class: "absolute bottom-2 right-2 bg-background-darker bg-opacity-75 text-text-primary text-xs px-2 py-1 rounded-full",
                                                     "{video.duration}"
                                                 }
+                                                // Already downloaded badge
+                                                if in_library().contains(&video.id) {
+                                                    div { class: "absolute top-2 left-2 bg-accent-green bg-opacity-90 text-text-primary text-xs px-2 py-1 rounded-full",
+                                                        "In library"
+                                                    }
+                                                }
                                             }
                                             // Video info
                                             div { class: "p-4",
@@ -289,7 +314,7 @@ pub fn Search() -> Element {
                                                         class: "flex-1 bg-accent-teal hover:bg-opacity-80 text-text-primary py-2 px-3 rounded-lg text-sm transition-colors flex items-center justify-center",
                                                         disabled: loading(),
                                                         onclick: move |_| handle_download(video_clone.clone()),
-                                                        if selected_format() == FormatType::Audio {
+                                                        if matches!(selected_format(), FormatType::Audio(_)) {
                                                             Icon {
                                                                 icon: FaMusic,
                                                                 width: 16,
@@ -0,0 +1,372 @@
+use crate::common::Toaster;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::settings::{
+    get_network_settings, get_site_allowlist, import_cookies, list_cookie_sites, remove_cookies,
+    set_network_settings, set_site_allowlist,
+};
+use crate::server::download::types::{CookieSite, IpVersion, NetworkSettings};
+use dioxus::prelude::*;
+
+#[component]
+pub fn Settings() -> Element {
+    let mut allowlist_input = use_signal(|| String::new());
+    let mut saving = use_signal(|| false);
+    let mut toaster = use_signal(|| None::<Toaster>);
+    // Sites with imported cookies, and the import form
+    let mut cookie_sites = use_signal(Vec::<CookieSite>::new);
+    let mut cookie_site_input = use_signal(String::new);
+    let mut cookie_content = use_signal(String::new);
+    let mut importing = use_signal(|| false);
+    // Proxy, timeout and rate limit settings
+    let mut network = use_signal(NetworkSettings::default);
+    let mut saving_network = use_signal(|| false);
+
+    use_hook(move || {
+        spawn(async move {
+            match get_site_allowlist().await {
+                Ok(domains) => allowlist_input.set(domains.join("\n")),
+                Err(e) => tracing::error!("Failed to load site allowlist: {}", e),
+            }
+            match get_network_settings().await {
+                Ok(settings) => network.set(settings),
+                Err(e) => tracing::error!("Failed to load network settings: {}", e),
+            }
+            match list_cookie_sites().await {
+                Ok(sites) => cookie_sites.set(sites),
+                Err(e) => tracing::error!("Failed to load cookie sites: {}", e),
+            }
+        });
+    });
+
+    let save_allowlist = move |_| {
+        let domains: Vec<String> = allowlist_input()
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        saving.set(true);
+        spawn(async move {
+            match set_site_allowlist(domains).await {
+                Ok(saved) => {
+                    let message = if saved.is_empty() {
+                        "Downloads are allowed from every site".to_string()
+                    } else {
+                        format!("Downloads are limited to {} sites", saved.len())
+                    };
+                    allowlist_input.set(saved.join("\n"));
+                    toaster.set(Some(Toaster::Success(message)));
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to save allowlist: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            saving.set(false);
+        });
+    };
+
+    let save_network = move |_| {
+        saving_network.set(true);
+        spawn(async move {
+            match set_network_settings(network()).await {
+                Ok(saved) => {
+                    network.set(saved);
+                    toaster.set(Some(Toaster::Success("Network settings saved".to_string())));
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to save network settings: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            saving_network.set(false);
+        });
+    };
+
+    // Read a picked cookies.txt into the import form
+    let read_cookie_file = move |e: FormEvent| async move {
+        let Some(files) = e.files() else {
+            return;
+        };
+        if let Some(name) = files.files().into_iter().next() {
+            match files.read_file_to_string(&name).await {
+                Some(content) => cookie_content.set(content),
+                None => toaster.set(Some(Toaster::Error(format!("Couldn't read {}", name)))),
+            }
+        }
+    };
+
+    let import_cookie_file = move |_| {
+        importing.set(true);
+        spawn(async move {
+            match import_cookies(cookie_site_input(), cookie_content()).await {
+                Ok(imported) => {
+                    toaster.set(Some(Toaster::Success(format!(
+                        "Imported {} cookies for {}",
+                        imported.cookie_count, imported.site
+                    ))));
+                    cookie_sites.with_mut(|sites| {
+                        sites.retain(|site| site.site != imported.site);
+                        sites.push(imported);
+                        sites.sort_by(|a, b| a.site.cmp(&b.site));
+                    });
+                    cookie_site_input.set(String::new());
+                    cookie_content.set(String::new());
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to import cookies: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            importing.set(false);
+        });
+    };
+
+    let remove_cookie_site = move |site: String| {
+        spawn(async move {
+            match remove_cookies(site.clone()).await {
+                Ok(()) => {
+                    cookie_sites.with_mut(|sites| sites.retain(|existing| existing.site != site));
+                    toaster.set(Some(Toaster::Success(format!(
+                        "Removed cookies for {}",
+                        site
+                    ))));
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to remove cookies: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+        });
+    };
+
+    let toast = toaster().map(|toast| match toast {
+        Toaster::Success(msg) => ("bg-green-900/40 text-green-300", msg),
+        Toaster::Error(msg) => ("bg-red-900/40 text-red-300", msg),
+        Toaster::Warning(msg) => ("bg-yellow-900/40 text-yellow-300", msg),
+        Toaster::Info(msg) => ("bg-background-hover text-text-secondary", msg),
+    });
+
+    rsx! {
+        div { class: "container mx-auto py-6 px-4",
+            h1 { class: "text-3xl font-bold mb-6 text-text-primary", "Settings" }
+
+            // Toast notification
+            if let Some((toast_class, message)) = toast {
+                div {
+                    class: "mb-4 p-3 rounded-lg text-sm cursor-pointer {toast_class}",
+                    onclick: move |_| toaster.set(None),
+                    "{message}"
+                }
+            }
+
+            // Site allowlist
+            div { class: "bg-background-card rounded-xl border border-border shadow-md p-5 mb-6",
+                h2 { class: "text-xl font-semibold mb-2 text-text-primary", "Allowed sites" }
+                p { class: "mb-4 text-sm text-text-secondary",
+                    "Any site yt-dlp supports can be downloaded from. To limit downloads to sites you trust, list their domains here, one per line. Subdomains are included. Leave the list empty to allow every site."
+                }
+                textarea {
+                    class: "bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5 mb-4 font-mono",
+                    rows: "6",
+                    placeholder: "youtube.com\nvimeo.com",
+                    value: "{allowlist_input}",
+                    oninput: move |e| allowlist_input.set(e.value().clone()),
+                }
+                div { class: "flex justify-end",
+                    button {
+                        class: "px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                        disabled: saving(),
+                        onclick: save_allowlist,
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                }
+            }
+
+            // Network settings
+            div { class: "bg-background-card rounded-xl border border-border shadow-md p-5 mb-6",
+                h2 { class: "text-xl font-semibold mb-2 text-text-primary", "Network" }
+                p { class: "mb-4 text-sm text-text-secondary",
+                    "Used for searches, video info and downloads. Leave a field empty to use the default."
+                }
+                div { class: "grid grid-cols-1 md:grid-cols-2 gap-4 mb-4",
+                    label { class: "text-sm text-text-secondary",
+                        "Proxy"
+                        input {
+                            class: "mt-1 bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                            r#type: "text",
+                            placeholder: "http://proxy:8080 or socks5://proxy:1080",
+                            value: network().proxy.unwrap_or_default(),
+                            oninput: move |e| network.with_mut(|settings| settings.proxy = Some(e.value())),
+                        }
+                    }
+                    label { class: "text-sm text-text-secondary",
+                        "IP version"
+                        select {
+                            class: "mt-1 bg-background border border-border text-text-primary text-sm rounded-lg block w-full p-2.5",
+                            onchange: move |e| {
+                                if let Some(version) = IpVersion::from_name(&e.value()) {
+                                    network.with_mut(|settings| settings.ip_version = version);
+                                }
+                            },
+                            for version in IpVersion::ALL {
+                                option {
+                                    value: version.as_str(),
+                                    selected: network().ip_version == version,
+                                    "{version.label()}"
+                                }
+                            }
+                        }
+                    }
+                    label { class: "text-sm text-text-secondary",
+                        "Source address"
+                        input {
+                            class: "mt-1 bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                            r#type: "text",
+                            placeholder: "Local IP address to connect from",
+                            value: network().source_address.unwrap_or_default(),
+                            oninput: move |e| network.with_mut(|settings| settings.source_address = Some(e.value())),
+                        }
+                    }
+                    label { class: "text-sm text-text-secondary",
+                        "Socket timeout (seconds)"
+                        input {
+                            class: "mt-1 bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                            r#type: "number",
+                            min: "1",
+                            max: "600",
+                            value: "{network().socket_timeout_secs}",
+                            oninput: move |e| {
+                                network
+                                    .with_mut(|settings| {
+                                        settings.socket_timeout_secs = e
+                                            .value()
+                                            .trim()
+                                            .parse()
+                                            .unwrap_or(NetworkSettings::DEFAULT_SOCKET_TIMEOUT);
+                                    })
+                            },
+                        }
+                    }
+                    label { class: "text-sm text-text-secondary",
+                        "Max download speed (KB/s)"
+                        input {
+                            class: "mt-1 bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                            r#type: "number",
+                            min: "1",
+                            placeholder: "No limit",
+                            value: network().rate_limit_kib.map(|rate| rate.to_string()).unwrap_or_default(),
+                            oninput: move |e| network.with_mut(|settings| settings.rate_limit_kib = e.value().trim().parse().ok()),
+                        }
+                    }
+                    label { class: "text-sm text-text-secondary",
+                        "Pause between requests (seconds)"
+                        input {
+                            class: "mt-1 bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                            r#type: "number",
+                            min: "0",
+                            step: "0.5",
+                            placeholder: "No pause",
+                            value: network().sleep_requests_secs.map(|secs| secs.to_string()).unwrap_or_default(),
+                            oninput: move |e| network.with_mut(|settings| settings.sleep_requests_secs = e.value().trim().parse().ok()),
+                        }
+                    }
+                }
+                div { class: "flex justify-end",
+                    button {
+                        class: "px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                        disabled: saving_network(),
+                        onclick: save_network,
+                        if saving_network() { "Saving..." } else { "Save" }
+                    }
+                }
+            }
+
+            // Cookies for signed-in downloads
+            div { class: "bg-background-card rounded-xl border border-border shadow-md p-5 mb-6",
+                h2 { class: "text-xl font-semibold mb-2 text-text-primary", "Cookies" }
+                p { class: "mb-4 text-sm text-text-secondary",
+                    "Age-restricted, members-only and private videos can only be downloaded while signed in. Export the cookies of a signed-in browser session as cookies.txt and import them here. They are only used for the site they were imported for and are stored where only your user account can read them."
+                }
+
+                if cookie_sites().is_empty() {
+                    p { class: "mb-4 text-sm text-text-muted", "No cookies imported yet." }
+                }
+                for cookie_site in cookie_sites() {
+                    CookieSiteRow {
+                        key: "{cookie_site.site}",
+                        cookie_site,
+                        on_remove: remove_cookie_site,
+                    }
+                }
+
+                // Import form
+                div { class: "mt-4 grid gap-3",
+                    input {
+                        class: "bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5",
+                        r#type: "text",
+                        placeholder: "Site, e.g. youtube.com (worked out from the cookies if left empty)",
+                        value: "{cookie_site_input}",
+                        oninput: move |e| cookie_site_input.set(e.value().clone()),
+                    }
+                    input {
+                        class: "text-sm text-text-secondary",
+                        r#type: "file",
+                        accept: ".txt",
+                        onchange: read_cookie_file,
+                    }
+                    textarea {
+                        class: "bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5 font-mono",
+                        rows: "4",
+                        placeholder: "Or paste the contents of cookies.txt",
+                        value: "{cookie_content}",
+                        oninput: move |e| cookie_content.set(e.value().clone()),
+                    }
+                }
+                div { class: "flex justify-end mt-4",
+                    button {
+                        class: "px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                        disabled: importing() || cookie_content().trim().is_empty(),
+                        onclick: import_cookie_file,
+                        if importing() { "Importing..." } else { "Import cookies" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// A site with imported cookies, with a button to remove them
+#[component]
+fn CookieSiteRow(cookie_site: CookieSite, on_remove: EventHandler<String>) -> Element {
+    let site = cookie_site.site.clone();
+
+    rsx! {
+        div { class: "flex items-center justify-between gap-4 mb-2 p-3 rounded-lg bg-background-medium",
+            div {
+                p { class: "text-text-primary font-medium", "{cookie_site.site}" }
+                p { class: "text-sm text-text-muted",
+                    "{cookie_site.cookie_count} cookies · imported {cookie_site.imported_at}"
+                }
+                if cookie_site.expired {
+                    p { class: "text-sm text-accent-rose",
+                        "These cookies have expired. Import them again."
+                    }
+                }
+            }
+            button {
+                class: "px-3 py-1.5 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                onclick: move |_| on_remove.call(site.clone()),
+                "Remove"
+            }
+        }
+    }
+}
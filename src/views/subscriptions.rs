@@ -0,0 +1,262 @@
+use crate::common::Toaster;
+use crate::server::download::error::DownloaderError;
+use crate::server::download::handlers::subscription::{
+    add_subscription, check_subscriptions_now, list_subscriptions, remove_subscription,
+    toggle_subscription,
+};
+use crate::server::download::types::{AudioFormat, SubscriptionEntry};
+use crate::views::download::{FormatType, Quality};
+use dioxus::prelude::*;
+use dioxus_free_icons::{
+    icons::fa_solid_icons::{FaMusic, FaRss, FaVideo},
+    Icon,
+};
+
+#[component]
+pub fn Subscriptions() -> Element {
+    let mut url_input = use_signal(|| String::new());
+    let mut selected_format = use_signal(|| FormatType::Video);
+    let mut selected_quality = use_signal(|| Quality::Highest);
+    let mut entries = use_signal(|| Vec::<SubscriptionEntry>::new());
+    let mut submitting = use_signal(|| false);
+    let mut checking = use_signal(|| false);
+    let mut toaster = use_signal(|| None::<Toaster>);
+
+    let reload = move || {
+        spawn(async move {
+            match list_subscriptions().await {
+                Ok(list) => entries.set(list),
+                Err(e) => tracing::error!("Failed to load subscriptions: {}", e),
+            }
+        });
+    };
+
+    use_hook(move || reload());
+
+    let subscribe = move |_| {
+        let url = url_input().trim().to_string();
+        if url.is_empty() {
+            toaster.set(Some(Toaster::Warning(
+                "Enter a YouTube channel URL".to_string(),
+            )));
+            return;
+        }
+
+        submitting.set(true);
+        spawn(async move {
+            match add_subscription(
+                url,
+                selected_format().to_string(),
+                selected_quality().to_string(),
+            )
+            .await
+            {
+                Ok(entry) => {
+                    toaster.set(Some(Toaster::Success(format!(
+                        "Subscribed to {}. New uploads will be downloaded automatically.",
+                        entry.title
+                    ))));
+                    url_input.set(String::new());
+                    reload();
+                }
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to subscribe: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            submitting.set(false);
+        });
+    };
+
+    let check_now = move |_| {
+        checking.set(true);
+        spawn(async move {
+            match check_subscriptions_now().await {
+                Ok(0) => toaster.set(Some(Toaster::Info("No new uploads".to_string()))),
+                Ok(queued) => toaster.set(Some(Toaster::Success(format!(
+                    "Queued {} new uploads",
+                    queued
+                )))),
+                Err(e) => {
+                    toaster.set(Some(Toaster::Error(format!(
+                        "Failed to check subscriptions: {}",
+                        DownloaderError::from(e).message()
+                    ))));
+                }
+            }
+            reload();
+            checking.set(false);
+        });
+    };
+
+    let toast = toaster().map(|toast| match toast {
+        Toaster::Success(msg) => ("bg-green-900/40 text-green-300", msg),
+        Toaster::Error(msg) => ("bg-red-900/40 text-red-300", msg),
+        Toaster::Warning(msg) => ("bg-yellow-900/40 text-yellow-300", msg),
+        Toaster::Info(msg) => ("bg-background-hover text-text-secondary", msg),
+    });
+
+    rsx! {
+        div { class: "container mx-auto py-6 px-4",
+            h1 { class: "text-3xl font-bold mb-4 text-text-primary", "Subscriptions" }
+            p { class: "mb-6 text-text-secondary",
+                "Follow channels and their new uploads are queued automatically. Channels are checked every hour."
+            }
+
+            // Toast notification
+            if let Some((toast_class, message)) = toast {
+                div {
+                    class: "mb-4 p-3 rounded-lg text-sm cursor-pointer {toast_class}",
+                    onclick: move |_| toaster.set(None),
+                    "{message}"
+                }
+            }
+
+            // New subscription form
+            div { class: "bg-background-card rounded-xl border border-border shadow-md p-5 mb-6",
+                input {
+                    class: "bg-background border border-border text-text-primary text-sm rounded-lg focus:ring-accent-teal focus:border-accent-teal block w-full p-2.5 mb-4",
+                    r#type: "text",
+                    placeholder: "https://www.youtube.com/@channel",
+                    value: "{url_input}",
+                    oninput: move |e| url_input.set(e.value().clone()),
+                }
+
+                div { class: "flex flex-wrap items-center gap-4",
+                    // Format selection
+                    div { class: "flex rounded-lg overflow-hidden border border-border",
+                        button {
+                            class: "flex items-center px-4 py-2 text-sm",
+                            class: if selected_format() == FormatType::Video { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                            onclick: move |_| selected_format.set(FormatType::Video),
+                            Icon { icon: FaVideo, width: 14, height: 14, class: "mr-2" }
+                            "Video"
+                        }
+                        button {
+                            class: "flex items-center px-4 py-2 text-sm",
+                            class: if matches!(selected_format(), FormatType::Audio(_)) { "bg-accent-teal text-white" } else { "text-text-muted hover:bg-background-hover" },
+                            onclick: move |_| selected_format.set(FormatType::Audio(AudioFormat::default())),
+                            Icon { icon: FaMusic, width: 14, height: 14, class: "mr-2" }
+                            "Audio"
+                        }
+                    }
+
+                    // Quality selection
+                    select {
+                        class: "bg-background border border-border text-text-primary text-sm rounded-lg p-2",
+                        onchange: move |e| {
+                            selected_quality
+                                .set(
+                                    match e.value().as_str() {
+                                        "medium" => Quality::Medium,
+                                        "lowest" => Quality::Lowest,
+                                        _ => Quality::Highest,
+                                    },
+                                )
+                        },
+                        option { value: "highest", selected: selected_quality() == Quality::Highest, "Highest quality" }
+                        option { value: "medium", selected: selected_quality() == Quality::Medium, "Medium quality" }
+                        option { value: "lowest", selected: selected_quality() == Quality::Lowest, "Lowest quality" }
+                    }
+
+                    button {
+                        class: "flex items-center ml-auto px-5 py-2 rounded-lg bg-primary-600 text-text-primary hover:bg-primary-500 disabled:opacity-50",
+                        disabled: submitting(),
+                        onclick: subscribe,
+                        Icon { icon: FaRss, width: 14, height: 14, class: "mr-2" }
+                        if submitting() { "Subscribing..." } else { "Subscribe" }
+                    }
+                }
+            }
+
+            div { class: "flex items-center justify-between mb-4",
+                span { class: "text-sm text-text-muted", "{entries().len()} channels" }
+                button {
+                    class: "px-4 py-2 text-sm rounded-lg border border-border text-text-secondary hover:bg-background-hover disabled:opacity-50",
+                    disabled: checking() || entries().is_empty(),
+                    onclick: check_now,
+                    if checking() { "Checking..." } else { "Check now" }
+                }
+            }
+
+            // Subscription list
+            if entries().is_empty() {
+                div { class: "text-center py-12 bg-background-card rounded-xl border border-border text-text-secondary",
+                    "You haven't subscribed to any channels yet"
+                }
+            } else {
+                div { class: "space-y-3",
+                    for entry in entries() {
+                        SubscriptionRow {
+                            key: "{entry.id}",
+                            entry: entry.clone(),
+                            on_change: move |_| reload(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SubscriptionRow(entry: SubscriptionEntry, on_change: EventHandler<()>) -> Element {
+    let id = entry.id;
+    let enabled = entry.enabled;
+
+    let toggle = move |_| {
+        spawn(async move {
+            match toggle_subscription(id, !enabled).await {
+                Ok(_) => on_change.call(()),
+                Err(e) => tracing::error!("Failed to update subscription {}: {}", id, e),
+            }
+        });
+    };
+    let remove = move |_| {
+        spawn(async move {
+            match remove_subscription(id).await {
+                Ok(_) => on_change.call(()),
+                Err(e) => tracing::error!("Failed to remove subscription {}: {}", id, e),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "bg-background-card rounded-lg border border-border p-4",
+            div { class: "flex items-center justify-between gap-4",
+                div { class: "min-w-0",
+                    p { class: "font-medium text-text-primary truncate", "{entry.title}" }
+                    p { class: "text-xs text-text-muted mt-1 truncate",
+                        "{entry.format_type} · {entry.quality} · checked {entry.last_checked} · {entry.url}"
+                    }
+                }
+                div { class: "flex shrink-0 items-center gap-2",
+                    button {
+                        class: "px-3 py-1 text-xs rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                        onclick: toggle,
+                        if enabled { "Pause" } else { "Resume" }
+                    }
+                    button {
+                        class: "px-3 py-1 text-xs rounded-lg border border-border text-text-secondary hover:bg-background-hover",
+                        onclick: remove,
+                        "Unsubscribe"
+                    }
+                    if enabled {
+                        span { class: "px-3 py-1 rounded-full text-xs font-medium bg-accent-teal/20 text-accent-teal",
+                            "Active"
+                        }
+                    } else {
+                        span { class: "px-3 py-1 rounded-full text-xs font-medium bg-background-hover text-text-muted",
+                            "Paused"
+                        }
+                    }
+                }
+            }
+            if let Some(error) = entry.last_error.clone() {
+                p { class: "text-xs text-red-300 mt-2", "Last check failed: {error}" }
+            }
+        }
+    }
+}